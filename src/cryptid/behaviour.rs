@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use bevy::prelude::Vec3;
+use rand::{rngs::SmallRng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::nav_mesh::flat_distance;
+
+/// Player closer than this gets chased instead of stalked
+pub const CHASE_DISTANCE: f32 = 4.;
+/// Distance the cryptid keeps while stalking
+pub const STALK_DISTANCE: f32 = 8.;
+/// Stalking player further than this gets away
+pub const LOSE_DISTANCE: f32 = 25.;
+/// Close enough to a point to count as having reached it
+pub const REACHED_DISTANCE: f32 = 0.5;
+
+/// What the cryptid is doing
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CryptidState {
+    /// motionless until something wakes it up
+    Dormant,
+    /// wanders between random points on the nav mesh
+    Roam,
+    /// walks to where something was heard or the player was last seen
+    Investigate(Vec3),
+    /// follows the player while keeping its distance
+    Stalk,
+    /// runs at the player
+    Chase,
+    /// runs away from the flashlight
+    Flee,
+}
+
+/// What the cryptid perceived this frame
+#[derive(Debug, Default)]
+pub struct Senses {
+    /// position of the player, if seen
+    pub player: Option<Vec3>,
+    /// where the player was last seen
+    pub last_seen: Option<Vec3>,
+    /// position of the flashlight, if the cryptid stands in its cone
+    pub light: Option<Vec3>,
+    /// position of the noise heard, if any
+    pub heard: Option<Vec3>,
+}
+
+impl CryptidState {
+    /// Movement speed in the state
+    pub fn speed(&self) -> f32 {
+        match self {
+            CryptidState::Dormant => 0.,
+            CryptidState::Roam => 1.5,
+            CryptidState::Investigate(_) => 2.5,
+            CryptidState::Stalk => 2.,
+            CryptidState::Chase => 6.,
+            CryptidState::Flee => 7.,
+        }
+    }
+
+    /// How long the state lasts before giving up
+    pub fn duration(&self, rng: &mut SmallRng) -> Duration {
+        Duration::from_secs_f32(match self {
+            CryptidState::Dormant => rng.gen_range(5. ..15.),
+            CryptidState::Roam => rng.gen_range(10. ..20.),
+            CryptidState::Investigate(_) => 10.,
+            CryptidState::Stalk => rng.gen_range(8. ..16.),
+            CryptidState::Chase => 8.,
+            CryptidState::Flee => 3.,
+        })
+    }
+
+    /// State the cryptid switches to once the player is spotted from `position`
+    fn spotted(position: Vec3, player: Vec3) -> CryptidState {
+        match position.distance(player) < CHASE_DISTANCE {
+            true => CryptidState::Chase,
+            false => CryptidState::Stalk,
+        }
+    }
+
+    /// Next state given what the cryptid perceived at `position`. `timed_out` is whether the
+    /// current state lasted its [`CryptidState::duration`]
+    pub fn next(&self, senses: &Senses, position: Vec3, timed_out: bool) -> CryptidState {
+        //the flashlight always scares it off
+        if senses.light.is_some() {
+            return CryptidState::Flee;
+        }
+
+        let lost_player = || match senses.last_seen {
+            Some(last_seen) => CryptidState::Investigate(last_seen),
+            None => CryptidState::Roam,
+        };
+
+        match (self, senses.player) {
+            (CryptidState::Flee, _) if !timed_out => CryptidState::Flee,
+            (CryptidState::Flee, Some(_)) => CryptidState::Stalk,
+            (CryptidState::Flee, None) => CryptidState::Roam,
+
+            (CryptidState::Chase, Some(player)) => match position.distance(player) {
+                dist if dist > STALK_DISTANCE * 1.5 => CryptidState::Stalk,
+                _ => CryptidState::Chase,
+            },
+            (CryptidState::Chase, None) => lost_player(),
+
+            (CryptidState::Stalk, Some(player)) => {
+                match (position.distance(player), timed_out) {
+                    //done stalking, goes for it
+                    (dist, _) if dist < CHASE_DISTANCE => CryptidState::Chase,
+                    (_, true) => CryptidState::Chase,
+                    (dist, _) if dist > LOSE_DISTANCE => lost_player(),
+                    _ => CryptidState::Stalk,
+                }
+            }
+            (CryptidState::Stalk, None) => lost_player(),
+
+            (_, Some(player)) => CryptidState::spotted(position, player),
+
+            (_, None) => match (self, senses.heard) {
+                (_, Some(noise)) => CryptidState::Investigate(noise),
+                (CryptidState::Investigate(point), None) => {
+                    match timed_out || flat_distance(position, *point) < REACHED_DISTANCE {
+                        true => CryptidState::Roam,
+                        false => *self,
+                    }
+                }
+                (CryptidState::Roam, None) if timed_out => CryptidState::Dormant,
+                (CryptidState::Dormant, None) if timed_out => CryptidState::Roam,
+                _ => *self,
+            },
+        }
+    }
+
+    /// Where the cryptid walks to in the state; None when it stands still or picks its own point
+    pub fn goal(&self, position: Vec3, senses: &Senses) -> Option<Vec3> {
+        match (self, senses.player, senses.light.or(senses.last_seen)) {
+            (CryptidState::Investigate(point), ..) => Some(*point),
+            (CryptidState::Chase, Some(player), _) => Some(player),
+            (CryptidState::Stalk, Some(player), _) => {
+                let away = (position - player).normalize_or_zero();
+                Some(player + away * STALK_DISTANCE)
+            }
+            (CryptidState::Flee, _, Some(threat)) => {
+                let away = (position - threat).normalize_or_zero();
+                Some(position + away * STALK_DISTANCE)
+            }
+            _ => None,
+        }
+    }
+}
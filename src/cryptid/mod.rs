@@ -0,0 +1,360 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use bevy::{
+    log::info,
+    prelude::{
+        Added, App, AssetServer, Commands, Component, ComputedVisibility, DetectChanges, Entity,
+        EventReader, EventWriter, GlobalTransform, IntoSystemConfigs, Plugin, PostUpdate, Quat,
+        Query, Res, SpatialBundle, SpotLight, Transform, Update, Vec3, With,
+    },
+    time::{Time, Timer, TimerMode},
+    transform::TransformSystem,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    humanoid::{
+        asset::{humanoid_path, HumanoidSpawnedEvent, SpawnHumanoid},
+        ragdoll::{RagdollEvent, Standing},
+        Humanoid,
+    },
+    noise::{update_hearing, Footsteps, Hearing},
+    perception::{spot_light_level, Perception, VisionSensor},
+    player::{
+        ik::{initialize_leg_ik, LegInitializeEvent},
+        inventory::Flashlight,
+        movement::walk_path,
+        Controllable,
+    },
+    scene::nav_mesh::{flat_distance, NavMesh},
+};
+
+use self::behaviour::{CryptidState, Senses, REACHED_DISTANCE};
+
+pub mod behaviour;
+
+const CRYPTID_HUMANOID: &str = "character";
+
+/// Height of the cryptid's eyes above its feet
+const EYE_HEIGHT: f32 = 1.6;
+/// Quietest sound level the cryptid notices
+const HEARING_THRESHOLD: f32 = 0.15;
+/// Seconds between path updates towards a moving goal
+const REPATH_TIME: f32 = 0.5;
+const TURN_SPEED: f32 = 5.;
+/// Distance the cryptid knocks the player down from while chasing
+const STRIKE_DISTANCE: f32 = 1.2;
+/// Impulse of the cryptid's strike
+const STRIKE_IMPULSE: f32 = 150.;
+/// Seconds the player stays down after a strike
+const STAGGER_TIME: f32 = 2.5;
+
+#[derive(Component)]
+pub struct Cryptid;
+
+/// Where the cryptid appears, placed by a scene. Ignored while a cryptid is already roaming
+#[derive(Component)]
+pub struct CryptidSpawn;
+
+/// Behaviour state & memory of a [`Cryptid`]
+#[derive(Component, Debug)]
+pub struct CryptidBrain {
+    pub state: CryptidState,
+    /// time left before the state times out
+    timer: Timer,
+    repath: Timer,
+    pub last_seen: Option<Vec3>,
+    /// where the cryptid is walking to
+    goal: Option<Vec3>,
+    /// nav mesh path to `goal`, planned when empty
+    path: VecDeque<Vec3>,
+    rng: SmallRng,
+}
+
+impl CryptidBrain {
+    pub fn new(rng: SmallRng) -> Self {
+        let mut brain = Self {
+            state: CryptidState::Dormant,
+            timer: Timer::default(),
+            repath: Timer::from_seconds(REPATH_TIME, TimerMode::Repeating),
+            last_seen: None,
+            goal: None,
+            path: VecDeque::new(),
+            rng,
+        };
+        brain.timer = Timer::new(
+            CryptidState::Dormant.duration(&mut brain.rng),
+            TimerMode::Once,
+        );
+        brain
+    }
+
+    /// Seconds left before the state times out
+    pub fn time_left(&self) -> f32 {
+        self.timer.remaining_secs()
+    }
+
+    /// Puts the brain back in a saved `state`, replanning its path
+    pub fn restore(&mut self, state: CryptidState, time_left: f32, last_seen: Option<Vec3>) {
+        self.state = state;
+        self.timer = Timer::from_seconds(time_left, TimerMode::Once);
+        self.last_seen = last_seen;
+        self.goal = None;
+        self.path.clear();
+    }
+
+    fn enter(&mut self, state: CryptidState) {
+        if state == self.state {
+            return;
+        }
+
+        info!("cryptid: {:?} -> {state:?}", self.state);
+
+        self.state = state;
+        self.timer = Timer::new(state.duration(&mut self.rng), TimerMode::Once);
+        self.goal = None;
+        self.path.clear();
+    }
+}
+
+/// Spawns the cryptid at the first spawn point placed, once its transform is propagated
+fn create_cryptid(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    spawn_query: Query<&GlobalTransform, Added<CryptidSpawn>>,
+    cryptid_query: Query<(), With<Cryptid>>,
+) {
+    if !cryptid_query.is_empty() {
+        return;
+    }
+    let Some(spawn) = spawn_query.iter().next() else {
+        return;
+    };
+
+    commands.spawn((
+        SpatialBundle::from_transform(spawn.compute_transform()),
+        //the rig is spawned once its asset is loaded
+        SpawnHumanoid(asset_server.load(humanoid_path(CRYPTID_HUMANOID))),
+        Cryptid,
+        CryptidBrain::new(SmallRng::from_entropy()),
+        VisionSensor::from_angle(PI / 3.)
+            .with_range(20.)
+            .with_darkness(6., 0.2),
+        Hearing::new(HEARING_THRESHOLD),
+        Footsteps::new(0.5, 8., 1.2),
+    ));
+}
+
+/// Sets up procedural walking on the cryptid's rig once spawned
+fn set_up_cryptid_humanoid(
+    mut spawned_event: EventReader<HumanoidSpawnedEvent>,
+    cryptid_query: Query<(), With<Cryptid>>,
+    mut leg_set_up_event: EventWriter<LegInitializeEvent>,
+) {
+    for HumanoidSpawnedEvent(cryptid) in spawned_event.iter() {
+        if cryptid_query.contains(*cryptid) {
+            leg_set_up_event.send(LegInitializeEvent(*cryptid));
+        }
+    }
+}
+
+/// Gathers what each cryptid sees & hears & updates its behaviour state
+fn update_cryptid_brain(
+    time: Res<Time>,
+    player_query: Query<(&GlobalTransform, Option<&Humanoid>), With<Controllable>>,
+    transform_query: Query<&GlobalTransform>,
+    flashlight_query: Query<(&SpotLight, &GlobalTransform, &ComputedVisibility), With<Flashlight>>,
+    mut cryptid_query: Query<
+        (&mut CryptidBrain, &VisionSensor, &Hearing, &GlobalTransform),
+        With<Cryptid>,
+    >,
+    mut perception: Perception,
+) {
+    //(feet, head) of the player
+    let player = player_query.iter().next().map(|(transform, humanoid)| {
+        let head = humanoid
+            .and_then(|humanoid| transform_query.get(humanoid.head).ok())
+            .map_or(transform.translation() + Vec3::Y * EYE_HEIGHT, |head| {
+                head.translation()
+            });
+
+        (transform.translation(), head)
+    });
+
+    for (mut brain, sensor, hearing, transform) in &mut cryptid_query {
+        let position = transform.translation();
+
+        let eye = position + Vec3::Y * EYE_HEIGHT;
+
+        let senses = Senses {
+            player: player
+                .filter(|(_, head)| perception.sees(sensor, eye, transform.forward(), *head, None))
+                .map(|(feet, _)| feet),
+            last_seen: brain.last_seen,
+            //switched off, flat or flickering lights are hidden
+            light: flashlight_query
+                .iter()
+                .filter(|(light, _, visibility)| visibility.is_visible() && light.intensity > 0.)
+                .find(|(light, light_transform, _)| {
+                    spot_light_level(light, light_transform, eye) > 0.
+                        && perception.line_of_sight(light_transform.translation(), eye, None)
+                })
+                .map(|(_, light_transform, _)| light_transform.translation()),
+            heard: hearing.heard.map(|heard| heard.position),
+        };
+
+        if senses.player.is_some() {
+            brain.last_seen = senses.player;
+        }
+
+        let timed_out = brain.timer.tick(time.delta()).finished();
+        let next = brain.state.next(&senses, position, timed_out);
+
+        //reached where the player was last seen without finding them
+        if let CryptidState::Investigate(point) = brain.state {
+            if Some(point) == brain.last_seen && next != brain.state {
+                brain.last_seen = None;
+            }
+        }
+
+        brain.enter(next);
+
+        //keep heading towards moving goals
+        let repath = brain.repath.tick(time.delta()).just_finished();
+        if let Some(goal) = brain.state.goal(position, &senses) {
+            if brain.goal.is_none() || repath {
+                brain.goal = Some(goal);
+                brain.path.clear();
+            }
+        }
+    }
+}
+
+/// Walks each cryptid along a nav mesh path to its goal & turns it to face where it walks
+fn move_cryptid(
+    time: Res<Time>,
+    nav_mesh: Res<NavMesh>,
+    mut cryptid_query: Query<(&mut CryptidBrain, &mut Transform), (With<Cryptid>, Standing)>,
+) {
+    if nav_mesh.is_empty() {
+        return;
+    }
+
+    for (mut brain, mut transform) in &mut cryptid_query {
+        let brain = brain.as_mut();
+
+        //doors opened or shut, the path may be blocked or shorter
+        if nav_mesh.is_changed() {
+            brain.path.clear();
+        }
+
+        if brain.state == CryptidState::Dormant {
+            continue;
+        }
+
+        //wander somewhere new
+        if brain.goal.is_none() && brain.state == CryptidState::Roam {
+            let triangle = brain.rng.gen_range(0..nav_mesh.len());
+            brain.goal = Some(nav_mesh.centroid(triangle));
+        }
+
+        let Some(goal) = brain.goal else {
+            continue;
+        };
+
+        if flat_distance(goal, transform.translation) < REACHED_DISTANCE {
+            brain.goal = None;
+            brain.path.clear();
+            continue;
+        }
+
+        if brain.path.is_empty() {
+            brain.path = match nav_mesh.find_path(transform.translation, goal) {
+                Some(path) => path.into_iter().skip(1).collect(),
+                //unreachable, give up on it
+                None => {
+                    brain.goal = None;
+                    continue;
+                }
+            };
+        }
+
+        let heading = walk_path(
+            &nav_mesh,
+            &mut transform.translation,
+            &mut brain.path,
+            time.delta_seconds() * brain.state.speed(),
+        );
+
+        //arrived
+        if brain.path.is_empty() {
+            brain.goal = None;
+        }
+
+        if heading != Vec3::ZERO {
+            let goal = Quat::from_rotation_arc(Vec3::NEG_Z, heading);
+            transform.rotation = transform
+                .rotation
+                .slerp(goal, (time.delta_seconds() * TURN_SPEED).min(1.));
+        }
+    }
+}
+
+/// Knocks the player down when a chase catches up with them, then backs off to stalk
+fn strike_player(
+    mut cryptid_query: Query<(&mut CryptidBrain, &GlobalTransform), With<Cryptid>>,
+    player_query: Query<(Entity, &GlobalTransform), (With<Controllable>, Standing)>,
+    mut ragdoll_event: EventWriter<RagdollEvent>,
+) {
+    let Some((player, player_transform)) = player_query.iter().next() else {
+        return;
+    };
+    let player_position = player_transform.translation();
+
+    for (mut brain, transform) in &mut cryptid_query {
+        let position = transform.translation();
+
+        if brain.state != CryptidState::Chase
+            || flat_distance(position, player_position) > STRIKE_DISTANCE
+        {
+            continue;
+        }
+
+        let push = Vec3::new(
+            player_position.x - position.x,
+            0.,
+            player_position.z - position.z,
+        )
+        .normalize_or_zero();
+
+        ragdoll_event.send(RagdollEvent {
+            humanoid: player,
+            impulse: (push + Vec3::Y * 0.3) * STRIKE_IMPULSE,
+            recover_after: Some(STAGGER_TIME),
+        });
+        brain.enter(CryptidState::Stalk);
+
+        //one strike at a time
+        break;
+    }
+}
+
+pub struct CryptidPlugin;
+
+impl Plugin for CryptidPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            create_cryptid.after(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            Update,
+            (
+                set_up_cryptid_humanoid.before(initialize_leg_ik),
+                (update_cryptid_brain, move_cryptid, strike_player)
+                    .chain()
+                    .after(update_hearing),
+            ),
+        );
+    }
+}
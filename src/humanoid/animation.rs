@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    animation::{animation_player, AnimationClip, EntityPath, Keyframes, VariableCurve},
+    prelude::{
+        App, Assets, Component, Entity, GlobalTransform, Handle, IntoSystemConfigs, Plugin,
+        PostUpdate, Quat, Query, Res, Transform, Update, Vec3,
+    },
+    time::Time,
+};
+
+use crate::{
+    ik::IKSystem,
+    player::{
+        fear::Fear,
+        movement::{Direction, MOVE_SPEED},
+        Controllable,
+    },
+    scene::nav_mesh::flat_distance,
+};
+
+/// Clips from a humanoid's glTF & the joints they animate
+#[derive(Component, Debug, Default)]
+pub struct HumanoidAnimations {
+    pub clips: HashMap<String, Handle<AnimationClip>>,
+    /// joints below the rig root & their paths from it, the clips' curves are keyed by path
+    bones: Vec<(Entity, EntityPath)>,
+}
+
+impl HumanoidAnimations {
+    pub fn new(
+        clips: HashMap<String, Handle<AnimationClip>>,
+        bones: Vec<(Entity, EntityPath)>,
+    ) -> Self {
+        Self { clips, bones }
+    }
+}
+
+/// Bones driven by a procedural layer (head look, body turn, ...), clips leave them alone
+#[derive(Component, Debug, Default)]
+pub struct BoneOverrides(pub HashSet<Entity>);
+
+/// Blend tree of idle, walk & run clips weighted by movement speed
+#[derive(Component, Debug, Clone)]
+pub struct LocomotionBlend {
+    pub idle: String,
+    pub walk: String,
+    pub run: String,
+    /// speed the walk clip is fully weighted at, above 0
+    pub walk_speed: f32,
+    /// speed the run clip is fully weighted at, above `walk_speed`
+    pub run_speed: f32,
+    pub speed: f32,
+    /// how far through a gait cycle the clips are, 0 to 1
+    phase: f32,
+    /// where the humanoid stood last frame, to measure its ground speed
+    last_position: Option<Vec3>,
+}
+
+impl Default for LocomotionBlend {
+    fn default() -> Self {
+        Self {
+            idle: "idle".into(),
+            walk: "walk".into(),
+            run: "run".into(),
+            walk_speed: 1.5,
+            run_speed: MOVE_SPEED,
+            speed: 0.,
+            phase: 0.,
+            last_position: None,
+        }
+    }
+}
+
+impl LocomotionBlend {
+    /// Weights of the idle, walk & run clips at the current speed
+    pub fn weights(&self) -> [f32; 3] {
+        let speed = self.speed.max(0.);
+
+        match speed < self.walk_speed {
+            true => {
+                let t = speed / self.walk_speed;
+                [1. - t, t, 0.]
+            }
+            false => {
+                let t = ((speed - self.walk_speed) / (self.run_speed - self.walk_speed)).min(1.);
+                [0., 1. - t, t]
+            }
+        }
+    }
+}
+
+enum Sample {
+    Translation(Vec3),
+    Rotation(Quat),
+    Scale(Vec3),
+}
+
+/// Value of `curve` at `time`, linearly interpolated between keyframes
+fn sample_curve(curve: &VariableCurve, time: f32) -> Option<Sample> {
+    let timestamps = &curve.keyframe_timestamps;
+
+    let next = timestamps.partition_point(|timestamp| *timestamp <= time);
+    let (from, to, t) = match next {
+        0 => (0, 0, 0.),
+        next if next >= timestamps.len() => (next - 1, next - 1, 0.),
+        next => {
+            let (start, end) = (timestamps[next - 1], timestamps[next]);
+            (next - 1, next, (time - start) / (end - start))
+        }
+    };
+
+    match &curve.keyframes {
+        Keyframes::Translation(keys) => {
+            Some(Sample::Translation(keys.get(from)?.lerp(*keys.get(to)?, t)))
+        }
+        Keyframes::Rotation(keys) => {
+            Some(Sample::Rotation(keys.get(from)?.slerp(*keys.get(to)?, t)))
+        }
+        Keyframes::Scale(keys) => Some(Sample::Scale(keys.get(from)?.lerp(*keys.get(to)?, t))),
+        //morph targets aren't used by rigs
+        Keyframes::Weights(_) => None,
+    }
+}
+
+/// Weighted blend of the samples of one bone
+#[derive(Default)]
+struct BonePose {
+    translation: Option<(Vec3, f32)>,
+    rotation: Option<(Quat, f32)>,
+    scale: Option<(Vec3, f32)>,
+}
+
+impl BonePose {
+    fn add(&mut self, sample: Sample, weight: f32) {
+        match sample {
+            Sample::Translation(translation) => {
+                let (sum, total) = self.translation.unwrap_or((Vec3::ZERO, 0.));
+                self.translation = Some((sum + translation * weight, total + weight));
+            }
+            Sample::Scale(scale) => {
+                let (sum, total) = self.scale.unwrap_or((Vec3::ZERO, 0.));
+                self.scale = Some((sum + scale * weight, total + weight));
+            }
+            Sample::Rotation(rotation) => {
+                self.rotation = Some(match self.rotation {
+                    Some((blended, total)) => (
+                        blended.slerp(rotation, weight / (total + weight)),
+                        total + weight,
+                    ),
+                    None => (rotation, weight),
+                });
+            }
+        }
+    }
+
+    fn apply(&self, transform: &mut Transform) {
+        if let Some((sum, total)) = self.translation {
+            transform.translation = sum / total;
+        }
+        if let Some((rotation, _)) = self.rotation {
+            transform.rotation = rotation;
+        }
+        if let Some((sum, total)) = self.scale {
+            transform.scale = sum / total;
+        }
+    }
+}
+
+/// Humanoids with a blend, the [`Controllable`] one steered by its [`Direction`]
+type Walkers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut LocomotionBlend,
+        &'static GlobalTransform,
+        Option<&'static Direction>,
+        Option<&'static Controllable>,
+    ),
+>;
+
+/// Blends the player by its input slowed by [`Fear`] & every other humanoid by its ground speed
+fn update_locomotion_speed(time: Res<Time>, fear: Res<Fear>, mut blend_query: Walkers) {
+    for (mut blend, transform, direction, controllable) in &mut blend_query {
+        let position = transform.translation();
+        let last_position = blend.last_position.replace(position);
+
+        blend.speed = match (direction, controllable) {
+            (Some(direction), Some(_)) => direction.0.length().min(1.) * MOVE_SPEED * fear.speed(),
+            _ => match (last_position, time.delta_seconds()) {
+                (Some(last_position), delta) if delta > 0. => {
+                    flat_distance(last_position, position) / delta
+                }
+                _ => 0.,
+            },
+        };
+    }
+}
+
+/// Poses every humanoid from its blended locomotion clips, skipping overridden bones
+fn play_locomotion(
+    time: Res<Time>,
+    clips: Res<Assets<AnimationClip>>,
+    mut humanoid_query: Query<(
+        &HumanoidAnimations,
+        &mut LocomotionBlend,
+        Option<&BoneOverrides>,
+    )>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    for (animations, mut blend, overrides) in &mut humanoid_query {
+        let layers: Vec<(&AnimationClip, f32)> = [&blend.idle, &blend.walk, &blend.run]
+            .into_iter()
+            .zip(blend.weights())
+            .filter(|(_, weight)| *weight > 0.)
+            .filter_map(|(name, weight)| {
+                let clip = clips.get(animations.clips.get(name)?)?;
+                Some((clip, weight))
+            })
+            .collect();
+
+        let total: f32 = layers.iter().map(|(_, weight)| weight).sum();
+        if total <= 0. {
+            continue;
+        }
+
+        //the clips stay in step by sharing a cycle as long as their weighted durations
+        let duration = layers
+            .iter()
+            .map(|(clip, weight)| clip.duration() * weight)
+            .sum::<f32>()
+            / total;
+        if duration > 0. {
+            blend.phase = (blend.phase + time.delta_seconds() / duration).fract();
+        }
+
+        for (entity, path) in &animations.bones {
+            if overrides.is_some_and(|overrides| overrides.0.contains(entity)) {
+                continue;
+            }
+
+            let mut pose = BonePose::default();
+
+            for (clip, weight) in &layers {
+                let Some(curves) = clip.get_curves_by_path(path) else {
+                    continue;
+                };
+
+                for curve in curves {
+                    if let Some(sample) = sample_curve(curve, blend.phase * clip.duration()) {
+                        pose.add(sample, *weight);
+                    }
+                }
+            }
+
+            if let Ok(mut transform) = transform_query.get_mut(*entity) {
+                pose.apply(&mut transform);
+            }
+        }
+    }
+}
+
+pub struct HumanoidAnimationPlugin;
+
+impl Plugin for HumanoidAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_locomotion_speed)
+            .add_systems(
+                PostUpdate,
+                play_locomotion.after(animation_player).before(IKSystem),
+            );
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::{
+    animation::{AnimationClip, EntityPath},
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    core::Name,
+    log::error,
+    prelude::{
+        AssetServer, Assets, BuildChildren, Commands, Component, ComputedVisibility, Entity, Event,
+        EventWriter, GlobalTransform, Handle, Mat4, Mesh, Query, Res, ResMut, StandardMaterial,
+        Transform, Visibility,
+    },
+    reflect::{TypePath, TypeUuid},
+    render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use super::{
+    animation::{HumanoidAnimations, LocomotionBlend},
+    convert_transform,
+    rig::{HumanoidBone, HumanoidLoadError, RigBones, RigProfile},
+    Humanoid,
+};
+
+/// Character made of a rigged glTF. Loaded from `characters/{name}.humanoid.ron`
+#[derive(Deserialize, Debug, Clone)]
+pub struct HumanoidDescription {
+    /// asset path of the glTF
+    pub gltf: String,
+    /// asset path of the rig profile naming its bones, the bundled one when missing
+    #[serde(default)]
+    pub rig: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HumanoidNode {
+    pub name: Name,
+    pub transform: Transform,
+    pub mesh: Option<Handle<Mesh>>,
+    pub skin: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HumanoidSkin {
+    /// node of every joint
+    pub joints: Vec<usize>,
+    pub inverse_bindposes: Handle<SkinnedMeshInverseBindposes>,
+}
+
+/// Rig read from a humanoid's glTF, ready to spawn
+#[derive(TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "0b6f2f0e-8f8a-4c55-bd0e-5a3e1d9c7b21"]
+pub struct HumanoidAsset {
+    pub nodes: Vec<HumanoidNode>,
+    pub skins: Vec<HumanoidSkin>,
+    /// node the rig hangs from
+    pub root: usize,
+    /// node of every bone found
+    pub bones: HashMap<HumanoidBone, usize>,
+    pub clips: HashMap<String, Handle<AnimationClip>>,
+}
+
+/// Name of a glTF node, matching the names Bevy's glTF loader keys animation curves by
+fn node_name(node: &gltf::Node) -> Name {
+    Name::new(
+        node.name()
+            .map_or_else(|| format!("GltfNode{}", node.index()), str::to_string),
+    )
+}
+
+/// Asset path of `uri` relative to the asset at `path`, '/' separated on every platform
+fn relative_path(path: &str, uri: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((parent, _)) => format!("{parent}/{uri}"),
+        None => uri.to_string(),
+    }
+}
+
+/// Reads every buffer of the glTF at `path`: the binary chunk, embedded data URIs & files next
+/// to it
+async fn load_buffers(
+    gltf: &gltf::Gltf,
+    path: &str,
+    load_context: &LoadContext<'_>,
+) -> Result<Vec<Vec<u8>>, bevy::asset::Error> {
+    let mut buffers = Vec::new();
+
+    for buffer in gltf.buffers() {
+        let data = match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or_else(|| bevy::asset::Error::msg("the glTF binary chunk is missing"))?,
+            gltf::buffer::Source::Uri(uri) => match uri.strip_prefix("data:") {
+                Some(data) => {
+                    let (_, encoded) = data
+                        .split_once(";base64,")
+                        .ok_or_else(|| bevy::asset::Error::msg("only base64 data URIs work"))?;
+
+                    base64::decode(encoded)?
+                }
+                None => {
+                    load_context
+                        .read_asset_bytes(relative_path(path, uri))
+                        .await?
+                }
+            },
+        };
+
+        buffers.push(data);
+    }
+
+    Ok(buffers)
+}
+
+impl HumanoidAsset {
+    /// Reads the rig of `gltf`, registering its inverse bindposes as labeled assets &
+    /// referencing its meshes & clips loaded from `path`
+    fn from_gltf(
+        gltf: &gltf::Gltf,
+        buffers: &[Vec<u8>],
+        path: &str,
+        rig_profile: &RigProfile,
+        load_context: &mut LoadContext,
+    ) -> Result<Self, HumanoidLoadError> {
+        let missing = rig_profile.missing(gltf.nodes().filter_map(|node| node.name()));
+        if !missing.is_empty() {
+            return Err(HumanoidLoadError::MissingBones(missing));
+        }
+
+        let root = gltf
+            .scenes()
+            .next()
+            .and_then(|scene| scene.nodes().next())
+            .ok_or(HumanoidLoadError::NoScene)?
+            .index();
+
+        let nodes = gltf
+            .nodes()
+            .map(|node| HumanoidNode {
+                name: node_name(&node),
+                transform: convert_transform(node.transform().decomposed()),
+                mesh: node.mesh().map(|mesh| {
+                    load_context.get_handle(format!("{path}#Mesh{}/Primitive0", mesh.index()))
+                }),
+                skin: node.skin().map(|skin| skin.index()),
+                children: node.children().map(|child| child.index()).collect(),
+            })
+            .collect();
+
+        let skins = gltf
+            .skins()
+            .map(|skin| {
+                let joints: Vec<usize> = skin.joints().map(|node| node.index()).collect();
+
+                let inverse_bind_matrices: Vec<Mat4> = skin
+                    .reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice))
+                    .read_inverse_bind_matrices()
+                    .map(|matrices| matrices.map(|matrix| Mat4::from_cols_array_2d(&matrix)))
+                    .map_or_else(|| vec![Mat4::IDENTITY; joints.len()], Iterator::collect);
+
+                HumanoidSkin {
+                    joints,
+                    inverse_bindposes: load_context.set_labeled_asset(
+                        &format!("Skin{}", skin.index()),
+                        LoadedAsset::new(SkinnedMeshInverseBindposes::from(inverse_bind_matrices)),
+                    ),
+                }
+            })
+            .collect();
+
+        let bones = gltf
+            .nodes()
+            .filter_map(|node| Some((rig_profile.bone(node.name()?)?, node.index())))
+            .collect();
+
+        let clips = gltf
+            .animations()
+            .map(|animation| {
+                let label = format!("Animation{}", animation.index());
+                let clip = load_context.get_handle(format!("{path}#{label}"));
+
+                (animation.name().map_or(label, str::to_string), clip)
+            })
+            .collect();
+
+        Ok(HumanoidAsset {
+            nodes,
+            skins,
+            root,
+            bones,
+            clips,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct HumanoidAssetLoader;
+
+impl AssetLoader for HumanoidAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let description: HumanoidDescription = ron::de::from_bytes(bytes)?;
+
+            let rig_profile = match &description.rig {
+                Some(rig) => RigProfile::from_ron(std::str::from_utf8(
+                    &load_context.read_asset_bytes(rig).await?,
+                )?)?,
+                None => RigProfile::default(),
+            };
+
+            let gltf =
+                gltf::Gltf::from_slice(&load_context.read_asset_bytes(&description.gltf).await?)?;
+            let buffers = load_buffers(&gltf, &description.gltf, load_context).await?;
+
+            let humanoid = HumanoidAsset::from_gltf(
+                &gltf,
+                &buffers,
+                &description.gltf,
+                &rig_profile,
+                load_context,
+            )?;
+
+            //the glTF loader provides the meshes & clips
+            load_context.set_default_asset(
+                LoadedAsset::new(humanoid).with_dependency(description.gltf.into()),
+            );
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["humanoid.ron"]
+    }
+}
+
+/// Asset path of the humanoid named `name`
+pub fn humanoid_path(name: &str) -> String {
+    format!("characters/{name}.humanoid.ron")
+}
+
+/// Turns the entity into the humanoid once the asset is loaded
+#[derive(Component, Debug)]
+pub struct SpawnHumanoid(pub Handle<HumanoidAsset>);
+
+/// The entity's [`Humanoid`] rig was spawned
+#[derive(Event, Debug)]
+pub struct HumanoidSpawnedEvent(pub Entity);
+
+fn spawn_node(
+    commands: &mut Commands,
+    node: &HumanoidNode,
+    standard_material: &mut Assets<StandardMaterial>,
+) -> Entity {
+    let entity = commands
+        .spawn((
+            node.transform,
+            GlobalTransform::default(),
+            Visibility::Visible,
+            ComputedVisibility::default(),
+            node.name.clone(),
+        ))
+        .id();
+
+    if let Some(mesh) = &node.mesh {
+        commands.entity(entity).insert((
+            mesh.clone(),
+            standard_material.add(StandardMaterial::default()),
+        ));
+    }
+
+    entity
+}
+
+/// Spawns the rig of `asset` under `entity`
+pub fn spawn_humanoid(
+    commands: &mut Commands,
+    entity: Entity,
+    asset: &HumanoidAsset,
+    standard_material: &mut Assets<StandardMaterial>,
+) -> Humanoid {
+    let mut entities = vec![None; asset.nodes.len()];
+    let root = spawn_node(commands, &asset.nodes[asset.root], standard_material);
+    entities[asset.root] = Some(root);
+
+    //path of every node from the root, to bind animation curves to
+    let mut paths: Vec<Option<EntityPath>> = vec![None; asset.nodes.len()];
+    paths[asset.root] = Some(EntityPath {
+        parts: vec![asset.nodes[asset.root].name.clone()],
+    });
+
+    let mut queue = VecDeque::from([asset.root]);
+
+    while let Some(index) = queue.pop_front() {
+        let node_entity = entities[index].unwrap();
+
+        for &child in &asset.nodes[index].children {
+            let child_entity = spawn_node(commands, &asset.nodes[child], standard_material);
+            commands.entity(node_entity).add_child(child_entity);
+
+            let mut path = paths[index].clone().unwrap_or_default();
+            path.parts.push(asset.nodes[child].name.clone());
+            paths[child] = Some(path);
+
+            entities[child] = Some(child_entity);
+            queue.push_back(child);
+        }
+    }
+
+    commands.entity(entity).add_child(root);
+
+    for (index, node) in asset.nodes.iter().enumerate() {
+        let (Some(node_entity), Some(skin)) = (entities[index], node.skin) else {
+            continue;
+        };
+        let skin = &asset.skins[skin];
+
+        let joints: Vec<Entity> = skin
+            .joints
+            .iter()
+            .filter_map(|joint| entities[*joint])
+            .collect();
+
+        commands.entity(node_entity).insert(SkinnedMesh {
+            inverse_bindposes: skin.inverse_bindposes.clone(),
+            joints,
+        });
+    }
+
+    let bones = RigBones(
+        asset
+            .bones
+            .iter()
+            .filter_map(|(bone, node)| Some((*bone, entities[*node]?)))
+            .collect(),
+    );
+
+    let meshes = asset
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(index, node)| {
+            node.mesh.is_some() && !asset.bones.values().any(|bone| bone == index)
+        })
+        .filter_map(|(index, node)| Some((node.name.to_string(), entities[index]?)))
+        .collect();
+
+    //the root is moved by gameplay, not clips
+    let animated_bones = paths
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| *index != asset.root)
+        .filter_map(|(index, path)| Some((entities[index]?, path?)))
+        .collect();
+
+    let humanoid = Humanoid::from_bones(bones, meshes)
+        .expect("the humanoid asset checked the rig had every bone");
+
+    commands.entity(entity).insert((
+        humanoid.clone(),
+        HumanoidAnimations::new(asset.clips.clone(), animated_bones),
+        LocomotionBlend::default(),
+    ));
+
+    humanoid
+}
+
+/// Spawns the rigs of every [`SpawnHumanoid`] whose asset finished loading
+pub fn spawn_humanoids(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    humanoid_assets: Res<Assets<HumanoidAsset>>,
+    mut standard_material: ResMut<Assets<StandardMaterial>>,
+    spawn_query: Query<(Entity, &SpawnHumanoid)>,
+    mut spawned_event: EventWriter<HumanoidSpawnedEvent>,
+) {
+    for (entity, SpawnHumanoid(handle)) in &spawn_query {
+        match humanoid_assets.get(handle) {
+            Some(asset) => {
+                spawn_humanoid(&mut commands, entity, asset, &mut standard_material);
+                spawned_event.send(HumanoidSpawnedEvent(entity));
+            }
+            None => {
+                if asset_server.get_load_state(handle) != LoadState::Failed {
+                    continue;
+                }
+
+                error!(
+                    "could not load humanoid {:?}",
+                    asset_server.get_handle_path(handle)
+                );
+            }
+        }
+
+        commands.entity(entity).remove::<SpawnHumanoid>();
+    }
+}
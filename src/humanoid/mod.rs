@@ -1,19 +1,27 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 
 use bevy::{
+    asset::AddAsset,
     ecs::query::ReadOnlyWorldQuery,
-    pbr::SkinnedMeshJoints,
     prelude::{
-        App, AssetServer, Assets, BuildChildren, Commands, Component, ComputedVisibility, Entity,
-        GlobalTransform, Handle, Mat4, Mesh, Plugin, Quat, Query, Res, ResMut, StandardMaterial,
-        Transform, Vec3, Visibility,
+        App, Component, Entity, GlobalTransform, Plugin, PreUpdate, Quat, Query, Transform, Vec3,
     },
-    render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
 };
-use gltf::Node;
 
 use crate::ik::IKChain;
 
+use self::{
+    animation::HumanoidAnimationPlugin,
+    asset::{spawn_humanoids, HumanoidAsset, HumanoidAssetLoader, HumanoidSpawnedEvent},
+    ragdoll::RagdollPlugin,
+    rig::{HumanoidBone, HumanoidLoadError, RigBones},
+};
+
+pub mod animation;
+pub mod asset;
+pub mod ragdoll;
+pub mod rig;
+
 fn convert_transform((translation, rotation, scale): ([f32; 3], [f32; 4], [f32; 3])) -> Transform {
     let mut transform = Transform::default();
 
@@ -36,7 +44,7 @@ fn convert_transform((translation, rotation, scale): ([f32; 3], [f32; 4], [f32;
     transform
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Limb(pub Entity, pub Entity, pub Entity);
 
 impl Limb {
@@ -68,22 +76,7 @@ impl<'a> Iterator for LimbIterator<'a> {
     }
 }
 
-#[derive(Clone)]
-pub struct LimbBuilder(Option<Entity>, Option<Entity>, Option<Entity>);
-
-impl Default for LimbBuilder {
-    fn default() -> Self {
-        Self(None, None, None)
-    }
-}
-
-impl From<LimbBuilder> for Limb {
-    fn from(value: LimbBuilder) -> Self {
-        Self(value.0.unwrap(), value.1.unwrap(), value.2.unwrap())
-    }
-}
-
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct Humanoid {
     pub head: Entity,
     pub body: Entity,
@@ -91,10 +84,52 @@ pub struct Humanoid {
     pub right_arm: Limb,
     pub left_leg: Limb,
     pub right_leg: Limb,
+    /// every bone found, optional ones (neck, clavicles, fingers) included
+    pub bones: RigBones,
     pub meshes: HashMap<String, Entity>,
 }
 
 impl Humanoid {
+    pub fn from_bones(
+        bones: RigBones,
+        meshes: HashMap<String, Entity>,
+    ) -> Result<Self, HumanoidLoadError> {
+        let missing = bones.missing();
+        if !missing.is_empty() {
+            return Err(HumanoidLoadError::MissingBones(missing));
+        }
+
+        let bone = |bone| bones.get(bone).unwrap();
+        let limb = |a, b, c| Limb(bone(a), bone(b), bone(c));
+
+        Ok(Humanoid {
+            head: bone(HumanoidBone::Head),
+            body: bone(HumanoidBone::Body),
+            left_arm: limb(
+                HumanoidBone::LeftUpperArm,
+                HumanoidBone::LeftLowerArm,
+                HumanoidBone::LeftHand,
+            ),
+            right_arm: limb(
+                HumanoidBone::RightUpperArm,
+                HumanoidBone::RightLowerArm,
+                HumanoidBone::RightHand,
+            ),
+            left_leg: limb(
+                HumanoidBone::LeftUpperLeg,
+                HumanoidBone::LeftLowerLeg,
+                HumanoidBone::LeftFoot,
+            ),
+            right_leg: limb(
+                HumanoidBone::RightUpperLeg,
+                HumanoidBone::RightLowerLeg,
+                HumanoidBone::RightFoot,
+            ),
+            bones,
+            meshes,
+        })
+    }
+
     pub fn left_arm_ik<F: ReadOnlyWorldQuery>(
         &self,
         global_transforms: &Query<&GlobalTransform, F>,
@@ -245,229 +280,14 @@ impl Humanoid {
     }
 }
 
-#[derive(Clone)]
-struct HumanoidBuilder {
-    head: Option<Entity>,
-    body: Option<Entity>,
-    left_arm: LimbBuilder,
-    right_arm: LimbBuilder,
-    left_leg: LimbBuilder,
-    right_leg: LimbBuilder,
-    meshes: HashMap<String, Entity>,
-}
-
-impl Default for HumanoidBuilder {
-    fn default() -> Self {
-        Self {
-            head: None,
-            body: None,
-            left_arm: LimbBuilder::default(),
-            right_arm: LimbBuilder::default(),
-            left_leg: LimbBuilder::default(),
-            right_leg: LimbBuilder::default(),
-            meshes: HashMap::new(),
-        }
-    }
-}
-
-impl From<HumanoidBuilder> for Humanoid {
-    fn from(value: HumanoidBuilder) -> Self {
-        Humanoid {
-            head: value.head.unwrap(),
-            body: value.body.unwrap(),
-            left_arm: value.left_arm.into(),
-            right_arm: value.right_arm.into(),
-            left_leg: value.left_leg.into(),
-            right_leg: value.right_leg.into(),
-            meshes: value.meshes,
-        }
-    }
-}
-
-pub fn load_humanoid(
-    humanoid_asset_path: &str,
-    commands: &mut Commands,
-
-    asset_server: &Res<AssetServer>,
-
-    standard_material: &mut ResMut<Assets<StandardMaterial>>,
-    inverse_bindposes: &mut ResMut<Assets<SkinnedMeshInverseBindposes>>,
-) -> Option<(Entity, Humanoid)> {
-    //start from start en
-    let (gltf, buffers, _) = gltf::import(&format!("assets/{humanoid_asset_path}")).ok()?;
-
-    let mut humanoid_builder = HumanoidBuilder::default();
-
-    let entities: Vec<(Entity, Transform)> = gltf
-        .nodes()
-        .map(|node| {
-            let transform = convert_transform(node.transform().decomposed());
-            let entity = commands
-                .spawn((
-                    transform.clone(),
-                    GlobalTransform::default(),
-                    Visibility::Visible,
-                    ComputedVisibility::default(),
-                ))
-                .id();
-
-            if let Some(mesh) = node.mesh() {
-                let mesh: Handle<Mesh> = asset_server.load(&format!(
-                    "{humanoid_asset_path}#Mesh{}/Primitive0",
-                    mesh.index()
-                ));
-                commands
-                    .entity(entity)
-                    .insert((mesh, standard_material.add(StandardMaterial::default())));
-            }
-
-            if let Some(name) = node.name() {
-                match name {
-                    "head" => {
-                        humanoid_builder.head = Some(entity);
-                    }
-
-                    "Spine" => {
-                        humanoid_builder.body = Some(entity);
-                    }
-
-                    "right.arm.0" => {
-                        humanoid_builder.right_arm.0 = Some(entity);
-                    }
-                    "right.arm.1" => {
-                        humanoid_builder.right_arm.1 = Some(entity);
-                    }
-                    "right.hand" => {
-                        humanoid_builder.right_arm.2 = Some(entity);
-                    }
-
-                    "left.arm.0" => {
-                        humanoid_builder.left_arm.0 = Some(entity);
-                    }
-                    "left.arm.1" => {
-                        humanoid_builder.left_arm.1 = Some(entity);
-                    }
-                    "left.hand" => {
-                        humanoid_builder.left_arm.2 = Some(entity);
-                    }
-
-                    "right.leg.upper" => {
-                        humanoid_builder.right_leg.0 = Some(entity);
-                    }
-                    "right.leg.lower" => {
-                        humanoid_builder.right_leg.1 = Some(entity);
-                    }
-                    "right.foot" => {
-                        humanoid_builder.right_leg.2 = Some(entity);
-                    }
-
-                    "left.leg.upper" => {
-                        humanoid_builder.left_leg.0 = Some(entity);
-                    }
-                    "left.leg.lower" => {
-                        humanoid_builder.left_leg.1 = Some(entity);
-                    }
-                    "left.foot" => {
-                        humanoid_builder.left_leg.2 = Some(entity);
-                    }
-
-                    name => {
-                        if node.mesh().is_some() {
-                            humanoid_builder.meshes.insert(name.into(), entity);
-                        }
-                    }
-                }
-            }
-            //println!("{:#?}",humanoid_builder.meshes);
-            (entity, transform)
-        })
-        .collect();
-
-    //generate skinned mesh
-
-    let skins: Vec<SkinnedMesh> = gltf
-        .skins()
-        .map(|skin| {
-            let joints: Vec<Entity> = skin
-                .joints()
-                .map(|node| {
-                    commands
-                        .entity(entities[node.index()].0)
-                        .insert(SkinnedMeshJoints {
-                            index: skin.index() as u32,
-                        });
-
-                    entities[node.index()].0
-                })
-                .collect();
-
-            let inverse_bind_matrices =
-                SkinnedMeshInverseBindposes::from(match skin.inverse_bind_matrices() {
-                    Some(accessor) => {
-                        let get_buffer_data =
-                            |buffer: gltf::Buffer| buffers.get(buffer.index()).map(|x| &*x.0);
-
-                        gltf::accessor::Iter::<[[f32; 4]; 4]>::new(accessor, get_buffer_data)
-                            .unwrap()
-                            .map(|matrix| {
-                                Mat4::from_cols(
-                                    matrix[0].into(),
-                                    matrix[1].into(),
-                                    matrix[2].into(),
-                                    matrix[3].into(),
-                                )
-                            })
-                            .collect::<Vec<Mat4>>()
-                    }
-                    None => (0..joints.len())
-                        .map(|_| Mat4::IDENTITY)
-                        .collect::<Vec<Mat4>>(),
-                });
-
-            SkinnedMesh {
-                inverse_bindposes: inverse_bindposes.add(inverse_bind_matrices),
-                joints,
-            }
-        })
-        .collect();
-
-    let mut queue: VecDeque<usize> = VecDeque::new();
-
-    let start_index = gltf.scenes().next()?.nodes().next()?.index();
-
-    queue.push_back(start_index);
-
-    let nodes: Vec<Node<'_>> = gltf.nodes().collect();
-
-    while !queue.is_empty() {
-        let index = queue.pop_front().unwrap();
-        let current_node = &entities[index];
-
-        if let Some(skin) = nodes[index].skin() {
-            commands
-                .entity(current_node.0)
-                .insert(skins[skin.index()].clone());
-        }
-
-        let _ = &nodes[index].children().for_each(|node| {
-            commands
-                .entity(current_node.0)
-                .push_children(&[entities[node.index()].0]);
-
-            queue.push_back(node.index());
-        });
-    }
-
-    let start_entity = entities[start_index].0;
-
-    let humanoid_component: Humanoid = humanoid_builder.clone().into();
-
-    commands.entity(start_entity).insert(humanoid_component);
-    return Some((start_entity, humanoid_builder.into()));
-}
-
 pub struct HumanoidPlugin;
 
 impl Plugin for HumanoidPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.add_asset::<HumanoidAsset>()
+            .init_asset_loader::<HumanoidAssetLoader>()
+            .add_event::<HumanoidSpawnedEvent>()
+            .add_plugins((HumanoidAnimationPlugin, RagdollPlugin))
+            .add_systems(PreUpdate, spawn_humanoids);
+    }
 }
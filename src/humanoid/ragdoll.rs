@@ -0,0 +1,886 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::{
+        App, Commands, Component, Entity, Event, EventReader, GlobalTransform, IntoSystemConfigs,
+        Mat3, Parent, Plugin, PostUpdate, PreUpdate, Quat, Query, Res, Transform, Update, Vec3,
+        Without,
+    },
+    time::{Time, Timer, TimerMode},
+    transform::TransformSystem,
+};
+
+use crate::{
+    ik::{global_transform, IKChainConstrains, IKConstraint, IKSystem},
+    player::movement::walk,
+    scene::nav_mesh::NavMesh,
+};
+
+use super::Humanoid;
+
+const GRAVITY: Vec3 = Vec3::new(0., -9.81, 0.);
+/// Share of the velocity kept every step
+const DAMPING: f32 = 0.99;
+/// Constraint passes per step
+const ITERATIONS: usize = 8;
+/// Share of the sliding velocity lost on the floor every step
+const FRICTION: f32 = 0.4;
+/// Mass of the whole body, spread evenly over its joints
+const MASS: f32 = 70.;
+/// Longest step simulated at once, longer frames are split
+const MAX_STEP: f32 = 1. / 60.;
+/// Capsule radii relative to the length of their segment
+const TORSO_RADIUS: f32 = 0.35;
+const LIMB_RADIUS: f32 = 0.2;
+/// Widest a shoulder or hip swings away from its rest direction
+const SOCKET_ANGLE: f32 = PI * 0.6;
+/// Furthest an elbow or knee bends
+const HINGE_ANGLE: f32 = PI * 0.8;
+/// Share of a joint limit's violation corrected every pass
+const LIMIT_STIFFNESS: f32 = 0.5;
+/// Seconds blending from the ragdoll back to animation
+const RECOVER_TIME: f32 = 0.6;
+
+/// Particle of every bone: the body, the head & 3 per limb
+const BODY: usize = 0;
+const HEAD: usize = 1;
+const LEFT_ARM: usize = 2;
+const RIGHT_ARM: usize = 5;
+/// First particle of each limb: left arm, right arm, left leg & right leg
+const LIMB_ROOTS: [usize; 4] = [LEFT_ARM, RIGHT_ARM, 8, 11];
+/// Particles held rigid together
+const TORSO: [usize; 6] = [BODY, HEAD, LEFT_ARM, RIGHT_ARM, 8, 11];
+
+/// Makes the humanoid go limp, pushed by `impulse`. It gets back up after `recover_after`
+/// seconds or stays down when none, eg: when dead
+#[derive(Event, Debug, Clone)]
+pub struct RagdollEvent {
+    pub humanoid: Entity,
+    pub impulse: Vec3,
+    pub recover_after: Option<f32>,
+}
+
+/// Capsule collider around the segment between 2 particles
+#[derive(Debug, Clone, Copy)]
+pub struct RagdollCapsule {
+    pub a: usize,
+    pub b: usize,
+    pub radius: f32,
+}
+
+/// Limit of the bone from particle `joint` to `child`
+#[derive(Debug, Clone, Copy)]
+struct RagdollJoint {
+    /// particle the parent bone starts at, none for limb roots which hang from the torso
+    parent: Option<usize>,
+    joint: usize,
+    child: usize,
+    constraint: IKConstraint,
+    /// rest world space direction the constraint is relative to
+    reference: Vec3,
+    /// rest global rotation of the parent joint
+    frame: Quat,
+}
+
+/// Filter for humanoids standing on their own, not limp
+pub type Standing = Without<Ragdoll>;
+
+#[derive(Debug, Clone)]
+pub enum RagdollState {
+    /// simulated, getting back up once the timer finishes
+    Active { recover: Option<Timer> },
+    /// blending back to animation
+    Recovering(Timer),
+}
+
+/// Limp humanoid simulated as particles at its joints, kept together by the bone lengths & bent
+/// within [`IKConstraint`]s
+#[derive(Component, Debug, Clone)]
+pub struct Ragdoll {
+    pub state: RagdollState,
+    bones: Vec<Entity>,
+    positions: Vec<Vec3>,
+    previous: Vec<Vec3>,
+    rest_positions: Vec<Vec3>,
+    rest_rotations: Vec<Quat>,
+    /// rotation of the torso at rest
+    rest_torso: Quat,
+    /// particles kept at a distance, the bones & the torso held rigid
+    links: Vec<(usize, usize, f32)>,
+    pub capsules: Vec<RagdollCapsule>,
+    /// capsules that may collide: not joined & apart at rest
+    contacts: Vec<(usize, usize)>,
+    joints: Vec<RagdollJoint>,
+    /// local transforms before going limp, restored every frame under the ragdoll
+    rest: Vec<(Entity, Transform)>,
+    /// local transforms the ragdoll left, blended from while recovering
+    limp: Vec<(Entity, Transform)>,
+    /// floor height where there's no nav mesh
+    ground: f32,
+    /// impulse to apply to the torso on the next step
+    impulse: Vec3,
+}
+
+/// Rotation of the torso, up from the body to the head & right across the shoulders
+fn torso_rotation(positions: &[Vec3]) -> Quat {
+    let up = (positions[HEAD] - positions[BODY])
+        .try_normalize()
+        .unwrap_or(Vec3::Y);
+    let side = positions[RIGHT_ARM] - positions[LEFT_ARM];
+    let right = (side - up * side.dot(up))
+        .try_normalize()
+        .unwrap_or_else(|| up.any_orthonormal_vector());
+
+    Quat::from_mat3(&Mat3::from_cols(right, up, right.cross(up)))
+}
+
+/// Moves `a` & `b` towards being `length` apart
+fn keep_distance(positions: &mut [Vec3], a: usize, b: usize, length: f32) {
+    let delta = positions[b] - positions[a];
+    let distance = delta.length();
+    if distance <= f32::EPSILON {
+        return;
+    }
+
+    let correction = delta * (distance - length) / distance * 0.5;
+    positions[a] += correction;
+    positions[b] -= correction;
+}
+
+/// Parameters along segments `a` & `b` of their closest points
+fn closest_points((a0, a1): (Vec3, Vec3), (b0, b1): (Vec3, Vec3)) -> (f32, f32) {
+    let (da, db, r) = (a1 - a0, b1 - b0, a0 - b0);
+    let (a, e, f) = (da.dot(da), db.dot(db), db.dot(r));
+
+    if a <= f32::EPSILON {
+        return (0., (f / e.max(f32::EPSILON)).clamp(0., 1.));
+    }
+    let c = da.dot(r);
+    if e <= f32::EPSILON {
+        return ((-c / a).clamp(0., 1.), 0.);
+    }
+
+    let b = da.dot(db);
+    let denom = a * e - b * b;
+    let s = match denom > f32::EPSILON {
+        true => ((b * f - c * e) / denom).clamp(0., 1.),
+        false => 0.,
+    };
+
+    let t = (b * s + f) / e;
+    match (t < 0., t > 1.) {
+        (true, _) => ((-c / a).clamp(0., 1.), 0.),
+        (_, true) => (((b - c) / a).clamp(0., 1.), 1.),
+        _ => (s, t),
+    }
+}
+
+impl RagdollCapsule {
+    /// Parameters of the closest points along both capsules & how far each must move to stop
+    /// overlapping, if they do
+    fn overlap(&self, other: &RagdollCapsule, positions: &[Vec3]) -> Option<(f32, f32, Vec3)> {
+        let first = (positions[self.a], positions[self.b]);
+        let second = (positions[other.a], positions[other.b]);
+        let (s, t) = closest_points(first, second);
+
+        let delta = second.0.lerp(second.1, t) - first.0.lerp(first.1, s);
+        let distance = delta.length();
+        let overlap = self.radius + other.radius - distance;
+
+        match overlap > 0. && distance > f32::EPSILON {
+            true => Some((s, t, delta / distance * overlap * 0.5)),
+            false => None,
+        }
+    }
+}
+
+impl Ragdoll {
+    /// Ragdoll of `humanoid` in its current pose. Each limb bends within the constraints of its
+    /// [`IKChainConstrains`], shoulders & hips default to a ball & socket & elbows & knees to a
+    /// hinge. `forward` is the way the humanoid faces
+    pub fn new(
+        humanoid: &Humanoid,
+        constraints: [Option<&IKChainConstrains>; 4],
+        forward: Vec3,
+        parent_query: &Query<&Parent>,
+        transform_query: &Query<&Transform>,
+    ) -> Self {
+        let limbs = [
+            &humanoid.left_arm,
+            &humanoid.right_arm,
+            &humanoid.left_leg,
+            &humanoid.right_leg,
+        ];
+
+        let bones: Vec<Entity> = [humanoid.body, humanoid.head]
+            .into_iter()
+            .chain(limbs.iter().flat_map(|limb| limb.iter()))
+            .collect();
+
+        let globals: Vec<Transform> = bones
+            .iter()
+            .map(|bone| global_transform(*bone, parent_query, transform_query).compute_transform())
+            .collect();
+        let positions: Vec<Vec3> = globals.iter().map(|global| global.translation).collect();
+        let rotations: Vec<Quat> = globals.iter().map(|global| global.rotation).collect();
+
+        let dir = |from: usize, to: usize| {
+            (positions[to] - positions[from])
+                .try_normalize()
+                .unwrap_or(Vec3::NEG_Y)
+        };
+        let link = |a: usize, b: usize| (a, b, positions[a].distance(positions[b]));
+
+        //the torso is rigid, the limbs hang from it
+        let mut links: Vec<(usize, usize, f32)> = TORSO
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| TORSO[i + 1..].iter().map(|b| link(*a, *b)))
+            .collect();
+
+        let mut capsules = vec![RagdollCapsule {
+            a: BODY,
+            b: HEAD,
+            radius: positions[BODY].distance(positions[HEAD]) * TORSO_RADIUS,
+        }];
+        let mut joints = Vec::new();
+
+        for (k, (limb, root)) in limbs.iter().zip(LIMB_ROOTS).enumerate() {
+            let (mid, end) = (root + 1, root + 2);
+
+            links.extend([link(root, mid), link(mid, end)]);
+            capsules.extend([(root, mid), (mid, end)].map(|(a, b)| RagdollCapsule {
+                a,
+                b,
+                radius: positions[a].distance(positions[b]) * LIMB_RADIUS,
+            }));
+
+            let chain = constraints[k].map_or(&[][..], |constraints| &constraints.0[..]);
+            let constraint = |index: usize| {
+                chain
+                    .get(index)
+                    .copied()
+                    .filter(|constraint| *constraint != IKConstraint::None)
+            };
+
+            let root_frame = parent_query.get(limb.0).map_or(Quat::IDENTITY, |parent| {
+                global_transform(parent.get(), parent_query, transform_query)
+                    .compute_transform()
+                    .rotation
+            });
+            joints.push(match constraint(0) {
+                Some(constraint) => RagdollJoint {
+                    parent: None,
+                    joint: root,
+                    child: mid,
+                    constraint,
+                    reference: root_frame * Vec3::Y,
+                    frame: root_frame,
+                },
+                None => RagdollJoint {
+                    parent: None,
+                    joint: root,
+                    child: mid,
+                    constraint: IKConstraint::BallAndSocket {
+                        max_angle: SOCKET_ANGLE,
+                    },
+                    reference: dir(root, mid),
+                    frame: root_frame,
+                },
+            });
+
+            //elbows bend forwards & knees backwards
+            let (upper, lower) = (dir(root, mid), dir(mid, end));
+            let hint = match k < 2 {
+                true => forward,
+                false => -forward,
+            };
+
+            joints.push(RagdollJoint {
+                parent: Some(root),
+                joint: mid,
+                child: end,
+                constraint: constraint(1).unwrap_or_else(|| {
+                    IKConstraint::bent_hinge(rotations[root], upper, lower, hint, HINGE_ANGLE)
+                }),
+                reference: upper,
+                frame: rotations[root],
+            });
+        }
+
+        let contacts = (0..capsules.len())
+            .flat_map(|i| (i + 1..capsules.len()).map(move |j| (i, j)))
+            .filter(|(i, j)| {
+                let (first, second) = (&capsules[*i], &capsules[*j]);
+                let ends = [first.a, first.b];
+
+                let joined = ends.iter().any(|end| *end == second.a || *end == second.b)
+                    || (ends.iter().any(|end| TORSO.contains(end))
+                        && [second.a, second.b].iter().any(|end| TORSO.contains(end)));
+
+                !joined && first.overlap(second, &positions).is_none()
+            })
+            .collect();
+
+        let ground = positions
+            .iter()
+            .map(|position| position.y)
+            .fold(f32::INFINITY, f32::min);
+
+        Self {
+            state: RagdollState::Active { recover: None },
+            rest: bones
+                .iter()
+                .map(|bone| {
+                    (
+                        *bone,
+                        transform_query.get(*bone).copied().unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            limp: Vec::new(),
+            bones,
+            previous: positions.clone(),
+            rest_torso: torso_rotation(&positions),
+            rest_positions: positions.clone(),
+            positions,
+            rest_rotations: rotations,
+            links,
+            capsules,
+            contacts,
+            joints,
+            ground,
+            impulse: Vec3::ZERO,
+        }
+    }
+
+    pub fn push(&mut self, impulse: Vec3) {
+        self.impulse += impulse;
+    }
+
+    /// How far the torso turned from rest
+    fn torso(&self, positions: &[Vec3]) -> Quat {
+        torso_rotation(positions) * self.rest_torso.inverse()
+    }
+
+    fn rest_dir(&self, from: usize, to: usize) -> Vec3 {
+        (self.rest_positions[to] - self.rest_positions[from])
+            .try_normalize()
+            .unwrap_or(Vec3::NEG_Y)
+    }
+
+    /// Bends the bone of `joint` back within its constraint
+    fn limit(&self, joint: &RagdollJoint, torso: Quat, positions: &mut [Vec3]) {
+        let rest_reference = torso * joint.reference;
+
+        let (frame, reference) = match joint.parent {
+            None => (torso * joint.frame, rest_reference),
+            Some(parent) => {
+                let current = (positions[joint.joint] - positions[parent])
+                    .try_normalize()
+                    .unwrap_or(rest_reference);
+
+                (
+                    Quat::from_rotation_arc(rest_reference, current) * torso * joint.frame,
+                    current,
+                )
+            }
+        };
+
+        let offset = positions[joint.child] - positions[joint.joint];
+        let length = offset.length();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let dir = joint.constraint.apply(frame, reference, offset / length);
+
+        //the joint & the child meet halfway, keeping their momentum
+        let correction = (dir * length - offset) * 0.5 * LIMIT_STIFFNESS;
+        positions[joint.child] += correction;
+        positions[joint.joint] -= correction;
+    }
+
+    /// Pushes apart overlapping capsules that may touch
+    fn collide(&self, positions: &mut [Vec3]) {
+        for (first, second) in &self.contacts {
+            let (first, second) = (&self.capsules[*first], &self.capsules[*second]);
+            let Some((s, t, push)) = first.overlap(second, positions) else {
+                continue;
+            };
+
+            positions[first.a] -= push * (1. - s);
+            positions[first.b] -= push * s;
+            positions[second.a] += push * (1. - t);
+            positions[second.b] += push * t;
+        }
+    }
+
+    /// Advances the simulation by `delta` seconds, landing on the nav mesh's floor
+    pub fn step(&mut self, delta: f32, nav_mesh: &NavMesh) {
+        let radii: Vec<f32> = (0..self.positions.len())
+            .map(|i| {
+                self.capsules
+                    .iter()
+                    .filter(|capsule| capsule.a == i || capsule.b == i)
+                    .map(|capsule| capsule.radius)
+                    .fold(0., f32::max)
+            })
+            .collect();
+        let floors: Vec<f32> = self
+            .positions
+            .iter()
+            .map(|position| {
+                nav_mesh
+                    .snap(*position)
+                    .map_or(self.ground, |(_, floor)| floor.y)
+            })
+            .collect();
+
+        //the torso takes the impulse & drags the limbs along
+        let kick = self.impulse * self.positions.len() as f32 / (MASS * TORSO.len() as f32);
+        for particle in TORSO {
+            self.previous[particle] -= kick * delta;
+        }
+        self.impulse = Vec3::ZERO;
+
+        let mut positions = self.positions.clone();
+        for (position, previous) in positions.iter_mut().zip(&self.previous) {
+            *position += (*position - *previous) * DAMPING + GRAVITY * delta * delta;
+        }
+
+        for _ in 0..ITERATIONS {
+            for (a, b, length) in &self.links {
+                keep_distance(&mut positions, *a, *b, *length);
+            }
+
+            let torso = self.torso(&positions);
+            for joint in &self.joints {
+                self.limit(joint, torso, &mut positions);
+            }
+
+            self.collide(&mut positions);
+
+            for ((position, radius), floor) in positions.iter_mut().zip(&radii).zip(&floors) {
+                position.y = position.y.max(floor + radius);
+            }
+        }
+
+        self.previous = std::mem::replace(&mut self.positions, positions);
+
+        //whatever touches the floor stops falling & slides less
+        for (((position, previous), radius), floor) in self
+            .positions
+            .iter()
+            .zip(self.previous.iter_mut())
+            .zip(&radii)
+            .zip(&floors)
+        {
+            if position.y > floor + radius + 0.001 {
+                continue;
+            }
+
+            let velocity = *position - *previous;
+            *previous = *position - Vec3::new(velocity.x, 0., velocity.z) * (1. - FRICTION);
+        }
+    }
+
+    /// Global (position, rotation) of every bone in the simulated pose, parents first. Only the
+    /// torso's & the limb roots' positions are set, the rest follow their parents
+    fn bone_poses(&self) -> Vec<(Entity, Option<Vec3>, Quat)> {
+        let torso = self.torso(&self.positions);
+        let current_dir = |from: usize, to: usize| {
+            (self.positions[to] - self.positions[from])
+                .try_normalize()
+                .unwrap_or(torso * self.rest_dir(from, to))
+        };
+
+        let mut poses = vec![
+            (
+                self.bones[BODY],
+                Some(self.positions[BODY]),
+                torso * self.rest_rotations[BODY],
+            ),
+            (
+                self.bones[HEAD],
+                Some(self.positions[HEAD]),
+                torso * self.rest_rotations[HEAD],
+            ),
+        ];
+
+        for root in LIMB_ROOTS {
+            let mid = root + 1;
+
+            let upper =
+                Quat::from_rotation_arc(torso * self.rest_dir(root, mid), current_dir(root, mid))
+                    * torso
+                    * self.rest_rotations[root];
+
+            //the lower bone as it hangs from the upper one
+            let carried = upper * self.rest_rotations[root].inverse();
+            let lower = Quat::from_rotation_arc(
+                carried * self.rest_dir(mid, mid + 1),
+                current_dir(mid, mid + 1),
+            ) * carried
+                * self.rest_rotations[mid];
+
+            poses.push((self.bones[root], Some(self.positions[root]), upper));
+            poses.push((self.bones[mid], None, lower));
+        }
+
+        poses
+    }
+
+    /// Sets the bones' local transforms to the simulated pose
+    fn write_pose(
+        &self,
+        parent_query: &Query<&Parent>,
+        transform_query: &mut Query<&mut Transform>,
+    ) {
+        for (bone, position, rotation) in self.bone_poses() {
+            let parent = parent_query
+                .get(bone)
+                .map_or(GlobalTransform::IDENTITY, |parent| {
+                    global_transform(parent.get(), parent_query, &transform_query.to_readonly())
+                });
+
+            let Ok(mut transform) = transform_query.get_mut(bone) else {
+                continue;
+            };
+
+            transform.rotation = parent.compute_transform().rotation.inverse() * rotation;
+            if let Some(position) = position {
+                transform.translation = parent.affine().inverse().transform_point3(position);
+            }
+        }
+    }
+}
+
+/// Makes humanoids go limp or pushes the ones already limp
+fn start_ragdolls(
+    mut commands: Commands,
+    mut ragdoll_event: EventReader<RagdollEvent>,
+    mut humanoid_query: Query<(&Humanoid, Option<&mut Ragdoll>)>,
+    constraint_query: Query<&IKChainConstrains>,
+    parent_query: Query<&Parent>,
+    transform_query: Query<&Transform>,
+) {
+    for event in ragdoll_event.iter() {
+        let Ok((humanoid, ragdoll)) = humanoid_query.get_mut(event.humanoid) else {
+            continue;
+        };
+
+        let recover = event
+            .recover_after
+            .map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+
+        if let Some(mut ragdoll) = ragdoll {
+            //falls back down from where it lies
+            ragdoll.state = RagdollState::Active { recover };
+            ragdoll.push(event.impulse);
+            continue;
+        }
+
+        let forward = global_transform(event.humanoid, &parent_query, &transform_query).forward();
+        let constraints = [
+            &humanoid.left_arm,
+            &humanoid.right_arm,
+            &humanoid.left_leg,
+            &humanoid.right_leg,
+        ]
+        .map(|limb| constraint_query.get(limb.0).ok());
+
+        let mut ragdoll = Ragdoll::new(
+            humanoid,
+            constraints,
+            forward,
+            &parent_query,
+            &transform_query,
+        );
+        ragdoll.state = RagdollState::Active { recover };
+        ragdoll.push(event.impulse);
+
+        commands.entity(event.humanoid).insert(ragdoll);
+    }
+}
+
+/// Puts the bones back in their pose before going limp, so animation & IK carry on from it
+fn restore_ragdoll_pose(
+    ragdoll_query: Query<&Ragdoll>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    for ragdoll in &ragdoll_query {
+        for (bone, rest) in &ragdoll.rest {
+            if let Ok(mut transform) = transform_query.get_mut(*bone) {
+                *transform = *rest;
+            }
+        }
+    }
+}
+
+/// Simulates limp humanoids over the animated pose, then blends them back into it
+fn simulate_ragdolls(
+    mut commands: Commands,
+    time: Res<Time>,
+    nav_mesh: Res<NavMesh>,
+    mut ragdoll_query: Query<(Entity, &mut Ragdoll)>,
+    parent_query: Query<&Parent>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    let delta = time.delta();
+
+    for (entity, mut ragdoll) in &mut ragdoll_query {
+        let ragdoll = ragdoll.as_mut();
+
+        match &mut ragdoll.state {
+            RagdollState::Active { recover } => {
+                let getting_up = recover
+                    .as_mut()
+                    .is_some_and(|recover| recover.tick(delta).finished());
+
+                let steps = (delta.as_secs_f32() / MAX_STEP).ceil().max(1.);
+                for _ in 0..steps as usize {
+                    ragdoll.step(delta.as_secs_f32() / steps, &nav_mesh);
+                }
+
+                //stands back up where the body lies
+                if getting_up {
+                    let offset = ragdoll.positions[BODY] - ragdoll.rest_positions[BODY];
+                    if let Ok(mut transform) = transform_query.get_mut(entity) {
+                        walk(
+                            &nav_mesh,
+                            &mut transform.translation,
+                            Vec3::new(offset.x, 0., offset.z),
+                        );
+                    }
+                }
+
+                ragdoll.write_pose(&parent_query, &mut transform_query);
+
+                if getting_up {
+                    ragdoll.limp = ragdoll
+                        .bones
+                        .iter()
+                        .filter_map(|bone| Some((*bone, *transform_query.get(*bone).ok()?)))
+                        .collect();
+                    ragdoll.state = RagdollState::Recovering(Timer::from_seconds(
+                        RECOVER_TIME,
+                        TimerMode::Once,
+                    ));
+                }
+            }
+            RagdollState::Recovering(timer) => {
+                let t = timer.tick(delta).percent();
+                //eases in & out
+                let t = t * t * (3. - 2. * t);
+
+                for (bone, limp) in &ragdoll.limp {
+                    if let Ok(mut transform) = transform_query.get_mut(*bone) {
+                        transform.translation = limp.translation.lerp(transform.translation, t);
+                        transform.rotation = limp.rotation.slerp(transform.rotation, t);
+                    }
+                }
+
+                if timer.finished() {
+                    commands.entity(entity).remove::<Ragdoll>();
+                }
+            }
+        }
+    }
+}
+
+pub struct RagdollPlugin;
+
+impl Plugin for RagdollPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RagdollEvent>()
+            .add_systems(PreUpdate, restore_ragdoll_pose)
+            .add_systems(Update, start_ragdolls)
+            .add_systems(
+                PostUpdate,
+                simulate_ragdolls
+                    .after(IKSystem)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
+
+    use bevy::{ecs::system::SystemState, prelude::World};
+
+    use super::*;
+    use crate::{
+        humanoid::{rig::RigBones, Limb},
+        scene::nav_mesh::tests::grid,
+    };
+
+    const STEP: f32 = 1. / 60.;
+
+    /// Humanoid standing at `feet` facing -Z, its bones unparented so their transforms are global
+    fn spawn_humanoid(world: &mut World, feet: Vec3) -> Humanoid {
+        let mut bone = |x: f32, y: f32| {
+            world
+                .spawn(Transform::from_translation(feet + Vec3::new(x, y, 0.)))
+                .id()
+        };
+        let mut limb = |x: f32, [a, b, c]: [f32; 3]| Limb(bone(x, a), bone(x, b), bone(x, c));
+
+        let (left_arm, right_arm) = (limb(-0.2, [1.45, 1.15, 0.9]), limb(0.2, [1.45, 1.15, 0.9]));
+        let (left_leg, right_leg) = (limb(-0.1, [0.9, 0.5, 0.1]), limb(0.1, [0.9, 0.5, 0.1]));
+
+        Humanoid {
+            body: bone(0., 1.),
+            head: bone(0., 1.6),
+            left_arm,
+            right_arm,
+            left_leg,
+            right_leg,
+            bones: RigBones(HashMap::new()),
+            meshes: HashMap::new(),
+        }
+    }
+
+    fn ragdoll(world: &mut World, humanoid: &Humanoid) -> Ragdoll {
+        let mut state: SystemState<(Query<&Parent>, Query<&Transform>)> = SystemState::new(world);
+        let (parent_query, transform_query) = state.get(world);
+
+        Ragdoll::new(
+            humanoid,
+            [None; 4],
+            Vec3::NEG_Z,
+            &parent_query,
+            &transform_query,
+        )
+    }
+
+    fn floor() -> NavMesh {
+        grid(
+            &(-3..3)
+                .flat_map(|x| (-3..3).map(move |z| (x, z)))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn falls_and_settles_on_the_floor() {
+        let mut world = World::new();
+        let humanoid = spawn_humanoid(&mut world, Vec3::Y);
+        let mut ragdoll = ragdoll(&mut world, &humanoid);
+        let nav_mesh = floor();
+
+        ragdoll.push(Vec3::new(0., 0., 60.));
+        for _ in 0..600 {
+            ragdoll.step(STEP, &nav_mesh);
+        }
+
+        for (position, previous) in ragdoll.positions.iter().zip(&ragdoll.previous) {
+            assert!(position.y > -0.01, "{position} sank through the floor");
+            assert!(position.y < 0.5, "{position} is still up");
+            assert!(
+                position.distance(*previous) < 0.001,
+                "{position} is still moving"
+            );
+        }
+    }
+
+    #[test]
+    fn joints_stay_within_their_limits() {
+        let mut world = World::new();
+        let humanoid = spawn_humanoid(&mut world, Vec3::Y);
+        let mut ragdoll = ragdoll(&mut world, &humanoid);
+        let nav_mesh = floor();
+
+        let dir = |positions: &[Vec3], from: usize, to: usize| {
+            (positions[to] - positions[from]).normalize()
+        };
+
+        ragdoll.push(Vec3::new(150., 0., -100.));
+        for _ in 0..300 {
+            ragdoll.step(STEP, &nav_mesh);
+
+            let positions = &ragdoll.positions;
+            let torso = ragdoll.torso(positions);
+
+            for joint in &ragdoll.joints {
+                let (angle, max) = match joint.parent {
+                    None => (
+                        (torso * joint.reference).angle_between(dir(
+                            positions,
+                            joint.joint,
+                            joint.child,
+                        )),
+                        SOCKET_ANGLE,
+                    ),
+                    Some(parent) => (
+                        dir(positions, parent, joint.joint).angle_between(dir(
+                            positions,
+                            joint.joint,
+                            joint.child,
+                        )),
+                        HINGE_ANGLE,
+                    ),
+                };
+
+                assert!(
+                    angle < max + 0.1,
+                    "joint {} bent {angle} past {max}",
+                    joint.joint
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn recovering_blends_back_into_the_animated_pose() {
+        let mut app = App::new();
+        let start = Instant::now();
+        app.insert_resource(Time::new(start))
+            .insert_resource(floor())
+            .add_systems(Update, (restore_ragdoll_pose, simulate_ragdolls).chain());
+
+        let humanoid = spawn_humanoid(&mut app.world, Vec3::ZERO);
+        let mut limp = ragdoll(&mut app.world, &humanoid);
+        limp.state = RagdollState::Active {
+            recover: Some(Timer::from_seconds(1., TimerMode::Once)),
+        };
+        limp.push(Vec3::new(0., 0., 100.));
+        let animated = limp.rest.clone();
+        let entity = app.world.spawn((Transform::default(), humanoid, limp)).id();
+
+        let pose = |app: &App| {
+            animated
+                .iter()
+                .map(|(bone, _)| *app.world.get::<Transform>(*bone).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let at_rest = |pose: &[Transform]| {
+            pose.iter().zip(&animated).all(|(transform, (_, rest))| {
+                transform.translation.distance(rest.translation) < 1e-4
+                    && transform.rotation.angle_between(rest.rotation) < 1e-3
+            })
+        };
+
+        let mut frame = 0;
+        let mut fell = false;
+        while app.world.get::<Ragdoll>(entity).is_some() {
+            frame += 1;
+            assert!(frame < 600, "never got back up");
+
+            app.world
+                .resource_mut::<Time>()
+                .update_with_instant(start + Duration::from_secs_f32(frame as f32 * STEP));
+            app.update();
+
+            fell |= !at_rest(&pose(&app));
+        }
+
+        assert!(fell, "the ragdoll never left the animated pose");
+        assert!(at_rest(&pose(&app)), "{:?}", pose(&app));
+    }
+}
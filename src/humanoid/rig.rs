@@ -0,0 +1,158 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::prelude::Entity;
+use serde::Deserialize;
+
+/// Rig profile bundled with the game, covering our own, Mixamo & Blender bone names
+const DEFAULT_PROFILE: &str = include_str!("../../assets/rigs/humanoid.rig.ron");
+
+/// Semantic bones of a humanoid rig
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HumanoidBone {
+    Head,
+    Body,
+
+    RightUpperArm,
+    RightLowerArm,
+    RightHand,
+    LeftUpperArm,
+    LeftLowerArm,
+    LeftHand,
+
+    RightUpperLeg,
+    RightLowerLeg,
+    RightFoot,
+    LeftUpperLeg,
+    LeftLowerLeg,
+    LeftFoot,
+
+    Neck,
+    RightClavicle,
+    LeftClavicle,
+
+    RightThumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+    LeftThumb,
+    LeftIndex,
+    LeftMiddle,
+    LeftRing,
+    LeftPinky,
+}
+
+impl HumanoidBone {
+    /// Bones every rig needs, the rest are optional
+    pub const REQUIRED: [HumanoidBone; 14] = [
+        HumanoidBone::Head,
+        HumanoidBone::Body,
+        HumanoidBone::RightUpperArm,
+        HumanoidBone::RightLowerArm,
+        HumanoidBone::RightHand,
+        HumanoidBone::LeftUpperArm,
+        HumanoidBone::LeftLowerArm,
+        HumanoidBone::LeftHand,
+        HumanoidBone::RightUpperLeg,
+        HumanoidBone::RightLowerLeg,
+        HumanoidBone::RightFoot,
+        HumanoidBone::LeftUpperLeg,
+        HumanoidBone::LeftLowerLeg,
+        HumanoidBone::LeftFoot,
+    ];
+}
+
+/// Maps the semantic bones to the node names a rig may use for them. Loaded from
+/// `rigs/{name}.rig.ron`
+#[derive(Deserialize, Debug, Clone)]
+pub struct RigProfile {
+    bones: HashMap<HumanoidBone, Vec<String>>,
+    /// node name -> bone, built from `bones`
+    #[serde(skip)]
+    lookup: HashMap<String, HumanoidBone>,
+}
+
+impl RigProfile {
+    pub fn from_ron(ron: &str) -> Result<Self, ron::error::SpannedError> {
+        let mut profile: RigProfile = ron::de::from_str(ron)?;
+
+        profile.lookup = profile
+            .bones
+            .iter()
+            .flat_map(|(bone, names)| names.iter().map(|name| (name.clone(), *bone)))
+            .collect();
+
+        Ok(profile)
+    }
+
+    /// Bone a node is, if any
+    pub fn bone(&self, node_name: &str) -> Option<HumanoidBone> {
+        self.lookup.get(node_name).copied()
+    }
+
+    /// Required bones none of `node_names` is
+    pub fn missing<'a>(&self, node_names: impl IntoIterator<Item = &'a str>) -> Vec<HumanoidBone> {
+        let found: Vec<HumanoidBone> = node_names
+            .into_iter()
+            .filter_map(|name| self.bone(name))
+            .collect();
+
+        HumanoidBone::REQUIRED
+            .into_iter()
+            .filter(|bone| !found.contains(bone))
+            .collect()
+    }
+}
+
+impl Default for RigProfile {
+    fn default() -> Self {
+        RigProfile::from_ron(DEFAULT_PROFILE).expect("the bundled rig profile is valid")
+    }
+}
+
+/// Bones found in a rig, by what they are
+#[derive(Debug, Default, Clone)]
+pub struct RigBones(pub HashMap<HumanoidBone, Entity>);
+
+impl RigBones {
+    pub fn get(&self, bone: HumanoidBone) -> Option<Entity> {
+        self.0.get(&bone).copied()
+    }
+
+    /// Required bones the rig lacks
+    pub fn missing(&self) -> Vec<HumanoidBone> {
+        HumanoidBone::REQUIRED
+            .into_iter()
+            .filter(|bone| !self.0.contains_key(bone))
+            .collect()
+    }
+}
+
+/// Why a humanoid couldn't be loaded
+#[derive(Debug)]
+pub enum HumanoidLoadError {
+    Import(gltf::Error),
+    /// the glTF has no scene to spawn
+    NoScene,
+    MissingBones(Vec<HumanoidBone>),
+}
+
+impl fmt::Display for HumanoidLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HumanoidLoadError::Import(err) => write!(f, "could not import the glTF: {err}"),
+            HumanoidLoadError::NoScene => write!(f, "the glTF has no scene"),
+            HumanoidLoadError::MissingBones(bones) => {
+                write!(f, "the rig is missing the bones {bones:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HumanoidLoadError {}
+
+impl From<gltf::Error> for HumanoidLoadError {
+    fn from(err: gltf::Error) -> Self {
+        HumanoidLoadError::Import(err)
+    }
+}
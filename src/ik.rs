@@ -4,9 +4,15 @@ use std::{
 };
 
 use bevy::{
+    animation::animation_player,
     ecs::{component::Component, entity::Entity, system::Query},
-    math::Vec3,
-    transform::components::{GlobalTransform, Transform},
+    hierarchy::Parent,
+    math::{Quat, Vec3},
+    prelude::{App, IntoSystemConfigs, IntoSystemSetConfig, Plugin, PostUpdate, SystemSet},
+    transform::{
+        components::{GlobalTransform, Transform},
+        TransformSystem,
+    },
 };
 
 #[derive(Component, Debug)]
@@ -24,6 +30,22 @@ impl IKChain {
             })
             .collect()
     }
+    /// Chain through `entities`, measured from their transforms this frame
+    pub fn from_hierarchy(
+        entities: impl IntoIterator<Item = Entity>,
+        parent_query: &Query<&Parent>,
+        transform_query: &Query<&Transform>,
+    ) -> Self {
+        entities
+            .into_iter()
+            .map(|entity| {
+                (
+                    entity,
+                    global_transform(entity, parent_query, transform_query).compute_transform(),
+                )
+            })
+            .collect()
+    }
     pub fn length(&self) -> f32 {
         self.0.iter().fold(0., |acc, (_, length)| acc + length)
     }
@@ -32,19 +54,89 @@ impl IKChain {
     }
 
     pub fn get(&self, index: usize) -> Option<&(Entity, f32)> {
-        if index > self.0.len() {
-            return None;
-        }
+        self.0.get(index)
+    }
 
-        Some(&self[index])
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut (Entity, f32)> {
+        self.0.get_mut(index)
     }
 
-    pub fn get_mut(&mut self, index: usize) -> Option<&(Entity, f32)> {
-        if index > self.0.len() {
-            return None;
+    /// Bone lengths, the end effector's 0 included
+    pub fn lengths(&self) -> Vec<f32> {
+        self.iter().map(|(_, length)| *length).collect()
+    }
+
+    /// Pose of the chain this frame, None if a joint has no transform. Joints have to be a
+    /// parent to child path
+    pub fn pose(
+        &self,
+        parent_query: &Query<&Parent>,
+        transform_query: &Query<&Transform>,
+    ) -> Option<IKPose> {
+        let (root, _) = self.0.first()?;
+        let mut parent = parent_query
+            .get(*root)
+            .map_or(GlobalTransform::IDENTITY, |parent| {
+                global_transform(parent.get(), parent_query, transform_query)
+            });
+
+        let mut pose = IKPose {
+            positions: Vec::with_capacity(self.0.len()),
+            rotations: Vec::with_capacity(self.0.len()),
+            root_parent: parent.to_scale_rotation_translation().1,
+        };
+
+        for (entity, _) in self.iter() {
+            let global = parent.mul_transform(*transform_query.get(*entity).ok()?);
+            let (_, rotation, position) = global.to_scale_rotation_translation();
+
+            pose.positions.push(position);
+            pose.rotations.push(rotation);
+            parent = global;
         }
 
-        Some(&mut self[index])
+        Some(pose)
+    }
+
+    /// Solves the chain towards `goal` & returns the new local rotation of every joint. Bones keep
+    /// the lengths stored in the chain, however the animated pose stretches them
+    pub fn solve(
+        &self,
+        constraints: Option<&IKChainConstrains>,
+        goal: Vec3,
+        settings: &IKSettings,
+        parent_query: &Query<&Parent>,
+        transform_query: &Query<&Transform>,
+    ) -> Option<(Vec<(Entity, Quat)>, IKSolution)> {
+        let pose = self.pose(parent_query, transform_query)?;
+        let constraints = constraints.map_or(&[][..], |constraints| &constraints.0[..]);
+
+        let solution = fabrik(&pose, &self.lengths(), constraints, goal, settings);
+
+        let rotations = self
+            .iter()
+            .map(|(entity, _)| *entity)
+            .zip(local_rotations(&solution.rotations, pose.root_parent))
+            .collect();
+
+        Some((rotations, solution))
+    }
+}
+
+/// World transform of `entity` this frame composed from its ancestors' [`Transform`]s, as
+/// [`GlobalTransform`]s are only propagated at the end of the frame
+pub fn global_transform(
+    entity: Entity,
+    parent_query: &Query<&Parent>,
+    transform_query: &Query<&Transform>,
+) -> GlobalTransform {
+    let transform = transform_query.get(entity).copied().unwrap_or_default();
+
+    match parent_query.get(entity) {
+        Ok(parent) => {
+            global_transform(parent.get(), parent_query, transform_query).mul_transform(transform)
+        }
+        Err(_) => GlobalTransform::from(transform),
     }
 }
 
@@ -90,96 +182,591 @@ impl FromIterator<(Entity, Transform)> for IKChain {
     }
 }
 
+/// Limits how a joint bends the bone it starts relative to its parent bone. Axes are in the
+/// local space of the parent joint & angles in radians
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum IKConstraint {
+    #[default]
     None,
+    /// doesn't bend, only twists along the parent bone
     Pivot,
-    Hinge,
-    Saddle,
-    BallAndSocket,
-    CondyLoid,
-    Planar,
+    /// bends around `axis` only, eg: knee & elbow
+    Hinge { axis: Vec3, min: f32, max: f32 },
+    /// bends up to `max_x` around the parent's x axis & `max_z` around its z axis independently,
+    /// eg: thumb base. Limits are below PI / 2
+    Saddle { max_x: f32, max_z: f32 },
+    /// bends inside a cone of half angle `max_angle`, eg: shoulder & hip
+    BallAndSocket { max_angle: f32 },
+    /// bends inside an elliptical cone, eg: wrist. Limits are below PI / 2
+    CondyLoid { max_x: f32, max_z: f32 },
+    /// bends freely inside the plane of `normal`
+    Planar { normal: Vec3 },
 }
 
-#[derive(Component)]
+/// Rest bend below which an elbow or knee is considered straight
+const STRAIGHT_ANGLE: f32 = 0.05;
+
+impl IKConstraint {
+    /// Hinge bending from 0 to `max` the way the bone `lower` is bent from its parent bone
+    /// `upper`, or towards `hint` when they're straight, eg: elbows bend forwards & knees
+    /// backwards. `frame` is the global rotation of the parent joint
+    pub fn bent_hinge(frame: Quat, upper: Vec3, lower: Vec3, hint: Vec3, max: f32) -> Self {
+        let axis = match upper.angle_between(lower) > STRAIGHT_ANGLE {
+            true => upper.cross(lower),
+            false => upper.cross(hint),
+        }
+        .try_normalize()
+        .unwrap_or_else(|| upper.any_orthonormal_vector());
+
+        IKConstraint::Hinge {
+            axis: frame.inverse() * axis,
+            min: 0.,
+            max,
+        }
+    }
+
+    /// Bends the world space bone direction `dir` into the constraint. `frame` is the global
+    /// rotation of the parent joint & `reference` the direction of the parent bone
+    pub fn apply(&self, frame: Quat, reference: Vec3, dir: Vec3) -> Vec3 {
+        match *self {
+            IKConstraint::None => dir,
+            IKConstraint::Pivot => reference,
+            IKConstraint::Hinge { axis, min, max } => {
+                let axis = (frame * axis).normalize_or_zero();
+                let Some(reference) = reject(reference, axis).try_normalize() else {
+                    return dir;
+                };
+                let projected = reject(dir, axis).try_normalize().unwrap_or(reference);
+
+                let angle = signed_angle(reference, projected, axis).clamp(min, max);
+
+                Quat::from_axis_angle(axis, angle) * reference
+            }
+            IKConstraint::BallAndSocket { max_angle } => {
+                if reference.angle_between(dir) <= max_angle {
+                    return dir;
+                }
+
+                let axis = reference
+                    .cross(dir)
+                    .try_normalize()
+                    .unwrap_or_else(|| reference.any_orthonormal_vector());
+
+                Quat::from_axis_angle(axis, max_angle) * reference
+            }
+            IKConstraint::Saddle { max_x, max_z } | IKConstraint::CondyLoid { max_x, max_z } => {
+                let x = reject(frame * Vec3::X, reference)
+                    .try_normalize()
+                    .unwrap_or_else(|| reference.any_orthonormal_vector());
+                let z = x.cross(reference);
+
+                //bend towards z is around x & towards x around z
+                let forward = dir.dot(reference);
+                let mut bend_x = f32::atan2(dir.dot(z), forward);
+                let mut bend_z = f32::atan2(dir.dot(x), forward);
+
+                match self {
+                    IKConstraint::Saddle { .. } => {
+                        bend_x = bend_x.clamp(-max_x, max_x);
+                        bend_z = bend_z.clamp(-max_z, max_z);
+                    }
+                    _ => {
+                        let ellipse = (bend_x / max_x).powi(2) + (bend_z / max_z).powi(2);
+                        if ellipse > 1. {
+                            bend_x /= ellipse.sqrt();
+                            bend_z /= ellipse.sqrt();
+                        }
+                    }
+                }
+
+                (reference + z * bend_x.tan() + x * bend_z.tan()).normalize()
+            }
+            IKConstraint::Planar { normal } => {
+                let normal = (frame * normal).normalize_or_zero();
+
+                reject(dir, normal)
+                    .try_normalize()
+                    .or_else(|| reject(reference, normal).try_normalize())
+                    .unwrap_or(dir)
+            }
+        }
+    }
+}
+
+/// Part of `vector` perpendicular to the unit `axis`
+fn reject(vector: Vec3, axis: Vec3) -> Vec3 {
+    vector - axis * vector.dot(axis)
+}
+
+/// Angle from `from` to `to` around `axis`
+fn signed_angle(from: Vec3, to: Vec3, axis: Vec3) -> f32 {
+    let angle = from.angle_between(to);
+
+    match axis.dot(from.cross(to)) < 0. {
+        true => -angle,
+        false => angle,
+    }
+}
+
+/// Constraint of every joint of the chain, missing ones are [`IKConstraint::None`]
+#[derive(Component, Debug, Clone, Default)]
 pub struct IKChainConstrains(pub Vec<IKConstraint>);
 
 #[derive(Component)]
 pub struct IKGoal(pub Vec3);
 
-// fn ik(
-//     ik_query: Query<(&IKChain, Option<&IKChainConstrains>, &IKGoal)>,
-//     mut _transform_query: Query<&mut Transform, &GlobalTransform>,
-// ) {
-//     for (_chain, _constraints, _goal) in &ik_query {
-//         todo!();
-//         //probally gonna be jacobian or cmf impl of ik
-//     }
-// }
-
-pub fn ik<const MAX_ITERATION: usize>(
-    chain: &IKChain,
-    _constraints: Option<&IKChainConstrains>,
-    transform_query: &Query<&GlobalTransform>,
-    goal: &IKGoal,
-    tolerance: f32,
-) -> Vec<Vec3> {
-    let start = transform_query
-        .get(chain.iter().next().unwrap().0)
-        .unwrap()
-        .translation();
-
-    let mut final_pos: Vec<Vec3> = chain
+/// How an [`IKChain`] is solved
+#[derive(Component, Debug, Clone)]
+pub struct IKSettings {
+    pub iterations: usize,
+    /// distance from the goal close enough to stop iterating
+    pub tolerance: f32,
+    /// how much the solved pose overrides the animated one, from 0 to 1
+    pub weight: f32,
+    /// world space point the chain bends towards, eg: in front of the knee
+    pub pole: Option<Vec3>,
+}
+
+impl IKSettings {
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_pole(mut self, pole: Vec3) -> Self {
+        self.pole = Some(pole);
+        self
+    }
+}
+
+impl Default for IKSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            tolerance: 0.01,
+            weight: 1.,
+            pole: None,
+        }
+    }
+}
+
+/// World space pose of a chain, root first
+#[derive(Debug, Clone)]
+pub struct IKPose {
+    pub positions: Vec<Vec3>,
+    /// global rotation of every joint
+    pub rotations: Vec<Quat>,
+    /// global rotation of the root's parent
+    pub root_parent: Quat,
+}
+
+impl IKPose {
+    /// Bone lengths in the pose, the end effector's 0 included
+    pub fn lengths(&self) -> Vec<f32> {
+        self.positions
+            .windows(2)
+            .map(|joints| joints[0].distance(joints[1]))
+            .chain([0.])
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IKSolution {
+    pub positions: Vec<Vec3>,
+    /// new global rotation of every joint
+    pub rotations: Vec<Quat>,
+    /// whether the end effector got within tolerance of the goal
+    pub reached: bool,
+}
+
+/// Direction of bone `index` in `positions`, falls back to up for zero length bones
+fn bone_dir(positions: &[Vec3], index: usize) -> Vec3 {
+    (positions[index + 1] - positions[index])
+        .try_normalize()
+        .unwrap_or(Vec3::Y)
+}
+
+/// Moves joints from the root out, keeping bone lengths & bending each joint into its constraint
+fn reach_backward(
+    pose: &IKPose,
+    positions: &mut [Vec3],
+    lengths: &[f32],
+    constraints: &[IKConstraint],
+) {
+    let mut frame = pose.root_parent;
+
+    for i in 0..positions.len() - 1 {
+        let rest = bone_dir(&pose.positions, i);
+        let reference = match i {
+            0 => frame * Vec3::Y,
+            _ => bone_dir(positions, i - 1),
+        };
+
+        let dir = (positions[i + 1] - positions[i])
+            .try_normalize()
+            .unwrap_or(rest);
+        let dir = constraints
+            .get(i)
+            .map_or(dir, |constraint| constraint.apply(frame, reference, dir));
+
+        positions[i + 1] = positions[i] + dir * lengths[i];
+        frame = Quat::from_rotation_arc(rest, dir) * pose.rotations[i];
+    }
+}
+
+/// Moves joints from the end effector at `goal` back to the root, keeping bone lengths
+fn reach_forward(pose: &IKPose, positions: &mut [Vec3], lengths: &[f32], goal: Vec3) {
+    let last = positions.len() - 1;
+    positions[last] = goal;
+
+    for i in (0..last).rev() {
+        let dir = (positions[i] - positions[i + 1])
+            .try_normalize()
+            .unwrap_or(-bone_dir(&pose.positions, i));
+
+        positions[i] = positions[i + 1] + dir * lengths[i];
+    }
+}
+
+/// Global joint rotations turning each bone of `pose` onto `positions`, the end effector keeps
+/// its local rotation
+fn solved_rotations(pose: &IKPose, positions: &[Vec3]) -> Vec<Quat> {
+    let mut rotations: Vec<Quat> = Vec::with_capacity(positions.len());
+
+    for i in 0..positions.len() {
+        let rotation = match (i + 1 < positions.len(), i) {
+            (true, _) => {
+                Quat::from_rotation_arc(bone_dir(&pose.positions, i), bone_dir(positions, i))
+                    * pose.rotations[i]
+            }
+            (false, 0) => pose.rotations[0],
+            (false, _) => rotations[i - 1] * pose.rotations[i - 1].inverse() * pose.rotations[i],
+        };
+
+        rotations.push(rotation);
+    }
+
+    rotations
+}
+
+/// Local rotations of a parent to child chain of global `rotations` under `root_parent`
+pub fn local_rotations(rotations: &[Quat], root_parent: Quat) -> Vec<Quat> {
+    let mut parent = root_parent;
+
+    rotations
         .iter()
-        .map(|(entity, _)| transform_query.get(*entity).unwrap().translation())
-        .collect();
-
-    let goal_dist = (start - goal.0).length();
-
-    // check target is within range
-    if goal_dist > chain.length() {
-        //target is unreachable
-        let mut joint_dist = chain.iter().map(|(_, dist)| dist);
-        for i in 1..(final_pos.len() - 1) {
-            let joint_dist_from_goal = (goal.0 - final_pos[i]).length();
-            let ratio = joint_dist.next().unwrap() / joint_dist_from_goal;
-
-            final_pos[i + 1] = (1. - ratio) * final_pos[i] + ratio * goal.0;
-        }
-    } else {
-        //target is reachable
-        for _iteration in 0..MAX_ITERATION {
-            //if distance from final_pos & goal is less than tolerance then break
-            if (goal.0 - *final_pos.last().unwrap()).length() < tolerance {
-                break;
+        .map(|rotation| {
+            let local = parent.inverse() * *rotation;
+            parent = *rotation;
+            local
+        })
+        .collect()
+}
+
+/// Turns the joints between the root & end effector around the line joining them, so the chain
+/// bends towards `pole`
+fn bend_towards(positions: &mut [Vec3], pole: Vec3) {
+    let (root, end) = (positions[0], positions[positions.len() - 1]);
+    let Some(axis) = (end - root).try_normalize() else {
+        return;
+    };
+    let (Some(bend), Some(target)) = (
+        positions
+            .get(1)
+            .and_then(|joint| reject(*joint - root, axis).try_normalize()),
+        reject(pole - root, axis).try_normalize(),
+    ) else {
+        return;
+    };
+
+    let rotation = Quat::from_axis_angle(axis, signed_angle(bend, target, axis));
+
+    let last = positions.len() - 1;
+    for joint in &mut positions[1..last] {
+        *joint = root + rotation * (*joint - root);
+    }
+}
+
+/// FABRIK: moves the end effector of `pose` towards `goal` keeping the root in place.
+/// `lengths[i]` is the length of the bone from joint i to i + 1 & `constraints[i]` limits joint i.
+/// Unreachable goals get the chain stretched straight at them. Chains are bent towards the
+/// settings' pole after solving, then bent back into their constraints
+pub fn fabrik(
+    pose: &IKPose,
+    lengths: &[f32],
+    constraints: &[IKConstraint],
+    goal: Vec3,
+    settings: &IKSettings,
+) -> IKSolution {
+    let tolerance = settings.tolerance;
+    let mut positions = pose.positions.clone();
+
+    if positions.len() < 2 {
+        return IKSolution {
+            reached: positions
+                .first()
+                .is_some_and(|p| p.distance(goal) <= tolerance),
+            rotations: pose.rotations.clone(),
+            positions,
+        };
+    }
+
+    let root = positions[0];
+    let reach: f32 = lengths[..positions.len() - 1].iter().sum();
+
+    match root.distance(goal) > reach {
+        true => {
+            let dir = (goal - root).normalize_or_zero();
+            for i in 0..positions.len() - 1 {
+                positions[i + 1] = positions[i] + dir * lengths[i];
             }
+            reach_backward(pose, &mut positions, lengths, constraints);
+        }
+        false => {
+            for _ in 0..settings.iterations {
+                if positions[positions.len() - 1].distance(goal) <= tolerance {
+                    break;
+                }
+
+                reach_forward(pose, &mut positions, lengths, goal);
 
-            //forward reaching
-            *final_pos.last_mut().unwrap() = goal.0.clone();
-            for i in (1..(final_pos.len() - 1)).rev() {
-                let joint_dist = (final_pos[i + 1] - final_pos[i]).length();
-                let ratio = chain[i - 1].1 / joint_dist;
+                positions[0] = root;
+                reach_backward(pose, &mut positions, lengths, constraints);
+            }
 
-                final_pos[i] = (1. - ratio) * final_pos[i + 1] + ratio * final_pos[i];
+            if let Some(pole) = settings.pole {
+                bend_towards(&mut positions, pole);
+                //the swing around the root to end line can bend joints out of their limits
+                reach_backward(pose, &mut positions, lengths, constraints);
             }
+        }
+    }
 
-            //backward reaching
-            *final_pos.get_mut(0).unwrap() = start.clone();
-            for i in 1..final_pos.len() {
-                let joint_dist = (final_pos[i + 1] - final_pos[i]).length();
-                let ratio = chain[i - 1].1 / joint_dist;
+    IKSolution {
+        reached: positions[positions.len() - 1].distance(goal) <= tolerance,
+        rotations: solved_rotations(pose, &positions),
+        positions,
+    }
+}
+
+/// Solves every [`IKChain`] towards its [`IKGoal`] & blends the result into the joints'
+/// [`Transform`]s
+fn solve_ik_chains(
+    chain_query: Query<(
+        &IKChain,
+        &IKGoal,
+        Option<&IKChainConstrains>,
+        Option<&IKSettings>,
+    )>,
+    parent_query: Query<&Parent>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    let default_settings = IKSettings::default();
+
+    for (chain, goal, constraints, settings) in &chain_query {
+        let settings = settings.unwrap_or(&default_settings);
+
+        if settings.weight <= 0. {
+            continue;
+        }
 
-                final_pos[i + 1] = (1. - ratio) * final_pos[i] + ratio * final_pos[i + 1];
+        let Some((rotations, _)) = chain.solve(
+            constraints,
+            goal.0,
+            settings,
+            &parent_query,
+            &transform_query.to_readonly(),
+        ) else {
+            continue;
+        };
+
+        for (entity, rotation) in rotations {
+            if let Ok(mut transform) = transform_query.get_mut(entity) {
+                transform.rotation = transform.rotation.slerp(rotation, settings.weight.min(1.));
             }
         }
     }
+}
 
-    final_pos
+/// Where IK runs: after animation & before transforms are propagated
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IKSystem;
+
+pub struct IKPlugin;
+
+impl Plugin for IKPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_set(
+            PostUpdate,
+            IKSystem
+                .after(animation_player)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_systems(PostUpdate, solve_ik_chains.in_set(IKSystem));
+    }
 }
 
-// pub struct IKPlugin;
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
 
-// impl Plugin for IKPlugin {
-//     fn build(&self, app: &mut bevy::prelude::App) {
-//         app.add_systems(Update, ik);
-//     }
-// }
+    use super::*;
+
+    /// Straight chain of `bones` unit long bones up from the origin
+    fn straight_pose(bones: usize) -> IKPose {
+        IKPose {
+            positions: (0..=bones).map(|i| Vec3::Y * i as f32).collect(),
+            rotations: vec![Quat::IDENTITY; bones + 1],
+            root_parent: Quat::IDENTITY,
+        }
+    }
+
+    /// Knee bending from straight up to straight forward (+Z) around X
+    const KNEE: IKConstraint = IKConstraint::Hinge {
+        axis: Vec3::X,
+        min: 0.,
+        max: FRAC_PI_2,
+    };
+
+    fn assert_lengths(solution: &IKSolution, lengths: &[f32]) {
+        for (i, joints) in solution.positions.windows(2).enumerate() {
+            assert!((joints[0].distance(joints[1]) - lengths[i]).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn reaches_reachable_goals() {
+        let pose = straight_pose(2);
+        let settings = IKSettings::default();
+
+        for goal in [
+            Vec3::new(1., 1., 0.),
+            Vec3::new(0.5, 0.5, 0.5),
+            Vec3::new(-1.2, 0.3, 0.8),
+        ] {
+            let solution = fabrik(&pose, &pose.lengths(), &[], goal, &settings);
+
+            assert!(solution.reached, "{goal}");
+            assert!(solution.positions[2].distance(goal) <= settings.tolerance);
+            assert_eq!(solution.positions[0], Vec3::ZERO);
+            assert_lengths(&solution, &pose.lengths());
+        }
+    }
+
+    #[test]
+    fn stretches_towards_unreachable_goals() {
+        let pose = straight_pose(3);
+        let goal = Vec3::new(3., 0., 4.);
+
+        let solution = fabrik(&pose, &pose.lengths(), &[], goal, &IKSettings::default());
+
+        assert!(!solution.reached);
+        assert_lengths(&solution, &pose.lengths());
+        let towards = goal.normalize();
+        for (i, joint) in solution.positions.iter().enumerate() {
+            assert!(joint.distance(towards * i as f32) < 1e-4, "{joint}");
+        }
+    }
+
+    #[test]
+    fn rotations_turn_the_bones_onto_the_solution() {
+        let pose = straight_pose(2);
+
+        let solution = fabrik(
+            &pose,
+            &pose.lengths(),
+            &[],
+            Vec3::new(1., 1., 0.),
+            &IKSettings::default(),
+        );
+
+        for i in 0..2 {
+            let bone = solution.positions[i + 1] - solution.positions[i];
+            assert!((solution.rotations[i] * Vec3::Y).distance(bone.normalize()) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn hinges_stay_inside_their_limits() {
+        let pose = straight_pose(2);
+        let constraints = [IKConstraint::None, KNEE];
+
+        //bending the knee backwards (-Z) or past straight forward isn't allowed
+        for goal in [
+            Vec3::new(0., 1., -1.),
+            Vec3::new(0., 1.2, 0.9),
+            Vec3::new(0., 0.2, 0.5),
+            Vec3::new(0.7, 0.5, -0.4),
+        ] {
+            let solution = fabrik(
+                &pose,
+                &pose.lengths(),
+                &constraints,
+                goal,
+                &IKSettings::default(),
+            );
+            let [hip, knee, ankle] = solution.positions[..] else {
+                panic!();
+            };
+
+            let axis = solution.rotations[0] * Vec3::X;
+            let angle = signed_angle(knee - hip, ankle - knee, axis);
+            assert!(
+                (-1e-3..=FRAC_PI_2 + 1e-3).contains(&angle),
+                "{goal}: {angle}"
+            );
+            assert!(
+                (ankle - knee).dot(axis).abs() < 1e-3,
+                "{goal}: the knee bent off its axis"
+            );
+        }
+    }
+
+    #[test]
+    fn poles_bend_the_chain_inside_its_limits() {
+        let pose = straight_pose(2);
+        let goal = Vec3::new(0., 1.2, 0.5);
+
+        //free to bend sideways towards the pole
+        let pole = Vec3::new(2., 1., 0.);
+        let settings = IKSettings::default().with_pole(pole);
+        let solution = fabrik(&pose, &pose.lengths(), &[], goal, &settings);
+        assert!(solution.reached);
+        assert!(solution.positions[1].x > 0.5, "{}", solution.positions[1]);
+
+        //a pole on the far side of the goal can't bend the knee backwards
+        let constraints = [IKConstraint::None, KNEE];
+        let settings = IKSettings::default().with_pole(Vec3::new(0., 0., 2.));
+        let solution = fabrik(&pose, &pose.lengths(), &constraints, goal, &settings);
+        let [hip, knee, ankle] = solution.positions[..] else {
+            panic!();
+        };
+
+        let axis = solution.rotations[0] * Vec3::X;
+        let angle = signed_angle(knee - hip, ankle - knee, axis);
+        assert!((-1e-3..=FRAC_PI_2 + 1e-3).contains(&angle), "{angle}");
+        assert!((ankle - knee).dot(axis).abs() < 1e-3);
+        assert_lengths(&solution, &pose.lengths());
+    }
+
+    #[test]
+    fn hinge_clamps_to_its_limits() {
+        let dir = |angle: f32| Quat::from_rotation_x(angle) * Vec3::Y;
+
+        for (bend, clamped) in [(0.3, 0.3), (-0.5, 0.), (2., FRAC_PI_2), (-2.5, 0.)] {
+            let bent = KNEE.apply(Quat::IDENTITY, Vec3::Y, dir(bend));
+
+            assert!(bent.distance(dir(clamped)) < 1e-4, "{bend}: {bent}");
+        }
+
+        //bending off the axis is projected onto its plane
+        let bent = KNEE.apply(Quat::IDENTITY, Vec3::Y, Vec3::new(1., 1., 1.).normalize());
+        assert!(bent.distance(dir(FRAC_PI_2 / 2.)) < 1e-4, "{bent}");
+
+        //the axis turns with the parent joint
+        let frame = Quat::from_rotation_y(FRAC_PI_2);
+        let bent = KNEE.apply(frame, Vec3::Y, Vec3::X);
+        assert!(bent.distance(Vec3::X) < 1e-4, "{bent}");
+    }
+}
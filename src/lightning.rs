@@ -14,12 +14,21 @@ use bevy::{
 use rand::SeedableRng;
 use rand::{rngs::SmallRng, RngCore};
 
-use crate::player::{Controllable, EAR_GAP};
+use crate::{
+    noise::NoiseMask,
+    player::{Controllable, EAR_GAP},
+};
 
 #[derive(Resource)]
 struct ThunderSoundEffect(Vec<Handle<AudioSource>>);
 
 const SOURCE_HEIGHT: f32 = 5.;
+/// Sound level of thunder drowning out noises
+const THUNDER_MASK: f32 = 1.;
+/// Seconds for thunder to fade out of the [`NoiseMask`]
+const THUNDER_MASK_TIME: f32 = 6.;
+/// Longest wait between a lightning flash & its thunder, the further it strikes the longer
+pub const MAX_THUNDER_DELAY: f32 = 2.;
 // const VISIBILITY_TIME: f32 = 0.25;
 
 #[derive(Debug)]
@@ -161,7 +170,7 @@ fn update_light_state(
                 if timer.finished() {
                     *visibility = Visibility::Hidden;
                     state.next(Some(Timer::from_seconds(
-                        get_float(rng) * 2.0,
+                        get_float(rng) * MAX_THUNDER_DELAY,
                         TimerMode::Once,
                     )));
                 }
@@ -264,13 +273,76 @@ fn update_lightning(
     }
 }
 
+/// Thunder drowns out noises while it rumbles
+fn mask_noise_with_thunder(mut mask: ResMut<NoiseMask>, query: Query<&Lightning>) {
+    mask.0 = query
+        .iter()
+        .map(|lightning| match lightning {
+            Lightning::Scary {
+                state: ScaryState::Thunder(timer),
+                ..
+            } => THUNDER_MASK * (1. - timer.elapsed_secs() / THUNDER_MASK_TIME).max(0.),
+            _ => 0.,
+        })
+        .fold(0., f32::max);
+}
+
 pub struct LightningPlugin;
 
 impl Plugin for LightningPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_systems(Startup, set_up_lightning).add_systems(
             Update,
-            (update_lightning_timer, update_light_state, update_lightning),
+            (
+                update_lightning_timer,
+                update_light_state,
+                update_lightning,
+                mask_noise_with_thunder,
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::App;
+
+    use super::*;
+
+    #[test]
+    fn thunder_masks_noise_while_it_fades() {
+        let mut app = App::new();
+        app.init_resource::<NoiseMask>()
+            .add_systems(Update, mask_noise_with_thunder);
+
+        let mut thunder = Timer::from_seconds(THUNDER_MASK_TIME, TimerMode::Once);
+        let lightning = app
+            .world
+            .spawn(Lightning::Scary {
+                state: ScaryState::Thunder(thunder.clone()),
+                rng: SmallRng::seed_from_u64(0),
+            })
+            .id();
+        let set_state = |app: &mut App, state| {
+            *app.world.get_mut::<Lightning>(lightning).unwrap() = Lightning::Scary {
+                state,
+                rng: SmallRng::seed_from_u64(0),
+            };
+            app.update();
+            app.world.resource::<NoiseMask>().0
+        };
+
+        assert_eq!(
+            set_state(&mut app, ScaryState::Thunder(thunder.clone())),
+            THUNDER_MASK
+        );
+
+        thunder.tick(std::time::Duration::from_secs_f32(THUNDER_MASK_TIME / 2.));
+        assert_eq!(
+            set_state(&mut app, ScaryState::Thunder(thunder)),
+            THUNDER_MASK / 2.
         );
+
+        assert_eq!(set_state(&mut app, ScaryState::Done), 0.);
     }
 }
@@ -1,37 +1,56 @@
+use std::time::Duration;
+
 // use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
-use bevy::prelude::*;
+use bevy::{asset::ChangeWatcher, prelude::*};
 use bevy_mod_raycast::DefaultRaycastingPlugin;
+use cryptid::CryptidPlugin;
 // use bevy::diagnostic::*;
 use humanoid::HumanoidPlugin;
+use ik::IKPlugin;
 use lightning::LightningPlugin;
+use noise::NoisePlugin;
 use player::PlayerPlugin;
 use rain::RainPlugin;
+use save::SavePlugin;
 use scene::shadow_caster::ShadowCasterMaterial;
 use scene::WorldPlugin;
 
+pub mod cryptid;
 pub mod humanoid;
 pub mod ik;
 pub mod lightning;
+pub mod noise;
+pub mod perception;
 pub mod player;
 pub mod rain;
+pub mod save;
 pub mod scene;
 pub mod standard_material;
 
 fn main() {
     App::new()
         .add_plugins((
-            DefaultPlugins.set(bevy_mod_raycast::low_latency_window_plugin()),
+            DefaultPlugins
+                .set(bevy_mod_raycast::low_latency_window_plugin())
+                .set(AssetPlugin {
+                    // hot reload scenes, floors, walls & props
+                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                    ..default()
+                }),
             DefaultRaycastingPlugin,
         ))
         .add_plugins((
             //DefaultPlugins,
-            WorldPlugin,
+            WorldPlugin::default(),
             PlayerPlugin,
             LightningPlugin,
             RainPlugin,
             MaterialPlugin::<ShadowCasterMaterial>::default(),
             HumanoidPlugin,
-            //IKPlugin,
+            NoisePlugin,
+            CryptidPlugin,
+            IKPlugin,
+            SavePlugin,
         ))
         //debug plugins
         // .add_plugins((
@@ -0,0 +1,226 @@
+use bevy::prelude::{
+    App, Component, Entity, Event, EventReader, EventWriter, GlobalTransform, IntoSystemConfigs,
+    Plugin, Query, Res, Resource, Update, Vec3,
+};
+
+use crate::scene::prop::sound_source::SoundVolume;
+
+/// Something in the world made a sound AI agents can hear
+#[derive(Event, Debug, Clone)]
+pub struct NoiseEvent {
+    /// entity making the noise, it doesn't hear itself
+    pub source: Option<Entity>,
+    pub position: Vec3,
+    /// sound level at the source
+    pub loudness: f32,
+    /// distance at which the noise fades out
+    pub range: f32,
+}
+
+impl NoiseEvent {
+    pub fn new(position: Vec3, loudness: f32, range: f32) -> Self {
+        Self {
+            source: None,
+            position,
+            loudness,
+            range,
+        }
+    }
+
+    pub fn from_entity(mut self, entity: Entity) -> Self {
+        self.source = Some(entity);
+        self
+    }
+
+    /// Sound level of the noise at `point`, falling off like a [`SoundVolume`]
+    pub fn level_at(&self, point: Vec3) -> f32 {
+        SoundVolume::new(self.loudness, self.range).sound_level(self.position.distance(point))
+    }
+}
+
+/// Background sound level drowning out quieter noises, eg: thunder
+#[derive(Resource, Default, Debug)]
+pub struct NoiseMask(pub f32);
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeardNoise {
+    pub position: Vec3,
+    pub level: f32,
+}
+
+/// Lets an entity hear [`NoiseEvent`]s louder than `threshold` (plus the [`NoiseMask`])
+#[derive(Component, Debug)]
+pub struct Hearing {
+    pub threshold: f32,
+    /// loudest noise heard this frame
+    pub heard: Option<HeardNoise>,
+}
+
+impl Hearing {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            heard: None,
+        }
+    }
+
+    /// Loudest of `noises` audible from `position` over the `mask`
+    pub fn hear<'a>(
+        &self,
+        entity: Entity,
+        position: Vec3,
+        mask: f32,
+        noises: impl IntoIterator<Item = &'a NoiseEvent>,
+    ) -> Option<HeardNoise> {
+        noises
+            .into_iter()
+            .filter(|noise| noise.source != Some(entity))
+            .map(|noise| HeardNoise {
+                position: noise.position,
+                level: noise.level_at(position),
+            })
+            .filter(|heard| heard.level > self.threshold + mask)
+            .max_by(|a, b| a.level.total_cmp(&b.level))
+    }
+}
+
+pub fn update_hearing(
+    mask: Res<NoiseMask>,
+    mut noise_events: EventReader<NoiseEvent>,
+    mut hearing_query: Query<(Entity, &mut Hearing, &GlobalTransform)>,
+) {
+    let noises: Vec<NoiseEvent> = noise_events.iter().cloned().collect();
+
+    for (entity, mut hearing, transform) in &mut hearing_query {
+        hearing.heard = hearing.hear(entity, transform.translation(), mask.0, &noises);
+    }
+}
+
+/// Makes noise every `stride` walked
+#[derive(Component, Debug)]
+pub struct Footsteps {
+    pub loudness: f32,
+    pub range: f32,
+    pub stride: f32,
+    walked: f32,
+    last_position: Option<Vec3>,
+}
+
+impl Footsteps {
+    pub fn new(loudness: f32, range: f32, stride: f32) -> Self {
+        Self {
+            loudness,
+            range,
+            stride,
+            walked: 0.,
+            last_position: None,
+        }
+    }
+}
+
+fn emit_footsteps(
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut footsteps_query: Query<(Entity, &mut Footsteps, &GlobalTransform)>,
+) {
+    for (entity, mut footsteps, transform) in &mut footsteps_query {
+        let position = transform.translation();
+
+        if let Some(last_position) = footsteps.last_position {
+            footsteps.walked += last_position.distance(position);
+        }
+        footsteps.last_position = Some(position);
+
+        if footsteps.walked >= footsteps.stride {
+            footsteps.walked = 0.;
+            noise_events.send(
+                NoiseEvent::new(position, footsteps.loudness, footsteps.range).from_entity(entity),
+            );
+        }
+    }
+}
+
+pub struct NoisePlugin;
+
+impl Plugin for NoisePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NoiseEvent>()
+            .init_resource::<NoiseMask>()
+            .add_systems(Update, (emit_footsteps, update_hearing).chain());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{MinimalPlugins, Transform};
+
+    use super::*;
+
+    #[test]
+    fn noise_falls_off_with_distance() {
+        let noise = NoiseEvent::new(Vec3::ZERO, 1., 10.);
+
+        assert_eq!(noise.level_at(Vec3::ZERO), 1.);
+        assert_eq!(noise.level_at(Vec3::new(5., 0., 0.)), 0.5);
+        assert_eq!(noise.level_at(Vec3::new(0., 0., -10.)), 0.);
+        assert_eq!(noise.level_at(Vec3::new(0., 20., 0.)), 0.);
+    }
+
+    #[test]
+    fn hears_the_loudest_noise_over_the_threshold() {
+        let (listener, other) = (Entity::from_raw(0), Entity::from_raw(1));
+        let hearing = Hearing::new(0.2);
+        let noises = [
+            NoiseEvent::new(Vec3::new(9., 0., 0.), 1., 10.),
+            NoiseEvent::new(Vec3::new(5., 0., 0.), 1., 10.).from_entity(other),
+            NoiseEvent::new(Vec3::new(2., 0., 0.), 1., 10.).from_entity(listener),
+        ];
+
+        //its own footsteps are the loudest but the listener doesn't hear itself
+        let heard = hearing.hear(listener, Vec3::ZERO, 0., &noises).unwrap();
+        assert_eq!(heard.position, Vec3::new(5., 0., 0.));
+        assert_eq!(heard.level, 0.5);
+
+        //too quiet
+        assert!(Hearing::new(0.5)
+            .hear(listener, Vec3::ZERO, 0., &noises)
+            .is_none());
+    }
+
+    #[test]
+    fn mask_drowns_out_quiet_noises() {
+        let hearing = Hearing::new(0.2);
+        let noises = [NoiseEvent::new(Vec3::new(5., 0., 0.), 1., 10.)];
+
+        let listener = Entity::from_raw(0);
+        assert!(hearing.hear(listener, Vec3::ZERO, 0.2, &noises).is_some());
+        assert!(hearing.hear(listener, Vec3::ZERO, 0.3, &noises).is_none());
+    }
+
+    #[test]
+    fn updates_hearing_from_noise_events() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, NoisePlugin));
+
+        let listener = app
+            .world
+            .spawn((
+                Hearing::new(0.2),
+                GlobalTransform::from(Transform::from_xyz(0., 0., 5.)),
+            ))
+            .id();
+        let hearing = |app: &App| app.world.get::<Hearing>(listener).unwrap().heard;
+
+        app.world.send_event(NoiseEvent::new(Vec3::ZERO, 1., 10.));
+        app.update();
+        assert_eq!(hearing(&app).unwrap().level, 0.5);
+
+        //heard for the frame the noise is made
+        app.update();
+        assert!(hearing(&app).is_none());
+
+        app.world.resource_mut::<NoiseMask>().0 = 0.5;
+        app.world.send_event(NoiseEvent::new(Vec3::ZERO, 1., 10.));
+        app.update();
+        assert!(hearing(&app).is_none());
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{
+        AmbientLight, Component, ComputedVisibility, DirectionalLight, Entity, GlobalTransform,
+        Handle, Mesh, PointLight, Query, Res, SpotLight, Vec3, With,
+    },
+};
+use bevy_mod_raycast::{
+    prelude::{Raycast, RaycastSettings, RaycastVisibility},
+    primitives::Ray3d,
+};
+
+/// Blocks line of sight for every [`VisionSensor`]
+#[derive(Component)]
+pub struct Occluder;
+
+/// Sees points inside a cone in front of the entity
+#[derive(Component, Debug, Clone)]
+pub struct VisionSensor {
+    /// cos of the half angle of the cone
+    pub cos: f32,
+    pub range: f32,
+    /// points closer than this are seen even in the dark
+    pub dark_range: f32,
+    /// light level needed to see points further than `dark_range`
+    pub min_light: f32,
+}
+
+impl VisionSensor {
+    pub fn from_angle(angle: f32) -> Self {
+        Self::from_cos(f32::cos(angle))
+    }
+    pub fn from_cos(cos: f32) -> Self {
+        Self {
+            cos,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Only sees points lit by at least `min_light` beyond `dark_range`
+    pub fn with_darkness(mut self, dark_range: f32, min_light: f32) -> Self {
+        self.dark_range = dark_range;
+        self.min_light = min_light;
+        self
+    }
+
+    /// Whether `point` is in range & inside the cone of an eye at `origin` looking at `forward`
+    pub fn in_view(&self, origin: Vec3, forward: Vec3, point: Vec3) -> bool {
+        let to_point = point - origin;
+
+        match to_point.try_normalize() {
+            Some(direction) => {
+                to_point.length() <= self.range && forward.dot(direction) >= self.cos
+            }
+            //the eye itself
+            None => true,
+        }
+    }
+
+    /// Whether a point `dist` away lit by `light_level` is bright enough to be seen
+    pub fn bright_enough(&self, dist: f32, light_level: f32) -> bool {
+        dist <= self.dark_range || light_level >= self.min_light
+    }
+}
+
+impl Default for VisionSensor {
+    fn default() -> Self {
+        Self {
+            cos: 0.25,
+            range: f32::INFINITY,
+            dark_range: f32::INFINITY,
+            min_light: 0.,
+        }
+    }
+}
+
+/// Light reaching `point` from a spot light, 1 at the light & 0 past its range or outside its cone
+pub fn spot_light_level(light: &SpotLight, transform: &GlobalTransform, point: Vec3) -> f32 {
+    let to_point = point - transform.translation();
+
+    match transform.forward().angle_between(to_point) <= light.outer_angle {
+        true => point_light_level(light.range, transform, point),
+        false => 0.,
+    }
+}
+
+/// Light reaching `point` from a light shining in every direction up to `range`
+pub fn point_light_level(range: f32, transform: &GlobalTransform, point: Vec3) -> f32 {
+    (1. - transform.translation().distance(point) / range).max(0.)
+}
+
+/// Everything needed to check what a [`VisionSensor`] sees
+#[derive(SystemParam)]
+pub struct Perception<'w, 's> {
+    raycast: Raycast<'w, 's>,
+    occluder_query: Query<'w, 's, (), (With<Occluder>, With<Handle<Mesh>>)>,
+    spot_light_query: Query<
+        'w,
+        's,
+        (
+            &'static SpotLight,
+            &'static GlobalTransform,
+            &'static ComputedVisibility,
+        ),
+    >,
+    point_light_query: Query<
+        'w,
+        's,
+        (
+            &'static PointLight,
+            &'static GlobalTransform,
+            &'static ComputedVisibility,
+        ),
+    >,
+    directional_light_query: Query<'w, 's, &'static ComputedVisibility, With<DirectionalLight>>,
+    ambient_light: Option<Res<'w, AmbientLight>>,
+}
+
+impl<'w, 's> Perception<'w, 's> {
+    /// How lit `point` is; 0 is pitch black & 1 fully lit. Light is assumed to reach the point
+    /// unblocked
+    pub fn light_level(&self, point: Vec3) -> f32 {
+        let ambient = self
+            .ambient_light
+            .as_ref()
+            .map_or(0., |ambient| ambient.brightness);
+
+        //lightning flashes light up everything
+        let directional = match self
+            .directional_light_query
+            .iter()
+            .any(|visibility| visibility.is_visible())
+        {
+            true => 1.,
+            false => 0.,
+        };
+
+        let spot: f32 = self
+            .spot_light_query
+            .iter()
+            .filter(|(_, _, visibility)| visibility.is_visible())
+            .map(|(light, transform, _)| spot_light_level(light, transform, point))
+            .sum();
+
+        let point_lights: f32 = self
+            .point_light_query
+            .iter()
+            .filter(|(_, _, visibility)| visibility.is_visible())
+            .map(|(light, transform, _)| point_light_level(light.range, transform, point))
+            .sum();
+
+        ambient + directional + spot + point_lights
+    }
+
+    /// Whether nothing blocks the line from `from` to `to`. With a `target` the line has to reach
+    /// the target's mesh before any [`Occluder`]; a line grazing past the mesh is only blocked by
+    /// occluders
+    pub fn line_of_sight(&mut self, from: Vec3, to: Vec3, target: Option<Entity>) -> bool {
+        let Some(direction) = (to - from).try_normalize() else {
+            return true;
+        };
+
+        let Self {
+            raycast,
+            occluder_query,
+            ..
+        } = self;
+
+        let settings = RaycastSettings {
+            visibility: RaycastVisibility::MustBeVisible,
+            filter: &|entity: Entity| Some(entity) == target || occluder_query.contains(entity),
+            early_exit_test: &|_| true,
+        };
+
+        match raycast
+            .cast_ray(Ray3d::new(from, direction), &settings)
+            .first()
+        {
+            Some((entity, _)) if Some(*entity) == target => true,
+            Some((_, hit)) => hit.distance() >= from.distance(to),
+            //nothing in the way, even if the target mesh was just missed
+            None => true,
+        }
+    }
+
+    /// Whether the sensor, with its eye at `origin` looking at `forward`, sees `point`
+    pub fn sees(
+        &mut self,
+        sensor: &VisionSensor,
+        origin: Vec3,
+        forward: Vec3,
+        point: Vec3,
+        target: Option<Entity>,
+    ) -> bool {
+        sensor.in_view(origin, forward, point)
+            && sensor.bright_enough(origin.distance(point), self.light_level(point))
+            && self.line_of_sight(origin, point, target)
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        schedule::{common_conditions::resource_equals, IntoSystemConfigs},
+        system::{Commands, Query, Res},
+    },
+    input::{mouse::MouseButton, Input},
+    log::warn,
+    math::Vec3,
+    prelude::DetectChanges,
+    render::camera::Camera,
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{humanoid::ragdoll::Standing, scene::nav_mesh::NavMesh};
+
+use super::{
+    controller::MovementMode,
+    fear::Fear,
+    movement::{camera_axes, walk_path, Direction, MOVE_SPEED},
+    target::PlayerTarget,
+    Controllable,
+};
+
+/// Waypoints left for the [`Controllable`] entity to walk through
+#[derive(Component, Debug)]
+pub struct NavPath(pub VecDeque<Vec3>);
+
+/// Paths a route across the nav mesh to the clicked [`super::target::PlayerTargetSet`] surface
+fn request_path(
+    mut commands: Commands,
+    mouse_input: Res<Input<MouseButton>>,
+    target: Res<PlayerTarget>,
+    nav_mesh: Res<NavMesh>,
+    player_query: Query<(Entity, &GlobalTransform), With<Controllable>>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let PlayerTarget(Some((_, hit))) = target.as_ref() else {
+        return;
+    };
+
+    for (entity, transform) in &player_query {
+        let Some(path) = nav_mesh.find_path(transform.translation(), hit.position()) else {
+            warn!("no nav mesh path to {}", hit.position());
+            continue;
+        };
+
+        //first point is where the player already is
+        commands
+            .entity(entity)
+            .insert(NavPath(path.into_iter().skip(1).collect()));
+    }
+}
+
+/// [`Controllable`] entities walking a [`NavPath`], limp ones stop
+type PathWalkers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Direction,
+        &'static mut NavPath,
+    ),
+    (With<Controllable>, Standing),
+>;
+
+/// Walks along the [`NavPath`] over the nav mesh, keeping [`Direction`] in sync so the body & legs
+/// react like they do for keyboard input
+fn follow_path(
+    mut commands: Commands,
+    time: Res<Time>,
+    fear: Res<Fear>,
+    nav_mesh: Res<NavMesh>,
+    camera_query: Query<&Transform, (With<Camera>, Without<Controllable>)>,
+    mut player_query: PathWalkers,
+) {
+    for (entity, mut transform, mut direction, mut path) in &mut player_query {
+        let transform = transform.as_mut();
+        let heading = walk_path(
+            &nav_mesh,
+            &mut transform.translation,
+            &mut path.0,
+            time.delta_seconds() * MOVE_SPEED * fear.speed(),
+        );
+
+        match path.0.is_empty() {
+            true => {
+                direction.0 = Vec3::ZERO;
+                commands.entity(entity).remove::<NavPath>();
+            }
+            false => {
+                let (local_x, local_z) = camera_axes(camera_query.iter().next(), transform);
+                direction.0 = Vec3::new(heading.dot(local_x), 0., heading.dot(local_z));
+            }
+        }
+    }
+}
+
+/// Drops any path in progress when switching back to keyboard movement
+fn clear_paths(
+    mut commands: Commands,
+    mode: Res<MovementMode>,
+    mut player_query: Query<(Entity, &mut Direction), With<NavPath>>,
+) {
+    if !mode.is_changed() || *mode == MovementMode::ClickToMove {
+        return;
+    }
+
+    for (entity, mut direction) in &mut player_query {
+        direction.0 = Vec3::ZERO;
+        commands.entity(entity).remove::<NavPath>();
+    }
+}
+
+pub struct ClickToMovePlugin;
+
+impl Plugin for ClickToMovePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(
+            Update,
+            (
+                clear_paths,
+                (request_path, follow_path)
+                    .chain()
+                    .run_if(resource_equals(MovementMode::ClickToMove)),
+            )
+                .chain(),
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use bevy::{
     app::{Plugin, Startup, Update},
-    ecs::system::{Commands, Res, ResMut, Resource},
+    ecs::{
+        schedule::IntoSystemConfigs,
+        system::{Commands, Res, ResMut, Resource},
+    },
     input::{keyboard::KeyCode, Input},
 };
 
@@ -45,6 +48,36 @@ impl Default for MovementInput {
     }
 }
 
+/// How the [`super::Controllable`] entity is driven
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MovementMode {
+    /// WASD through [`MovementInput`]
+    #[default]
+    Keyboard,
+    /// walks along a nav mesh path to the clicked [`super::target::PlayerTargetSet`] surface
+    ClickToMove,
+}
+
+pub const TOGGLE_MOVEMENT_MODE: KeyCode = KeyCode::Tab;
+pub const KICK: KeyCode = KeyCode::F;
+pub const INTERACT: KeyCode = KeyCode::E;
+pub const TOGGLE_FLASHLIGHT: KeyCode = KeyCode::T;
+pub const EQUIP_KEYS: [KeyCode; 4] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+pub const QUICK_SAVE: KeyCode = KeyCode::F5;
+pub const QUICK_LOAD: KeyCode = KeyCode::F9;
+pub const TOGGLE_IK_GIZMOS: KeyCode = KeyCode::F3;
+
+fn toggle_movement_mode(keyboard_input: Res<Input<KeyCode>>, mut mode: ResMut<MovementMode>) {
+    if !keyboard_input.just_pressed(TOGGLE_MOVEMENT_MODE) {
+        return;
+    }
+
+    *mode = match *mode {
+        MovementMode::Keyboard => MovementMode::ClickToMove,
+        MovementMode::ClickToMove => MovementMode::Keyboard,
+    };
+}
+
 fn key_board_input(
     keyboard_input: Res<Input<KeyCode>>,
 
@@ -103,7 +136,8 @@ impl ControllerPlugin {
 
 impl Plugin for ControllerPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, (ControllerPlugin::start,))
-            .add_systems(Update, (key_board_input,));
+        app.init_resource::<MovementMode>()
+            .add_systems(Startup, (ControllerPlugin::start,))
+            .add_systems(Update, (toggle_movement_mode, key_board_input).chain());
     }
 }
@@ -2,20 +2,34 @@ use std::f32::consts::PI;
 
 use bevy::{
     core_pipeline::clear_color::ClearColorConfig,
-    ecs::{component::Component, event::EventWriter},
+    ecs::{
+        component::Component,
+        event::{EventReader, EventWriter},
+    },
     prelude::{
-        default, AssetServer, Assets, Camera3d, Camera3dBundle, Color, Commands, Quat, Res, ResMut,
-        SpotLight, SpotLightBundle, StandardMaterial, Transform, Vec3,
+        default, AssetServer, Camera3d, Camera3dBundle, Color, Commands, Query, Res, SpatialBundle,
+        Transform, Vec3, With,
     },
-    render::mesh::skinning::SkinnedMeshInverseBindposes,
 };
 use bevy_mod_raycast::prelude::RaycastPluginState;
+use rand::{rngs::SmallRng, SeedableRng};
 
-use crate::{humanoid::load_humanoid, scene::prop::PropVisibilitySource};
+use crate::{
+    humanoid::{
+        animation::BoneOverrides,
+        asset::{humanoid_path, HumanoidSpawnedEvent, SpawnHumanoid},
+        Humanoid,
+    },
+    noise::Footsteps,
+    perception::VisionSensor,
+    scene::prop::physics::Pusher,
+};
 
 use super::{
+    flashlight::FlashlightBattery,
     follow::{Coord, Follow, FollowTarget},
-    ik::LegInitializeEvent,
+    ik::{ArmInitializeEvent, LegInitializeEvent},
+    inventory::{EquipEvent, Equipment, Inventory},
     movement,
     target::{PlayerTarget, PlayerTargetSet},
     Controllable,
@@ -24,33 +38,38 @@ use super::{
 #[derive(Component)]
 pub struct Player;
 
-pub fn create_player(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+/// Item the player holds from the start
+const FLASHLIGHT: &str = "flashlight";
+/// Seed of the flashlight's flicker pattern
+const FLICKER_SEED: u64 = 0x5eed;
+/// Half angle of the player's vision cone when calm
+pub const SIGHT_ANGLE: f32 = PI / 4.;
+/// Props closer than this are seen without light
+const DARK_SIGHT: f32 = 1.;
+/// Light level props need to be seen further away, so they're revealed by the flashlight, other
+/// lights & lightning
+const SIGHT_LIGHT: f32 = 0.1;
 
-    mut ik_set_up_event: EventWriter<LegInitializeEvent>,
-) {
+pub fn create_player(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(RaycastPluginState::<PlayerTargetSet>::default());
 
     commands.insert_resource(PlayerTarget(None));
 
-    //load mesh
-    let (player, humanoid) = load_humanoid(
-        "character\\mesh\\character.gltf",
-        &mut commands,
-        &asset_server,
-        &mut materials,
-        &mut inverse_bindposes,
-    )
-    .unwrap();
-
-    ik_set_up_event.send(LegInitializeEvent(player));
-
-    commands
-        .entity(player)
-        .insert((Controllable, movement::Direction(Vec3::ZERO), Player));
+    //the rig is spawned once its asset is loaded
+    let player = commands
+        .spawn((
+            SpatialBundle::default(),
+            SpawnHumanoid(asset_server.load(humanoid_path("character"))),
+            Controllable,
+            movement::Direction(Vec3::ZERO),
+            Player,
+            Footsteps::new(1., 12., 0.8),
+            Pusher::new(0.3, 1.8),
+            Inventory(vec![FLASHLIGHT.into()]),
+            Equipment::default(),
+            FlashlightBattery::new(SmallRng::seed_from_u64(FLICKER_SEED)),
+        ))
+        .id();
 
     //followable camera
     let camera_and_light_transform = Transform::from_xyz(0., 0., 10.).looking_to(
@@ -92,43 +111,43 @@ pub fn create_player(
             },
         })),
     ));
-    commands
-        .entity(humanoid.head)
-        .insert((PropVisibilitySource::from_angle(PI / 4.),));
-
-    commands.spawn((
-        SpotLightBundle {
-            spot_light: SpotLight {
-                intensity: 2500.,
-                range: 20.,
-                shadows_enabled: true,
-                //inner_angle: PI / 10.0,
-                outer_angle: PI / 10.0,
-                // radius: todo!(),
-                // shadows_enabled: todo!(),
-                // shadow_depth_bias: todo!(),
-                // shadow_normal_bias: todo!(),
-                // outer_angle: todo!(),
-                // inner_angle: todo!(),
-                ..default()
-            },
-            transform: Transform {
-                rotation: Quat::from_rotation_x(PI / 2.),
-                ..default()
-            },
-            ..default()
-        },
-        Follow(Some(FollowTarget {
-            target: humanoid.right_arm.2,
-            offset: Coord::Cartesian {
-                x: 0.,
-                y: 0.5,
-                z: 0.,
-            },
-        })),
-    ));
     //create camera
     //camera follows controllable
 
     // commands.spawn((..Components));
 }
+
+/// Sets up the player's rig once spawned: procedural limbs, vision & the flashlight in hand
+pub fn set_up_player_humanoid(
+    mut commands: Commands,
+    mut spawned_event: EventReader<HumanoidSpawnedEvent>,
+    player_query: Query<&Humanoid, With<Player>>,
+
+    mut ik_set_up_event: EventWriter<LegInitializeEvent>,
+    mut arm_set_up_event: EventWriter<ArmInitializeEvent>,
+    mut equip_event: EventWriter<EquipEvent>,
+) {
+    for HumanoidSpawnedEvent(player) in spawned_event.iter() {
+        let Ok(humanoid) = player_query.get(*player) else {
+            continue;
+        };
+
+        ik_set_up_event.send(LegInitializeEvent(*player));
+        arm_set_up_event.send(ArmInitializeEvent(*player));
+
+        //head looks at & body turns to the target, the body bobs with the gait
+        commands
+            .entity(*player)
+            .insert(BoneOverrides([humanoid.head, humanoid.body].into()));
+
+        commands.entity(humanoid.head).insert((
+            VisionSensor::from_angle(SIGHT_ANGLE).with_darkness(DARK_SIGHT, SIGHT_LIGHT),
+        ));
+
+        equip_event.send(EquipEvent {
+            holder: *player,
+            item: FLASHLIGHT.into(),
+            hand: None,
+        });
+    }
+}
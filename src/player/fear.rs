@@ -0,0 +1,318 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    audio::{AddAudioSource, AudioSinkPlayback, Decodable, Source, Volume},
+    ecs::{component::Component, system::Resource},
+    prelude::{
+        App, Assets, AudioSink, AudioSourceBundle, Camera, Commands, EulerRot, EventReader,
+        GlobalTransform, IntoSystemConfigs, Local, PlaybackSettings, Plugin, PostUpdate, Quat,
+        Query, Res, ResMut, Startup, Transform, Update, Vec3, With,
+    },
+    reflect::{TypePath, TypeUuid},
+    time::Time,
+    transform::TransformSystem,
+};
+
+use crate::{
+    cryptid::Cryptid,
+    humanoid::Humanoid,
+    lightning::{Lightning, ScaryState, MAX_THUNDER_DELAY},
+    noise::NoiseEvent,
+    perception::{Perception, VisionSensor},
+};
+
+use super::{create::SIGHT_ANGLE, Controllable};
+
+/// Distance in front of the eyes whose light calms the player
+const LOOK_DISTANCE: f32 = 1.5;
+/// Light level under which the dark scares the player
+const DARK_LIGHT: f32 = 0.1;
+/// Fear gained per second in pitch black
+const DARK_RATE: f32 = 0.02;
+/// Fear lost per second with the surroundings lit
+const CALM_RATE: f32 = 0.05;
+/// Fear gained per second while the cryptid is in sight
+const SEEN_RATE: f32 = 0.4;
+/// Fear gained per unit of sound level of each cryptid noise heard
+const HEARD_FEAR: f32 = 0.05;
+/// Quietest cryptid noise the player notices
+const HEARING_THRESHOLD: f32 = 0.1;
+/// Fear gained per second while the closest thunder rumbles
+const THUNDER_RATE: f32 = 0.15;
+/// Seconds of thunder rumble that scare the player
+const THUNDER_FEAR_TIME: f32 = 3.;
+/// Height above the cryptid's feet the player has to see
+const CRYPTID_HEIGHT: f32 = 1.;
+/// Vision cone angle at full fear, relative to calm
+const NARROW_SIGHT: f32 = 0.5;
+/// Walking speed at full fear, relative to calm
+const FEARFUL_SPEED: f32 = 0.6;
+/// Fear under which the heart can't be heard
+const HEARTBEAT_FEAR: f32 = 0.2;
+const HEARTBEAT_VOLUME: f32 = 0.8;
+/// Heart rate at full fear, relative to the resting 60 beats a minute
+const FEARFUL_HEART_RATE: f32 = 2.2;
+/// Radians the camera sways by at full fear
+const MAX_SWAY: f32 = 0.015;
+
+/// How scared the player is, 0 calm & 1 terrified
+#[derive(Resource, Default, Debug)]
+pub struct Fear(pub f32);
+
+impl Fear {
+    fn add(&mut self, fear: f32) {
+        self.0 = (self.0 + fear).clamp(0., 1.);
+    }
+
+    /// Walking speed relative to calm
+    pub fn speed(&self) -> f32 {
+        1. - self.0 * (1. - FEARFUL_SPEED)
+    }
+}
+
+/// Procedural lub-dub heartbeat at 60 beats a minute, sped up with the [`Fear`]
+#[derive(TypeUuid, TypePath, Debug, Clone, Copy)]
+#[uuid = "3b7e2f91-c4d8-4a5e-9f16-8d0a7c5b2e49"]
+pub struct Heartbeat;
+
+pub struct HeartbeatDecoder {
+    sample: u32,
+}
+
+impl HeartbeatDecoder {
+    const SAMPLE_RATE: u32 = 22_050;
+    const BEAT_TIME: f32 = 1.;
+    /// seconds from lub to dub
+    const DUB_TIME: f32 = 0.3;
+
+    /// Low thump `time` seconds after it starts
+    fn thump(time: f32) -> f32 {
+        match (0. ..0.15).contains(&time) {
+            true => f32::sin(2. * PI * 55. * time) * f32::exp(-25. * time),
+            false => 0.,
+        }
+    }
+}
+
+impl Iterator for HeartbeatDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let time = (self.sample as f32 / Self::SAMPLE_RATE as f32) % Self::BEAT_TIME;
+        self.sample = (self.sample + 1) % (Self::SAMPLE_RATE * Self::BEAT_TIME as u32);
+
+        Some(Self::thump(time) + 0.7 * Self::thump(time - Self::DUB_TIME))
+    }
+}
+
+impl Source for HeartbeatDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        Self::SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+impl Decodable for Heartbeat {
+    type DecoderItem = f32;
+    type Decoder = HeartbeatDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        HeartbeatDecoder { sample: 0 }
+    }
+}
+
+/// The player's heartbeat
+#[derive(Component)]
+struct HeartbeatSound;
+
+fn create_heartbeat(mut commands: Commands, mut heartbeats: ResMut<Assets<Heartbeat>>) {
+    commands.spawn((
+        AudioSourceBundle {
+            source: heartbeats.add(Heartbeat),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new_relative(0.)),
+        },
+        HeartbeatSound,
+    ));
+}
+
+/// Eyes of the [`Controllable`] player
+type PlayerEyes<'w, 's> = Query<'w, 's, &'static Humanoid, With<Controllable>>;
+
+/// The dark ahead scares the player, light calms them
+fn fear_the_dark(
+    time: Res<Time>,
+    mut fear: ResMut<Fear>,
+    player_query: PlayerEyes,
+    head_query: Query<&GlobalTransform>,
+    perception: Perception,
+) {
+    let Some(head) = player_query
+        .iter()
+        .next()
+        .and_then(|humanoid| head_query.get(humanoid.head).ok())
+    else {
+        return;
+    };
+
+    let ahead = head.translation() + head.forward() * LOOK_DISTANCE;
+    let darkness = 1. - (perception.light_level(ahead) / DARK_LIGHT).min(1.);
+
+    fear.add(time.delta_seconds() * (darkness * DARK_RATE - (1. - darkness) * CALM_RATE));
+}
+
+/// Seeing or hearing the cryptid scares the player
+fn fear_the_cryptid(
+    time: Res<Time>,
+    mut fear: ResMut<Fear>,
+    player_query: PlayerEyes,
+    head_query: Query<(&GlobalTransform, &VisionSensor)>,
+    cryptid_query: Query<&GlobalTransform, With<Cryptid>>,
+    mut noise_events: EventReader<NoiseEvent>,
+    mut perception: Perception,
+) {
+    let Some((head, sensor)) = player_query
+        .iter()
+        .next()
+        .and_then(|humanoid| head_query.get(humanoid.head).ok())
+    else {
+        return;
+    };
+    let eye = head.translation();
+
+    let seen = cryptid_query.iter().any(|cryptid| {
+        let point = cryptid.translation() + Vec3::Y * CRYPTID_HEIGHT;
+
+        perception.sees(sensor, eye, head.forward(), point, None)
+    });
+    if seen {
+        fear.add(time.delta_seconds() * SEEN_RATE);
+    }
+
+    let heard: f32 = noise_events
+        .iter()
+        .filter(|noise| {
+            noise
+                .source
+                .is_some_and(|source| cryptid_query.contains(source))
+        })
+        .map(|noise| noise.level_at(eye))
+        .filter(|level| *level > HEARING_THRESHOLD)
+        .sum();
+    fear.add(heard * HEARD_FEAR);
+}
+
+/// Thunder scares the player, more so the closer it strikes. Close thunder follows its lightning
+/// flash quickly
+fn fear_thunder(
+    time: Res<Time>,
+    mut fear: ResMut<Fear>,
+    lightning_query: Query<&Lightning>,
+    mut delay: Local<f32>,
+) {
+    for lightning in &lightning_query {
+        match lightning {
+            Lightning::Scary {
+                state: ScaryState::Wait(timer),
+                ..
+            } => *delay = timer.duration().as_secs_f32(),
+            Lightning::Scary {
+                state: ScaryState::Thunder(timer),
+                ..
+            } if timer.elapsed_secs() < THUNDER_FEAR_TIME => {
+                let closeness = 1. - (*delay / MAX_THUNDER_DELAY).min(1.);
+
+                fear.add(time.delta_seconds() * THUNDER_RATE * closeness);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fear narrows the player's vision cone
+fn narrow_sight(
+    fear: Res<Fear>,
+    player_query: PlayerEyes,
+    mut sensor_query: Query<&mut VisionSensor>,
+) {
+    for humanoid in &player_query {
+        let Ok(mut sensor) = sensor_query.get_mut(humanoid.head) else {
+            continue;
+        };
+
+        let cos = f32::cos(SIGHT_ANGLE * (1. - fear.0 * (1. - NARROW_SIGHT)));
+        if sensor.cos != cos {
+            sensor.cos = cos;
+        }
+    }
+}
+
+/// The heart beats louder & faster with fear
+fn beat_heart(fear: Res<Fear>, sink_query: Query<&AudioSink, With<HeartbeatSound>>) {
+    let volume = ((fear.0 - HEARTBEAT_FEAR) / (1. - HEARTBEAT_FEAR)).max(0.) * HEARTBEAT_VOLUME;
+    let speed = 1. + fear.0 * (FEARFUL_HEART_RATE - 1.);
+
+    for sink in &sink_query {
+        sink.set_volume(volume);
+        sink.set_speed(speed);
+    }
+}
+
+/// Fear sways the camera. Runs after the camera is placed & aimed for the frame
+fn sway_camera(
+    time: Res<Time>,
+    fear: Res<Fear>,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+) {
+    let sway = fear.0 * fear.0 * MAX_SWAY;
+    if sway <= 0. {
+        return;
+    }
+
+    let time = time.elapsed_seconds();
+    let rotation = Quat::from_euler(
+        EulerRot::XYZ,
+        f32::sin(time * 1.3) * sway,
+        f32::sin(time * 0.9 + 1.) * sway,
+        0.,
+    );
+
+    for mut transform in &mut camera_query {
+        transform.rotation *= rotation;
+    }
+}
+
+pub struct FearPlugin;
+
+impl Plugin for FearPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Fear>()
+            .add_audio_source::<Heartbeat>()
+            .add_systems(Startup, create_heartbeat)
+            .add_systems(
+                Update,
+                (
+                    fear_the_dark,
+                    fear_the_cryptid,
+                    fear_thunder,
+                    narrow_sight,
+                    beat_heart,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                sway_camera.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
@@ -0,0 +1,297 @@
+use std::{collections::HashSet, time::Duration};
+
+use bevy::{
+    ecs::component::Component,
+    log::info,
+    prelude::{
+        App, Assets, Input, IntoSystemConfigs, KeyCode, Plugin, Query, Res, SpotLight, Update,
+        Visibility, With,
+    },
+    time::{Time, Timer, TimerMode},
+};
+use rand::{rngs::SmallRng, Rng};
+
+use super::{
+    controller::TOGGLE_FLASHLIGHT,
+    inventory::{
+        equip_items, pick_up_items, Flashlight, HeldItem, Inventory, ItemDefinitions, ItemKind,
+        ItemLibrary,
+    },
+    Controllable,
+};
+
+/// Seconds a full battery lasts
+const DRAIN_TIME: f32 = 300.;
+/// Charge under which the light dims & flickers
+const LOW_CHARGE: f32 = 0.2;
+/// Brightness of the light on an empty battery, relative to full
+const DIM_INTENSITY: f32 = 0.3;
+/// Cone angle of the light on an empty battery, relative to full
+const DIM_ANGLE: f32 = 0.5;
+/// Longest blackout of a flicker, in seconds
+const FLICKER_OFF_TIME: f32 = 0.12;
+/// Longest time lit between flickers on an empty battery, in seconds. Longer on fuller ones
+const FLICKER_ON_TIME: f32 = 0.3;
+
+/// Switch & charge of the flashlight carried by the entity
+#[derive(Component, Debug)]
+pub struct FlashlightBattery {
+    pub on: bool,
+    /// 1 when full, the light dies at 0
+    pub charge: f32,
+    /// blacked out by a flicker
+    flickering: bool,
+    flicker: Timer,
+    rng: SmallRng,
+}
+
+impl FlashlightBattery {
+    /// Full & switched on, flickering as `rng` decides
+    pub fn new(rng: SmallRng) -> Self {
+        Self {
+            on: true,
+            charge: 1.,
+            flickering: false,
+            flicker: Timer::default(),
+            rng,
+        }
+    }
+
+    /// Whether the light is shining
+    pub fn lit(&self) -> bool {
+        self.on && self.charge > 0. && !self.flickering
+    }
+
+    /// How far the battery is drained into its low charge, 0 above [`LOW_CHARGE`] & 1 when empty
+    pub fn dimness(&self) -> f32 {
+        (1. - self.charge / LOW_CHARGE).clamp(0., 1.)
+    }
+
+    pub fn recharge(&mut self, charge: f32) {
+        self.charge = (self.charge + charge).min(1.);
+    }
+
+    /// Drains the battery by `delta` seconds of light & flickers it when low
+    fn drain(&mut self, delta: f32) {
+        if !self.on {
+            return;
+        }
+
+        self.charge = (self.charge - delta / DRAIN_TIME).max(0.);
+
+        if self.dimness() <= 0. {
+            self.flickering = false;
+            return;
+        }
+
+        self.flicker.tick(Duration::from_secs_f32(delta));
+        if !self.flicker.finished() {
+            return;
+        }
+
+        //blackouts get more frequent as the battery empties
+        self.flickering = !self.flickering;
+        let duration = match self.flickering {
+            true => self.rng.gen_range(0.02..FLICKER_OFF_TIME),
+            false => {
+                let longest = FLICKER_ON_TIME + 2. * (1. - self.dimness());
+                self.rng.gen_range(0.05..longest)
+            }
+        };
+        self.flicker = Timer::from_seconds(duration, TimerMode::Once);
+    }
+}
+
+fn toggle_flashlight(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut battery_query: Query<&mut FlashlightBattery, With<Controllable>>,
+) {
+    if !keyboard_input.just_pressed(TOGGLE_FLASHLIGHT) {
+        return;
+    }
+
+    for mut battery in &mut battery_query {
+        battery.on = !battery.on;
+    }
+}
+
+/// Drains the batteries of entities holding a flashlight, stowed ones keep their charge
+fn drain_batteries(
+    time: Res<Time>,
+    flashlight_query: Query<&HeldItem, With<Flashlight>>,
+    mut battery_query: Query<&mut FlashlightBattery>,
+) {
+    let holders: HashSet<_> = flashlight_query.iter().map(|held| held.holder).collect();
+
+    for holder in holders {
+        if let Ok(mut battery) = battery_query.get_mut(holder) {
+            battery.drain(time.delta_seconds());
+        }
+    }
+}
+
+/// Uses up battery items as soon as they're picked up
+fn use_batteries(
+    library: Res<ItemLibrary>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    mut holder_query: Query<(&mut Inventory, &mut FlashlightBattery)>,
+) {
+    let Some(definitions) = definitions.get(&library.0) else {
+        return;
+    };
+
+    for (mut inventory, mut battery) in &mut holder_query {
+        inventory.0.retain(|item| {
+            let Some(ItemKind::Battery(charge)) = definitions
+                .items
+                .get(item)
+                .map(|definition| &definition.kind)
+            else {
+                return true;
+            };
+
+            info!("recharged the flashlight by {charge}");
+            battery.recharge(*charge);
+            false
+        });
+    }
+}
+
+/// Switches held flashlights on & off, dimming & narrowing the beam on a low battery. Unlit
+/// flashlights are hidden & put out so they light up nothing, for the renderer, perception & the
+/// cryptid alike
+fn update_flashlight_beam(
+    battery_query: Query<&FlashlightBattery>,
+    mut flashlight_query: Query<(&mut SpotLight, &mut Visibility, &Flashlight, &HeldItem)>,
+) {
+    for (mut light, mut visibility, flashlight, held) in &mut flashlight_query {
+        let Ok(battery) = battery_query.get(held.holder) else {
+            continue;
+        };
+
+        let new_visibility = match battery.lit() {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+
+        let dimness = battery.dimness();
+        light.intensity = match battery.lit() {
+            true => flashlight.intensity * (1. - dimness * (1. - DIM_INTENSITY)),
+            false => 0.,
+        };
+        light.outer_angle = flashlight.angle * (1. - dimness * (1. - DIM_ANGLE));
+    }
+}
+
+pub struct FlashlightPlugin;
+
+impl Plugin for FlashlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_flashlight,
+                drain_batteries,
+                use_batteries.after(pick_up_items),
+                update_flashlight_beam.after(equip_items),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn battery(charge: f32) -> FlashlightBattery {
+        let mut battery = FlashlightBattery::new(SmallRng::seed_from_u64(0));
+        battery.charge = charge;
+        battery
+    }
+
+    #[test]
+    fn drains_only_while_on() {
+        let mut battery = battery(1.);
+
+        battery.drain(DRAIN_TIME / 4.);
+        assert!((battery.charge - 0.75).abs() < 1e-5);
+
+        battery.on = false;
+        battery.drain(DRAIN_TIME / 4.);
+        assert!((battery.charge - 0.75).abs() < 1e-5);
+        assert!(!battery.lit());
+
+        battery.on = true;
+        battery.drain(DRAIN_TIME);
+        assert_eq!(battery.charge, 0.);
+        assert!(!battery.lit());
+    }
+
+    #[test]
+    fn flickers_only_when_low() {
+        let flickers = |charge: f32| {
+            let mut battery = battery(charge);
+            (0..600).any(|_| {
+                battery.drain(1. / 60.);
+                !battery.lit()
+            })
+        };
+
+        assert!(!flickers(1.));
+        assert!(flickers(LOW_CHARGE / 2.));
+    }
+
+    #[test]
+    fn recharging_relights_a_dead_battery() {
+        let mut battery = battery(0.);
+        assert!(!battery.lit());
+        assert_eq!(battery.dimness(), 1.);
+
+        battery.recharge(0.5);
+        assert_eq!(battery.charge, 0.5);
+        assert!(battery.lit());
+        assert_eq!(battery.dimness(), 0.);
+
+        battery.recharge(0.8);
+        assert_eq!(battery.charge, 1.);
+    }
+
+    #[test]
+    fn only_held_flashlights_drain() {
+        let mut app = App::new();
+        let start = Instant::now();
+        app.insert_resource(Time::new(start))
+            .add_systems(Update, drain_batteries);
+
+        let holder = app.world.spawn(battery(1.)).id();
+        let tick = |app: &mut App, seconds: f32| {
+            app.world
+                .resource_mut::<Time>()
+                .update_with_instant(start + Duration::from_secs_f32(seconds));
+            app.update();
+            app.world.get::<FlashlightBattery>(holder).unwrap().charge
+        };
+
+        assert_eq!(tick(&mut app, DRAIN_TIME / 4.), 1.);
+
+        app.world.spawn((
+            Flashlight {
+                intensity: 1.,
+                angle: 1.,
+            },
+            HeldItem {
+                holder,
+                item: "flashlight".into(),
+            },
+        ));
+        assert!((tick(&mut app, DRAIN_TIME / 2.) - 0.75).abs() < 1e-5);
+    }
+}
@@ -1,86 +1,336 @@
-use std::{f32::consts::PI, mem};
+use std::f32::consts::PI;
 
 use bevy::{
     app::{Plugin, Update},
-    asset::{Assets, Handle},
     ecs::{
         component::Component,
         entity::Entity,
         event::{Event, EventReader},
-        query::{ReadOnlyWorldQuery, Without},
-        system::Commands,
+        schedule::common_conditions::resource_equals,
+        system::{Commands, ResMut, Resource},
     },
-    prelude::{Color, EulerRot, Gizmos, GlobalTransform, Quat, Query, Res, Transform, Vec3, With},
-    render::{
-        camera::Camera,
-        mesh::{Indices, Mesh},
+    hierarchy::Parent,
+    prelude::{
+        Color, EulerRot, Gizmos, GlobalTransform, Input, IntoSystemConfigs, KeyCode, Quat, Query,
+        Res, Transform, Vec3, With, Without,
     },
     time::Time,
 };
-use bevy_mod_raycast::{
-    immediate::{Raycast, RaycastSettings, RaycastVisibility},
-    primitives::Ray3d,
-};
-
-use crate::{humanoid::Humanoid, ik::IKChain, scene::nav_mesh::NavMesh};
 
-use super::{create::Player, movement::Direction, target::PlayerTarget, Controllable};
+use crate::{
+    humanoid::{Humanoid, Limb},
+    ik::{global_transform, IKChain, IKChainConstrains, IKConstraint, IKGoal, IKSettings},
+    scene::nav_mesh::{flat_distance, NavMesh},
+};
 
-pub struct IKPlugin;
+use super::{
+    controller::TOGGLE_IK_GIZMOS, interaction::Interacting, target::PlayerTarget, Controllable,
+};
 
-impl Plugin for IKPlugin {
+/// Distance a planted foot drifts from under the hips before it steps
+const STRIDE: f32 = 0.45;
+/// Stride while standing still, so feet settle back under the hips
+const IDLE_STRIDE: f32 = 0.08;
+/// Seconds a step takes
+const STEP_TIME: f32 = 0.25;
+/// How high feet lift mid step
+const STEP_HEIGHT: f32 = 0.15;
+/// Seconds of movement the stepping foot lands ahead of the hips
+const STEP_LEAD: f32 = 0.2;
+/// How much the pelvis rises mid step
+const PELVIS_BOB: f32 = 0.04;
+/// Height of the ankle above the ground
+const FOOT_HEIGHT: f32 = 0.1;
+/// Slower than this counts as standing still
+const MOVING_SPEED: f32 = 0.1;
+
+/// Furthest a knee or elbow bends
+const LIMB_BEND: f32 = PI * 0.8;
+
+/// Furthest the arm aims away from where the body faces
+const MAX_AIM_ANGLE: f32 = PI / 2.;
+/// How fast the arm follows the target
+const AIM_SPEED: f32 = 10.;
+/// Fraction of the arm's reach the hand is held at, so the elbow stays bent
+const HOLD_REACH: f32 = 0.85;
+/// Gap between the hands on a two handed hold
+const GRIP_GAP: f32 = 0.08;
+
+/// Whether the feet targets & body directions are drawn, see [`TOGGLE_IK_GIZMOS`]
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IKGizmos(pub bool);
+
+pub struct HumanoidIKPlugin;
+
+impl Plugin for HumanoidIKPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_event::<LegInitializeEvent>()
-            .add_systems(Update, initialize_player_leg_ik)
-            .add_systems(Update, (update_head_dir, update_body_dir, update_legs));
+        app.init_resource::<IKGizmos>()
+            .add_event::<LegInitializeEvent>()
+            .add_event::<ArmInitializeEvent>()
+            .add_systems(Update, (initialize_leg_ik, initialize_arm_ik))
+            .add_systems(
+                Update,
+                (
+                    update_head_dir,
+                    update_body_dir,
+                    update_legs.after(initialize_leg_ik),
+                    update_arm_aim.after(initialize_arm_ik),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_ik_gizmos,
+                    draw_ik_gizmos
+                        .after(update_body_dir)
+                        .after(update_legs)
+                        .run_if(resource_equals(IKGizmos(true))),
+                )
+                    .chain(),
+            );
     }
 }
 
+/// Constraints of `limb`: free at its root & a hinge at its knee or elbow, bending the way it's
+/// bent now or towards `hint` if the limb is straight
+fn limb_constraints(
+    limb: &Limb,
+    hint: Vec3,
+    parent_query: &Query<&Parent>,
+    transform_query: &Query<&Transform>,
+) -> IKChainConstrains {
+    let global = |entity| global_transform(entity, parent_query, transform_query);
+    let (root, mid, end) = (global(limb.0), global(limb.1), global(limb.2));
+    let dir = |from: &GlobalTransform, to: &GlobalTransform| {
+        (to.translation() - from.translation())
+            .try_normalize()
+            .unwrap_or(Vec3::NEG_Y)
+    };
+
+    IKChainConstrains(vec![
+        IKConstraint::None,
+        IKConstraint::bent_hinge(
+            root.compute_transform().rotation,
+            dir(&root, &mid),
+            dir(&mid, &end),
+            hint,
+            LIMB_BEND,
+        ),
+    ])
+}
+
+/// Sets up procedural walking for the [`Humanoid`] entity
 #[derive(Event)]
 pub struct LegInitializeEvent(pub Entity);
 
-pub fn initialize_player_leg_ik(
+pub fn initialize_leg_ik(
     mut commands: Commands,
     mut ik_set_up_event: EventReader<LegInitializeEvent>,
-    player_query: Query<&Humanoid, With<Player>>,
-    transform_query: Query<&GlobalTransform, Without<Humanoid>>,
+    humanoid_query: Query<&Humanoid>,
+    parent_query: Query<&Parent>,
+    transform_query: Query<&Transform>,
 ) {
     for &LegInitializeEvent(entity) in ik_set_up_event.iter() {
-        let humanoid = player_query.get(entity).unwrap();
+        let Ok(humanoid) = humanoid_query.get(entity) else {
+            continue;
+        };
+
+        let position = |entity| global_transform(entity, &parent_query, &transform_query);
+        //knees bend backwards
+        let hint = -position(entity).forward();
+
+        for leg in [&humanoid.left_leg, &humanoid.right_leg] {
+            commands.entity(leg.0).insert((
+                IKChain::from_hierarchy(leg.iter(), &parent_query, &transform_query),
+                limb_constraints(leg, hint, &parent_query, &transform_query),
+                IKGoal(position(leg.2).translation()),
+                IKSettings::default(),
+            ));
+        }
+
+        let leg_length = |leg: &Limb| {
+            let (hip, knee, ankle) = (
+                position(leg.0).translation(),
+                position(leg.1).translation(),
+                position(leg.2).translation(),
+            );
 
-        let (_, _, left_leg, right_leg) = humanoid.get_iks(&transform_query);
+            hip.distance(knee) + knee.distance(ankle)
+        };
 
         commands.entity(entity).insert(HumanoidFeetTarget::new(
-            left_leg,
-            right_leg,
-            &transform_query,
+            position(humanoid.left_leg.2).translation(),
+            position(humanoid.right_leg.2).translation(),
+            position(humanoid.left_leg.0)
+                .translation()
+                .distance(position(humanoid.right_leg.0).translation())
+                / 2.,
+            leg_length(&humanoid.left_leg),
+            transform_query
+                .get(humanoid.body)
+                .map_or(Vec3::ZERO, |body| body.translation),
         ));
     }
 }
 
+/// Sets up the arms of the [`Humanoid`] entity to aim the flashlight
+#[derive(Event)]
+pub struct ArmInitializeEvent(pub Entity);
+
+pub fn initialize_arm_ik(
+    mut commands: Commands,
+    mut ik_set_up_event: EventReader<ArmInitializeEvent>,
+    humanoid_query: Query<&Humanoid>,
+    parent_query: Query<&Parent>,
+    transform_query: Query<&Transform>,
+) {
+    for &ArmInitializeEvent(entity) in ik_set_up_event.iter() {
+        let Ok(humanoid) = humanoid_query.get(entity) else {
+            continue;
+        };
+
+        let position =
+            |entity| global_transform(entity, &parent_query, &transform_query).translation();
+
+        //elbows bend forwards
+        let hint = global_transform(entity, &parent_query, &transform_query).forward();
+
+        for arm in [&humanoid.left_arm, &humanoid.right_arm] {
+            commands.entity(arm.0).insert((
+                IKChain::from_hierarchy(arm.iter(), &parent_query, &transform_query),
+                limb_constraints(arm, hint, &parent_query, &transform_query),
+                IKGoal(position(arm.2)),
+                IKSettings::default().with_weight(0.),
+            ));
+        }
+
+        let arm = &humanoid.right_arm;
+        let reach =
+            position(arm.0).distance(position(arm.1)) + position(arm.1).distance(position(arm.2));
+
+        commands.entity(entity).insert(ArmAim::new(reach));
+    }
+}
+
+/// Arms holding the flashlight out towards the [`PlayerTarget`]
+#[derive(Component, Debug)]
+pub struct ArmAim {
+    /// smoothed direction the flashlight points in
+    pub aim: Option<Vec3>,
+    /// the left hand supports the flashlight too
+    pub two_handed: bool,
+    reach: f32,
+}
+
+impl ArmAim {
+    pub fn new(reach: f32) -> Self {
+        Self {
+            aim: None,
+            two_handed: false,
+            reach,
+        }
+    }
+}
+
+/// `dir` turned towards `forward` until it's at most `max_angle` away from it
+fn clamp_to_cone(dir: Vec3, forward: Vec3, max_angle: f32) -> Vec3 {
+    let angle = forward.angle_between(dir);
+
+    if angle <= max_angle {
+        return dir;
+    }
+
+    let axis = forward
+        .cross(dir)
+        .try_normalize()
+        .unwrap_or_else(|| forward.any_orthonormal_vector());
+
+    Quat::from_axis_angle(axis, max_angle) * forward
+}
+
+/// Points the right hand, & the left on two handed holds, along the smoothed aim ray
+pub fn update_arm_aim(
+    time: Res<Time>,
+    target: Res<PlayerTarget>,
+    mut arm_query: Query<(&mut ArmAim, &Humanoid)>,
+    transform_query: Query<&GlobalTransform>,
+    mut ik_query: Query<(&mut IKGoal, &mut IKSettings)>,
+) {
+    for (mut arm_aim, humanoid) in &mut arm_query {
+        let (Ok(body), Ok(shoulder)) = (
+            transform_query.get(humanoid.body),
+            transform_query.get(humanoid.right_arm.0),
+        ) else {
+            continue;
+        };
+        let shoulder = shoulder.translation();
+
+        let forward = Vec3::new(body.forward().x, 0., body.forward().z).normalize_or_zero();
+
+        let desired = match target.as_ref() {
+            PlayerTarget(Some((_, hit))) => (hit.position() - shoulder).try_normalize(),
+            PlayerTarget(None) => None,
+        }
+        .map_or(forward, |dir| clamp_to_cone(dir, forward, MAX_AIM_ANGLE));
+
+        let aim = arm_aim.aim.map_or(desired, |aim| {
+            aim.lerp(desired, (time.delta_seconds() * AIM_SPEED).min(1.))
+                .try_normalize()
+                .unwrap_or(desired)
+        });
+        arm_aim.aim = Some(aim);
+
+        let hand = shoulder + aim * arm_aim.reach * HOLD_REACH;
+
+        //elbows hang down & out
+        if let Ok((mut goal, mut settings)) = ik_query.get_mut(humanoid.right_arm.0) {
+            goal.0 = hand;
+            settings.weight = 1.;
+            settings.pole = Some(shoulder + body.right() - Vec3::Y);
+        }
+
+        if let Ok((mut goal, mut settings)) = ik_query.get_mut(humanoid.left_arm.0) {
+            goal.0 = hand + body.left() * GRIP_GAP;
+            settings.pole = Some(shoulder + body.left() - Vec3::Y);
+            settings.weight = match arm_aim.two_handed {
+                true => 1.,
+                false => 0.,
+            };
+        }
+    }
+}
+
+/// Point the player looks at: what it's interacting with, or else the [`PlayerTarget`]
+fn look_point(target: &PlayerTarget, interacting: Option<&Interacting>) -> Option<Vec3> {
+    match (interacting, target) {
+        (Some(interacting), _) => Some(interacting.point),
+        (None, PlayerTarget(Some((_, hit)))) => Some(hit.position()),
+        (None, PlayerTarget(None)) => None,
+    }
+}
+
 pub fn update_body_dir(
     time: Res<Time>,
 
     target: Res<PlayerTarget>,
-    player_query: Query<&Humanoid, With<Controllable>>,
+    player_query: Query<(&Humanoid, Option<&Interacting>), With<Controllable>>,
     mut bone_entities: Query<(&mut Transform, &GlobalTransform)>,
-
-    mut gizmos: Gizmos,
 ) {
-    let PlayerTarget(Some(target)) = target.as_ref() else {
-        return;
-    };
+    for (humanoid, interacting) in &player_query {
+        let Some(point) = look_point(&target, interacting) else {
+            continue;
+        };
 
-    for humanoid in &player_query {
-        let (mut transform, global_transform) = bone_entities.get_mut(humanoid.body).unwrap();
+        let Ok((mut transform, global_transform)) = bone_entities.get_mut(humanoid.body) else {
+            continue;
+        };
         let transform = transform.as_mut();
 
         let dir = {
-            let mut dir =
-                match (target.1.position() - global_transform.translation()).try_normalize() {
-                    Some(dir) => dir,
-                    None => continue,
-                };
+            let mut dir = match (point - global_transform.translation()).try_normalize() {
+                Some(dir) => dir,
+                None => continue,
+            };
 
             dir.y = 0.; //can be used to lean back or forward
 
@@ -91,23 +341,14 @@ pub fn update_body_dir(
 
         let target_angle = Quat::angle_between(transform.rotation, goal);
 
-        {
-            gizmos.sphere(
-                global_transform.translation(),
-                Quat::default(),
-                0.01,
-                Color::RED,
-            );
-            gizmos.ray(global_transform.translation(), dir, Color::RED);
-        }
-
         //should beable to change based on state
         // -> default
         // -> actively aim (gun)
         // -> kinematic restriction
         const MIN_ANGLE: f32 = PI / 4.;
 
-        if target_angle.abs() < MIN_ANGLE {
+        //face what's being interacted with squarely
+        if target_angle.abs() < MIN_ANGLE && interacting.is_none() {
             continue;
         }
 
@@ -120,25 +361,21 @@ pub fn update_body_dir(
 }
 pub fn update_head_dir(
     target: Res<PlayerTarget>,
-    player_query: Query<&Humanoid, With<Controllable>>,
+    player_query: Query<(&Humanoid, Option<&Interacting>), With<Controllable>>,
     mut bone_entities: Query<(&mut Transform, &GlobalTransform)>,
 ) {
-    let PlayerTarget(Some(target)) = target.as_ref() else {
-        return;
-    };
+    for (humanoid, interacting) in &player_query {
+        let Some(point) = look_point(&target, interacting) else {
+            continue;
+        };
 
-    for humanoid in &player_query {
         //rotate head
-        let dir = {
-            let (_, global_head_transform) = bone_entities.get(humanoid.head).unwrap();
-
-            match (target.1.position() - global_head_transform.translation()).try_normalize() {
-                Some(dir) => dir,
-                None => continue,
-            }
+        let Ok((mut head, global_head_transform)) = bone_entities.get_mut(humanoid.head) else {
+            continue;
+        };
+        let Some(dir) = (point - global_head_transform.translation()).try_normalize() else {
+            continue;
         };
-
-        let (mut head, _) = bone_entities.get_mut(humanoid.head).unwrap();
         let head = head.as_mut();
 
         let (mut x_rot, mut y_rot, _) = head
@@ -158,7 +395,9 @@ pub fn update_head_dir(
 
 #[derive(Debug)]
 pub enum FootTarget {
+    /// planted on the ground
     Locked(Vec3),
+    /// stepping towards where it lands
     Active(Vec3),
 }
 
@@ -178,47 +417,51 @@ impl FootTarget {
 
         self
     }
+    pub fn target(&self) -> Vec3 {
+        match self {
+            FootTarget::Locked(val) | FootTarget::Active(val) => *val,
+        }
+    }
 }
 
+/// Procedural gait: one foot stays planted while the other steps, swapping once the planted
+/// foot drifts a stride away from under the hips
 #[derive(Component, Debug)]
 pub struct HumanoidFeetTarget {
     pub left_target: FootTarget,
     pub right_target: FootTarget,
 
-    left_ik: IKChain,
-    right_ik: IKChain,
+    /// where the active foot lifted off from
+    step_from: Vec3,
+    /// how far through its step the active foot is, 1 once landed
+    step_progress: f32,
 
     leg_offset: f32,
     leg_length_cache: f32,
+    /// body translation the pelvis bob is added to
+    pelvis_rest: Vec3,
+    last_position: Option<Vec3>,
 }
 
 impl HumanoidFeetTarget {
-    pub fn new<F: ReadOnlyWorldQuery>(
-        left_leg: IKChain,
-        right_leg: IKChain,
-        global_transform_query: &Query<&GlobalTransform, F>,
+    pub fn new(
+        left_target: Vec3,
+        right_target: Vec3,
+        leg_offset: f32,
+        leg_length: f32,
+        pelvis_rest: Vec3,
     ) -> Self {
-        let left_target = {
-            let entity = left_leg.iter().last().unwrap().0;
-
-            global_transform_query.get(entity).unwrap().translation()
-        };
-        let right_target = {
-            let entity = right_leg.iter().last().unwrap().0;
-
-            global_transform_query.get(entity).unwrap().translation()
-        };
-
         HumanoidFeetTarget {
-            leg_length_cache: left_leg.length(),
-
             left_target: FootTarget::Locked(left_target),
             right_target: FootTarget::Active(right_target),
 
-            left_ik: left_leg,
-            right_ik: right_leg,
+            step_from: right_target,
+            step_progress: 1.,
 
-            leg_offset: left_target.distance(right_target) / 2.,
+            leg_offset,
+            leg_length_cache: leg_length,
+            pelvis_rest,
+            last_position: None,
         }
     }
 
@@ -235,224 +478,185 @@ impl HumanoidFeetTarget {
 
     pub fn active_target(&self) -> &FootTarget {
         match (&self.left_target, &self.right_target) {
-            (FootTarget::Locked(_), FootTarget::Active(_)) => &self.right_target,
-            (FootTarget::Active(_), FootTarget::Locked(_)) => &self.left_target,
-            _ => todo!(),
+            (_, FootTarget::Active(_)) => &self.right_target,
+            _ => &self.left_target,
         }
     }
     pub fn locked_target(&self) -> &FootTarget {
         match (&self.left_target, &self.right_target) {
-            (FootTarget::Locked(_), FootTarget::Active(_)) => &self.left_target,
-            (FootTarget::Active(_), FootTarget::Locked(_)) => &self.right_target,
-            _ => todo!(),
+            (_, FootTarget::Active(_)) => &self.left_target,
+            _ => &self.right_target,
+        }
+    }
+    fn active_target_mut(&mut self) -> &mut FootTarget {
+        match (&self.left_target, &self.right_target) {
+            (_, FootTarget::Active(_)) => &mut self.right_target,
+            _ => &mut self.left_target,
         }
     }
-}
 
-fn get_edges_and_triangles(mesh: &Mesh) {
-    // Get the indices and vertices
-    let Some(data) = mesh.indices() else {
-        return;
-    };
-    let indices = match data {
-        Indices::U16(indices) => indices.iter().map(|i| *i as usize).collect::<Vec<_>>(),
-        Indices::U32(indices) => indices.iter().map(|i| *i as usize).collect::<Vec<_>>(),
-    };
+    /// Where a foot rests on the nav mesh under the hips, `side` of them & `lead` ahead
+    fn rest_spot(&self, hips: Vec3, side: Vec3, lead: Vec3, nav_mesh: &NavMesh) -> Vec3 {
+        let above = hips + side * self.leg_offset + lead;
 
-    let vertices = match mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap() {
-        bevy::render::mesh::VertexAttributeValues::Float32x3(vertices) => vertices.clone(),
-        _ => panic!("Unexpected vertex type"),
-    };
+        match nav_mesh
+            .project_down(above)
+            .or_else(|| nav_mesh.closest_point(above))
+        {
+            Some((_, ground)) if ground.distance(above) <= self.leg_length_cache => {
+                ground + Vec3::Y * FOOT_HEIGHT
+            }
+            // legs be straight
+            _ => above - Vec3::Y * self.leg_length_cache,
+        }
+    }
 
-    // Get triangles
-    let triangles: Vec<([f32; 3], [f32; 3], [f32; 3])> = indices
-        .chunks_exact(3)
-        .map(|tri| {
-            let v1 = vertices[tri[0]];
-            let v2 = vertices[tri[1]];
-            let v3 = vertices[tri[2]];
-            (v1, v2, v3)
-        })
-        .collect();
-
-    // Get edges
-    let edges: Vec<([f32; 3], [f32; 3])> = indices
-        .windows(2)
-        .map(|edge| {
-            let v1 = vertices[edge[0]];
-            let v2 = vertices[edge[1]];
-            (v1, v2)
-        })
-        .collect();
-
-    // Print triangles and edges
-    println!("Triangles: {:?}", triangles);
-    println!("Edges: {:?}", edges);
-}
+    /// Advances the gait of hips moving at `velocity`
+    pub fn step(
+        &mut self,
+        hips: Vec3,
+        right: Vec3,
+        velocity: Vec3,
+        delta: f32,
+        nav_mesh: &NavMesh,
+    ) {
+        let moving = velocity.length() > MOVING_SPEED;
+        let lead = velocity * STEP_LEAD;
+
+        let left_rest = self.rest_spot(hips, -right, lead, nav_mesh);
+        let right_rest = self.rest_spot(hips, right, lead, nav_mesh);
+
+        let (active_rest, locked_rest) = match self.right_target {
+            FootTarget::Active(_) => (right_rest, left_rest),
+            FootTarget::Locked(_) => (left_rest, right_rest),
+        };
 
-fn nav_mesh_intercept(
-    ray: &Ray3d,
-    ray_cast: &mut Raycast,
-    mesh_query: &Query<&Handle<Mesh>, With<NavMesh>>,
-
-    gizmos: &mut Gizmos,
-) -> Option<Vec3> {
-    let intercepts = ray_cast.debug_cast_ray(
-        ray.clone(),
-        &RaycastSettings {
-            visibility: RaycastVisibility::Ignore,
-            filter: &|entity| mesh_query.contains(entity),
-            early_exit_test: &|_| true,
-        },
-        gizmos,
-    );
-
-    match intercepts.len() {
-        0 => None,
-        _ => Some(intercepts[0].1.position()),
-    }
-}
-fn nearest_nav_mesh_intercept(
-    ray: &Ray3d,
-    meshes: &Res<Assets<Mesh>>,
-    mesh_query: &Query<&Handle<Mesh>, With<NavMesh>>,
-) -> Option<Vec3> {
-    for handle in mesh_query {
-        let Some(mesh) = meshes.get(handle) else {
-            continue;
+        //the stepping foot keeps aiming ahead of the hips until it lands
+        if self.step_progress < 1. {
+            self.step_progress = (self.step_progress + delta / STEP_TIME).min(1.);
+            self.active_target_mut().map(active_rest);
+        }
+
+        let stride = match moving {
+            true => STRIDE,
+            false => IDLE_STRIDE,
         };
 
-        // mesh.attributes()
-        //     .filter(|(x, _)| x == &Mesh::ATTRIBUTE_POSITION.id)
-        //     .for_each(|data| println!("{data:#?}"));
+        let locked = self.locked_target().target();
+
+        if self.step_progress >= 1. && flat_distance(locked, locked_rest) > stride {
+            self.left_target.swap();
+            self.right_target.swap();
+
+            self.step_from = locked;
+            self.step_progress = 0.;
+            self.active_target_mut().map(locked_rest);
+        }
+    }
+
+    /// Current position of a foot, stepping ones lift along an arc
+    pub fn foot_position(&self, target: &FootTarget) -> Vec3 {
+        match target {
+            FootTarget::Locked(pos) => *pos,
+            FootTarget::Active(pos) => {
+                self.step_from.lerp(*pos, self.step_progress)
+                    + Vec3::Y * STEP_HEIGHT * f32::sin(PI * self.step_progress)
+            }
+        }
     }
-    None
-}
 
-fn straight_legs_target(down: Vec3, pos: Vec3, dist: f32) -> Vec3 {
-    down * dist + pos
+    /// Height the pelvis rises by, highest as the stepping foot passes the planted one
+    pub fn pelvis_bob(&self) -> f32 {
+        PELVIS_BOB * f32::sin(PI * self.step_progress)
+    }
 }
 
+/// Steps the feet of every walking [`Humanoid`], points the leg IK at them & bobs the pelvis
 pub fn update_legs(
     // 🦵
-    mut leg_query: Query<(&mut HumanoidFeetTarget, &Direction, &Humanoid)>,
-    transform_query: Query<&GlobalTransform>,
-    camera_query: Query<&Transform, With<Camera>>,
-
-    // ray cast nav mesh
-    mut ray_cast: Raycast,
-
-    // gets closet nav mesh target
-    meshes: Res<Assets<Mesh>>,
-    nav_mesh_query: Query<&Handle<Mesh>, With<NavMesh>>,
-
-    //debug stuff
-    mut gizmos: Gizmos,
+    time: Res<Time>,
+    nav_mesh: Res<NavMesh>,
+    mut leg_query: Query<(&mut HumanoidFeetTarget, &Humanoid, &GlobalTransform)>,
+    mut ik_query: Query<(&mut IKGoal, &mut IKSettings)>,
+    mut body_query: Query<(&mut Transform, &GlobalTransform), Without<HumanoidFeetTarget>>,
 ) {
-    for (mut feet_targets, dir, humanoid) in &mut leg_query {
+    let delta = time.delta_seconds();
+
+    for (mut feet_targets, humanoid, transform) in &mut leg_query {
         let feet_targets: &mut HumanoidFeetTarget = feet_targets.as_mut();
 
-        let body_transform = transform_query.get(humanoid.body).unwrap();
+        let position = transform.translation();
+        let velocity = match (feet_targets.last_position, delta > 0.) {
+            (Some(last_position), true) => {
+                let velocity = (position - last_position) / delta;
+                Vec3::new(velocity.x, 0., velocity.z)
+            }
+            _ => Vec3::ZERO,
+        };
+        feet_targets.last_position = Some(position);
 
-        feet_targets.draw_gizmo(&mut gizmos);
+        let Ok((mut body, body_transform)) = body_query.get_mut(humanoid.body) else {
+            continue;
+        };
 
-        match (dir.0.length() > 0.01,) {
-            (false, ..) => {
-                let transform = transform_query.get(humanoid.body).unwrap();
-
-                let target = straight_legs_target(
-                    transform.down(),
-                    transform.translation(),
-                    feet_targets.leg_length_cache,
-                );
-
-                feet_targets
-                    .left_target
-                    .map(target + transform.left() * feet_targets.leg_offset);
-                feet_targets
-                    .right_target
-                    .map(target + transform.right() * feet_targets.leg_offset);
-                //not moving
-            }
-            (true, ..) => {
-                //update position
-                new_target(
-                    body_transform,
-                    feet_targets,
-                    dir,
-                    &camera_query,
-                    &transform_query,
-                    humanoid,
-                    &mut ray_cast,
-                    &nav_mesh_query,
-                    &mut gizmos,
-                    &meshes,
-                );
+        feet_targets.step(
+            body_transform.translation(),
+            body_transform.right(),
+            velocity,
+            delta,
+            &nav_mesh,
+        );
+
+        //bob along world up
+        let parent_rotation =
+            body_transform.to_scale_rotation_translation().1 * body.rotation.inverse();
+        body.translation = feet_targets.pelvis_rest
+            + parent_rotation.inverse() * Vec3::Y * feet_targets.pelvis_bob();
+
+        //knees bend forwards
+        let pole = body_transform.translation() + body_transform.forward();
+
+        for (leg, target) in [
+            (&humanoid.left_leg, &feet_targets.left_target),
+            (&humanoid.right_leg, &feet_targets.right_target),
+        ] {
+            if let Ok((mut goal, mut settings)) = ik_query.get_mut(leg.0) {
+                goal.0 = feet_targets.foot_position(target);
+                settings.pole = Some(pole);
             }
         }
+    }
+}
 
-        //update ik to new target
+fn toggle_ik_gizmos(keyboard_input: Res<Input<KeyCode>>, mut gizmos: ResMut<IKGizmos>) {
+    if keyboard_input.just_pressed(TOGGLE_IK_GIZMOS) {
+        gizmos.0 = !gizmos.0;
     }
 }
 
-fn new_target(
-    body_transform: &GlobalTransform,
-    feet_targets: &mut HumanoidFeetTarget,
-    dir: &Direction,
-    camera_query: &Query<'_, '_, &Transform, With<Camera>>,
-    transform_query: &Query<'_, '_, &GlobalTransform>,
-    humanoid: &Humanoid,
-    ray_cast: &mut Raycast<'_, '_>,
-    nav_mesh_query: &Query<'_, '_, &Handle<Mesh>, With<NavMesh>>,
-    gizmos: &mut Gizmos<'_>,
-    meshes: &Res<'_, Assets<Mesh>>,
+/// Draws the feet targets of every walking [`Humanoid`] & where the player's body turns to
+fn draw_ik_gizmos(
+    target: Res<PlayerTarget>,
+    feet_query: Query<&HumanoidFeetTarget>,
+    player_query: Query<(&Humanoid, Option<&Interacting>), With<Controllable>>,
+    transform_query: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
 ) {
-    let ray = Ray3d::new(
-        body_transform.translation()
-            + match (&feet_targets.left_target, &feet_targets.right_target) {
-                (FootTarget::Locked(_), FootTarget::Active(_)) => {
-                    body_transform.right() * feet_targets.leg_offset
-                }
-                (FootTarget::Active(_), FootTarget::Locked(_)) => {
-                    body_transform.left() * feet_targets.leg_offset
-                }
-                _ => todo!(),
-            },
-        {
-            let dir = dir.0;
-
-            let camera_transform = camera_query.iter().next().unwrap();
-            let player_transform = transform_query.get(humanoid.body).unwrap();
-
-            let forward = camera_transform.forward();
-            let right = camera_transform.right();
-            let down = player_transform.down();
-
-            (forward * dir.x + right * dir.z + down * dir.y + down).normalize()
-        },
-    );
+    for feet_targets in &feet_query {
+        feet_targets.draw_gizmo(&mut gizmos);
+    }
 
-    let mesh_pos = nav_mesh_intercept(&ray, ray_cast, nav_mesh_query, gizmos);
-    let nearest_pos = nearest_nav_mesh_intercept(&ray, meshes, nav_mesh_query);
+    for (humanoid, interacting) in &player_query {
+        let (Some(point), Ok(body)) = (
+            look_point(&target, interacting),
+            transform_query.get(humanoid.body),
+        ) else {
+            continue;
+        };
 
-    let foot_target = match (mesh_pos, nearest_pos) {
-        (None, Some(val)) | (Some(val), None) => {
-            // if val is too far - legs be straight
-            val
-        }
-        (Some(val_1), Some(val_2)) => {
-            //pick which ever one is closest
-            todo!();
-        }
-        (None, None) => {
-            // legs be straight
-            let transform = transform_query.get(humanoid.body).unwrap();
+        let dir = Vec3::new(point.x, body.translation().y, point.z) - body.translation();
 
-            straight_legs_target(
-                transform.down(),
-                transform.translation(),
-                feet_targets.leg_length_cache,
-            )
-        }
-    };
+        gizmos.sphere(body.translation(), Quat::default(), 0.01, Color::RED);
+        gizmos.ray(body.translation(), dir.normalize_or_zero(), Color::RED);
+    }
 }
@@ -0,0 +1,391 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        system::{Commands, Resource, SystemParam},
+    },
+    hierarchy::{Children, HierarchyQueryExt, Parent},
+    log::info,
+    prelude::{
+        default, App, Color, DetectChanges, GlobalTransform, Input, IntoSystemConfigs, KeyCode, Or,
+        Plugin, PointLight, Query, Res, ResMut, SpotLight, Startup, TextBundle, Update, Vec3,
+        Visibility, With, Without,
+    },
+    text::{Text, TextStyle},
+    time::{Time, Timer, TimerMode},
+    ui::{PositionType, Style, Val},
+};
+use serde::Deserialize;
+
+use crate::humanoid::ragdoll::Standing;
+
+use super::{controller::INTERACT, target::PlayerTarget, Controllable};
+
+/// Seconds the player keeps facing what it interacted with
+const FACE_TIME: f32 = 1.;
+/// Seconds the description of an inspected [`Interactable`] stays on screen
+const INSPECT_TIME: f32 = 4.;
+
+/// What interacting with an [`Interactable`] does, each fires its own event
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    /// fires [`OpenEvent`]
+    Open,
+    /// fires [`PickUpEvent`]
+    PickUp,
+    /// fires [`InspectEvent`], showing the interactable's description
+    Inspect,
+    /// fires [`ToggleEvent`], switching the lights of the interactable on & off
+    Toggle,
+}
+
+/// Something the player can interact with while hovering it within `range`. Needs a
+/// [`super::target::PlayerTargetSet`] on it or an ancestor to be hovered
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct Interactable {
+    pub verb: Verb,
+    /// distance from the player's feet to the hovered point
+    #[serde(default = "Interactable::default_range")]
+    pub range: f32,
+    pub prompt: String,
+    /// shown when inspected
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl Interactable {
+    pub fn new(verb: Verb, prompt: impl Into<String>) -> Self {
+        Self {
+            verb,
+            range: Self::default_range(),
+            prompt: prompt.into(),
+            description: None,
+        }
+    }
+
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    fn default_range() -> f32 {
+        2.
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct OpenEvent {
+    pub interactor: Entity,
+    pub target: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickUpEvent {
+    pub interactor: Entity,
+    pub target: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InspectEvent {
+    pub interactor: Entity,
+    pub target: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ToggleEvent {
+    pub interactor: Entity,
+    pub target: Entity,
+}
+
+/// Writers of the event of each [`Verb`]
+#[derive(SystemParam)]
+pub struct InteractionEvents<'w> {
+    open: EventWriter<'w, OpenEvent>,
+    pick_up: EventWriter<'w, PickUpEvent>,
+    inspect: EventWriter<'w, InspectEvent>,
+    toggle: EventWriter<'w, ToggleEvent>,
+}
+
+impl<'w> InteractionEvents<'w> {
+    fn send(&mut self, verb: Verb, interactor: Entity, target: Entity) {
+        match verb {
+            Verb::Open => self.open.send(OpenEvent { interactor, target }),
+            Verb::PickUp => self.pick_up.send(PickUpEvent { interactor, target }),
+            Verb::Inspect => self.inspect.send(InspectEvent { interactor, target }),
+            Verb::Toggle => self.toggle.send(ToggleEvent { interactor, target }),
+        }
+    }
+}
+
+/// The [`Interactable`] hovered within reach of the player & its prompt
+#[derive(Resource, Default, Debug)]
+pub struct InteractionPrompt(pub Option<(Entity, String)>);
+
+/// Turns the head & body towards what the entity is interacting with
+#[derive(Component, Debug)]
+pub struct Interacting {
+    pub target: Entity,
+    pub point: Vec3,
+    timer: Timer,
+}
+
+/// Text showing the [`InteractionPrompt`]
+#[derive(Component)]
+struct PromptText;
+
+/// Text showing the description of the last inspected [`Interactable`] for a while
+#[derive(Component)]
+struct InspectionText(Timer);
+
+/// `entity` or its closest ancestor with an [`Interactable`]
+fn find_interactable<'a>(
+    entity: Entity,
+    interactable_query: &'a Query<&Interactable>,
+    parent_query: &Query<&Parent>,
+) -> Option<(Entity, &'a Interactable)> {
+    let mut entity = entity;
+
+    loop {
+        if let Ok(interactable) = interactable_query.get(entity) {
+            return Some((entity, interactable));
+        }
+
+        entity = parent_query.get(entity).ok()?.get();
+    }
+}
+
+fn update_interaction_prompt(
+    target: Res<PlayerTarget>,
+    player_query: Query<&GlobalTransform, (With<Controllable>, Standing)>,
+    interactable_query: Query<&Interactable>,
+    parent_query: Query<&Parent>,
+    mut prompt: ResMut<InteractionPrompt>,
+) {
+    let hovered = match (target.as_ref(), player_query.iter().next()) {
+        (PlayerTarget(Some((entity, hit))), Some(player)) => {
+            find_interactable(*entity, &interactable_query, &parent_query).filter(
+                |(_, interactable)| {
+                    player.translation().distance(hit.position()) <= interactable.range
+                },
+            )
+        }
+        _ => None,
+    }
+    .map(|(entity, interactable)| (entity, interactable.prompt.clone()));
+
+    //only touch the resource on changes, the prompt text updates on them
+    if prompt.0 != hovered {
+        prompt.0 = hovered;
+    }
+}
+
+/// Fires the event of the prompted [`Interactable`]'s verb & turns the player towards it
+pub fn interact(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    prompt: Res<InteractionPrompt>,
+    target: Res<PlayerTarget>,
+    player_query: Query<Entity, (With<Controllable>, Standing)>,
+    interactable_query: Query<&Interactable>,
+    mut events: InteractionEvents,
+) {
+    //shift + E rotates the camera
+    if !keyboard_input.just_pressed(INTERACT) || keyboard_input.pressed(KeyCode::ShiftLeft) {
+        return;
+    }
+
+    let (Some((target_entity, _)), PlayerTarget(Some((_, hit))), Some(player)) =
+        (&prompt.0, target.as_ref(), player_query.iter().next())
+    else {
+        return;
+    };
+    let Ok(interactable) = interactable_query.get(*target_entity) else {
+        return;
+    };
+
+    events.send(interactable.verb, player, *target_entity);
+
+    commands.entity(player).insert(Interacting {
+        target: *target_entity,
+        point: hit.position(),
+        timer: Timer::from_seconds(FACE_TIME, TimerMode::Once),
+    });
+}
+
+fn stop_interacting(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut interacting_query: Query<(Entity, &mut Interacting)>,
+) {
+    for (entity, mut interacting) in &mut interacting_query {
+        if interacting.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Interacting>();
+        }
+    }
+}
+
+fn create_prompt_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(10.),
+            left: Val::Percent(45.),
+            ..default()
+        }),
+        PromptText,
+    ));
+}
+
+fn create_inspection_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Percent(15.),
+                left: Val::Percent(35.),
+                ..default()
+            })
+        },
+        InspectionText(Timer::from_seconds(INSPECT_TIME, TimerMode::Once)),
+    ));
+}
+
+/// Shows the description of inspected [`Interactable`]s
+fn inspect(
+    mut inspect_event: EventReader<InspectEvent>,
+    interactable_query: Query<&Interactable>,
+    mut text_query: Query<(&mut Text, &mut Visibility, &mut InspectionText)>,
+) {
+    for event in inspect_event.iter() {
+        let Ok(interactable) = interactable_query.get(event.target) else {
+            continue;
+        };
+        let description = interactable
+            .description
+            .as_deref()
+            .unwrap_or("Nothing out of the ordinary.");
+
+        info!("inspected {:?}: {description}", event.target);
+
+        for (mut text, mut visibility, mut inspection) in &mut text_query {
+            text.sections[0].value = description.into();
+            *visibility = Visibility::Inherited;
+            inspection.0.reset();
+        }
+    }
+}
+
+fn hide_inspection_text(
+    time: Res<Time>,
+    mut text_query: Query<(&mut Visibility, &mut InspectionText)>,
+) {
+    for (mut visibility, mut inspection) in &mut text_query {
+        if inspection.0.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+/// Lights switched by a [`ToggleEvent`]
+type ToggledLights<'w, 's> = Query<
+    'w,
+    's,
+    &'static mut Visibility,
+    (
+        Or<(With<PointLight>, With<SpotLight>)>,
+        Without<PromptText>,
+        Without<InspectionText>,
+    ),
+>;
+
+/// Switches the lights of toggled [`Interactable`]s, their own & their children's, on & off
+fn toggle(
+    mut toggle_event: EventReader<ToggleEvent>,
+    children_query: Query<&Children>,
+    mut light_query: ToggledLights,
+) {
+    for event in toggle_event.iter() {
+        let lights =
+            std::iter::once(event.target).chain(children_query.iter_descendants(event.target));
+
+        for light in lights {
+            let Ok(mut visibility) = light_query.get_mut(light) else {
+                continue;
+            };
+
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                Visibility::Inherited | Visibility::Visible => Visibility::Hidden,
+            };
+            info!("switched {light:?} {:?}", *visibility);
+        }
+    }
+}
+
+fn update_prompt_text(
+    prompt: Res<InteractionPrompt>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<PromptText>>,
+) {
+    if !prompt.is_changed() {
+        return;
+    }
+
+    for (mut text, mut visibility) in &mut text_query {
+        match &prompt.0 {
+            Some((_, prompt)) => {
+                text.sections[0].value = format!("[{INTERACT:?}] {prompt}");
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenEvent>()
+            .add_event::<PickUpEvent>()
+            .add_event::<InspectEvent>()
+            .add_event::<ToggleEvent>()
+            .init_resource::<InteractionPrompt>()
+            .add_systems(Startup, (create_prompt_text, create_inspection_text))
+            .add_systems(
+                Update,
+                (
+                    update_interaction_prompt,
+                    interact,
+                    stop_interacting,
+                    update_prompt_text,
+                    inspect,
+                    hide_inspection_text,
+                    toggle,
+                )
+                    .chain(),
+            );
+    }
+}
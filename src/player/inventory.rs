@@ -0,0 +1,429 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        system::{Commands, Local, Resource},
+    },
+    log::{info, warn},
+    prelude::{
+        default, AddAsset, App, AssetServer, Assets, DespawnRecursiveExt, Handle, Input,
+        IntoSystemConfigs, KeyCode, Plugin, PointLight, PointLightBundle, Quat, Query, Res,
+        SpatialBundle, SpotLight, SpotLightBundle, Startup, Transform, Update, With,
+    },
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+
+use crate::humanoid::Humanoid;
+
+use super::{
+    controller::EQUIP_KEYS,
+    follow::{Coord, Follow, FollowTarget},
+    ik::ArmAim,
+    interaction::{interact, PickUpEvent},
+    Controllable,
+};
+
+const ITEMS_PATH: &str = "items/items.items.ron";
+
+/// Hand an item is held in, see [`Humanoid::left_arm`] & [`Humanoid::right_arm`]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+impl Hand {
+    /// Hand bone of `humanoid`
+    pub fn bone(&self, humanoid: &Humanoid) -> Entity {
+        match self {
+            Hand::Left => humanoid.left_arm.2,
+            Hand::Right => humanoid.right_arm.2,
+        }
+    }
+
+    pub fn other(&self) -> Hand {
+        match self {
+            Hand::Left => Hand::Right,
+            Hand::Right => Hand::Left,
+        }
+    }
+}
+
+/// Light given off by a held item
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum ItemLight {
+    /// shines where the hand aims, `angle` in degrees
+    Spot {
+        intensity: f32,
+        range: f32,
+        angle: f32,
+    },
+    Point {
+        intensity: f32,
+        range: f32,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum ItemKind {
+    Light(ItemLight),
+    Camera,
+    /// opens the locks with this name
+    Key(String),
+    /// recharges the flashlight by this fraction of a full charge once picked up
+    Battery(f32),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemDefinition {
+    pub name: String,
+    pub kind: ItemKind,
+    /// hand it's held in unless another is asked for
+    #[serde(default = "ItemDefinition::default_hand")]
+    pub hand: Hand,
+    /// takes both hands, the other hand helps hold it
+    #[serde(default)]
+    pub two_handed: bool,
+}
+
+impl ItemDefinition {
+    fn default_hand() -> Hand {
+        Hand::Right
+    }
+}
+
+/// Every item by key. Loaded from `items/{name}.items.ron`
+#[derive(Deserialize, TypeUuid, TypePath, Debug, Clone, Default)]
+#[uuid = "6d2a9c1e-3f4b-4e8a-a7d5-1b9e0c2f8a64"]
+pub struct ItemDefinitions {
+    pub items: HashMap<String, ItemDefinition>,
+}
+
+#[derive(Default)]
+pub struct ItemDefinitionsLoader;
+
+impl AssetLoader for ItemDefinitionsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let definitions: ItemDefinitions = ron::de::from_bytes(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(definitions));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["items.ron"]
+    }
+}
+
+/// The [`ItemDefinitions`] of the game
+#[derive(Resource)]
+pub struct ItemLibrary(pub Handle<ItemDefinitions>);
+
+/// Item lying in the world, picked up into the [`Inventory`] by a [`PickUpEvent`]
+#[derive(Component, Debug, Clone)]
+pub struct Item(pub String);
+
+/// Keys of the items carried
+#[derive(Component, Debug, Default, Clone)]
+pub struct Inventory(pub Vec<String>);
+
+impl Inventory {
+    pub fn contains(&self, item: &str) -> bool {
+        self.0.iter().any(|carried| carried == item)
+    }
+}
+
+/// Item held in a hand & the entity spawned for it
+#[derive(Debug)]
+pub struct Held {
+    pub item: String,
+    pub entity: Entity,
+    two_handed: bool,
+}
+
+/// What's held in each hand
+#[derive(Component, Debug, Default)]
+pub struct Equipment {
+    pub left: Option<Held>,
+    pub right: Option<Held>,
+}
+
+impl Equipment {
+    pub fn hand(&self, hand: Hand) -> Option<&Held> {
+        match hand {
+            Hand::Left => self.left.as_ref(),
+            Hand::Right => self.right.as_ref(),
+        }
+    }
+
+    fn hand_mut(&mut self, hand: Hand) -> &mut Option<Held> {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+
+    /// Hand holding `item`
+    pub fn holding(&self, item: &str) -> Option<Hand> {
+        [Hand::Left, Hand::Right]
+            .into_iter()
+            .find(|hand| self.hand(*hand).is_some_and(|held| held.item == item))
+    }
+}
+
+/// The spot light of a held item, eg: the flashlight, with its full `intensity` & `angle`
+#[derive(Component, Debug)]
+pub struct Flashlight {
+    pub intensity: f32,
+    pub angle: f32,
+}
+
+/// Entity spawned for an item held by `holder`
+#[derive(Component, Debug)]
+pub struct HeldItem {
+    pub holder: Entity,
+    pub item: String,
+}
+
+/// Puts `item` from the holder's [`Inventory`] in `hand`, or the item's own hand if None
+#[derive(Event, Debug, Clone)]
+pub struct EquipEvent {
+    pub holder: Entity,
+    pub item: String,
+    pub hand: Option<Hand>,
+}
+
+/// Puts away what's held in `hand`
+#[derive(Event, Debug, Clone)]
+pub struct UnequipEvent {
+    pub holder: Entity,
+    pub hand: Hand,
+}
+
+fn load_item_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ItemLibrary(asset_server.load(ITEMS_PATH)));
+}
+
+pub fn pick_up_items(
+    mut commands: Commands,
+    mut pick_up_event: EventReader<PickUpEvent>,
+    item_query: Query<&Item>,
+    mut inventory_query: Query<&mut Inventory>,
+) {
+    for event in pick_up_event.iter() {
+        let (Ok(Item(item)), Ok(mut inventory)) = (
+            item_query.get(event.target),
+            inventory_query.get_mut(event.interactor),
+        ) else {
+            continue;
+        };
+
+        info!("picked up {item}");
+
+        inventory.0.push(item.clone());
+        commands.entity(event.target).despawn_recursive();
+    }
+}
+
+/// Number keys equip the matching inventory item, or put it away when it's held
+fn equip_from_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    player_query: Query<(Entity, &Inventory, &Equipment), With<Controllable>>,
+    mut equip_event: EventWriter<EquipEvent>,
+    mut unequip_event: EventWriter<UnequipEvent>,
+) {
+    let Some(slot) = EQUIP_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+    else {
+        return;
+    };
+
+    for (player, inventory, equipment) in &player_query {
+        let Some(item) = inventory.0.get(slot) else {
+            continue;
+        };
+
+        match equipment.holding(item) {
+            Some(hand) => unequip_event.send(UnequipEvent {
+                holder: player,
+                hand,
+            }),
+            None => equip_event.send(EquipEvent {
+                holder: player,
+                item: item.clone(),
+                hand: None,
+            }),
+        }
+    }
+}
+
+/// Entity giving off the item's light, if any
+fn spawn_held_item(commands: &mut Commands, definition: &ItemDefinition) -> Entity {
+    match definition.kind {
+        ItemKind::Light(ItemLight::Spot {
+            intensity,
+            range,
+            angle,
+        }) => commands
+            .spawn((
+                SpotLightBundle {
+                    spot_light: SpotLight {
+                        intensity,
+                        range,
+                        shadows_enabled: true,
+                        outer_angle: angle.to_radians(),
+                        ..default()
+                    },
+                    transform: Transform {
+                        rotation: Quat::from_rotation_x(PI / 2.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Flashlight {
+                    intensity,
+                    angle: angle.to_radians(),
+                },
+            ))
+            .id(),
+        ItemKind::Light(ItemLight::Point { intensity, range }) => commands
+            .spawn(PointLightBundle {
+                point_light: PointLight {
+                    intensity,
+                    range,
+                    shadows_enabled: true,
+                    ..default()
+                },
+                ..default()
+            })
+            .id(),
+        ItemKind::Camera | ItemKind::Key(_) | ItemKind::Battery(_) => {
+            commands.spawn(SpatialBundle::default()).id()
+        }
+    }
+}
+
+fn put_away(commands: &mut Commands, equipment: &mut Equipment, hand: Hand) {
+    if let Some(held) = equipment.hand_mut(hand).take() {
+        commands.entity(held.entity).despawn_recursive();
+    }
+}
+
+/// Puts items in & out of hands. Equipping waits for the [`ItemDefinitions`] to load
+pub fn equip_items(
+    mut commands: Commands,
+    library: Res<ItemLibrary>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    mut pending: Local<Vec<EquipEvent>>,
+    mut equip_event: EventReader<EquipEvent>,
+    mut unequip_event: EventReader<UnequipEvent>,
+    mut holder_query: Query<(&Inventory, &mut Equipment, &Humanoid, Option<&mut ArmAim>)>,
+) {
+    for event in unequip_event.iter() {
+        if let Ok((_, mut equipment, _, arm_aim)) = holder_query.get_mut(event.holder) {
+            put_away(&mut commands, &mut equipment, event.hand);
+
+            if let (Hand::Right, Some(mut arm_aim)) = (event.hand, arm_aim) {
+                arm_aim.two_handed = false;
+            }
+        }
+    }
+
+    pending.extend(equip_event.iter().cloned());
+    let Some(definitions) = definitions.get(&library.0) else {
+        return;
+    };
+
+    for event in pending.drain(..) {
+        let Ok((inventory, mut equipment, humanoid, arm_aim)) = holder_query.get_mut(event.holder)
+        else {
+            warn!("{:?} can't hold {}", event.holder, event.item);
+            continue;
+        };
+        if !inventory.contains(&event.item) {
+            warn!("{:?} doesn't carry {}", event.holder, event.item);
+            continue;
+        }
+        let Some(definition) = definitions.items.get(&event.item) else {
+            warn!("no item named {:?} in {ITEMS_PATH}", event.item);
+            continue;
+        };
+
+        let hand = event.hand.unwrap_or(definition.hand);
+
+        //an item is held in one hand at most, equipping it into the other moves it
+        match equipment.holding(&event.item) {
+            Some(held) if held == hand => continue,
+            Some(held) => put_away(&mut commands, &mut equipment, held),
+            None => {}
+        }
+
+        //make room, two handed items need both hands
+        put_away(&mut commands, &mut equipment, hand);
+        let other_hand_busy = equipment
+            .hand(hand.other())
+            .is_some_and(|held| held.two_handed);
+        if definition.two_handed || other_hand_busy {
+            put_away(&mut commands, &mut equipment, hand.other());
+        }
+
+        let entity = spawn_held_item(&mut commands, definition);
+        commands.entity(entity).insert((
+            HeldItem {
+                holder: event.holder,
+                item: event.item.clone(),
+            },
+            Follow(Some(FollowTarget {
+                target: hand.bone(humanoid),
+                offset: Coord::Cartesian {
+                    x: 0.,
+                    y: 0.,
+                    z: 0.,
+                },
+            })),
+        ));
+
+        *equipment.hand_mut(hand) = Some(Held {
+            item: event.item,
+            entity,
+            two_handed: definition.two_handed,
+        });
+
+        //the left hand supports what the right hand aims
+        if let Some(mut arm_aim) = arm_aim {
+            arm_aim.two_handed = equipment.right.as_ref().is_some_and(|held| held.two_handed);
+        }
+    }
+}
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<ItemDefinitions>()
+            .init_asset_loader::<ItemDefinitionsLoader>()
+            .add_event::<EquipEvent>()
+            .add_event::<UnequipEvent>()
+            .add_systems(Startup, load_item_definitions)
+            .add_systems(
+                Update,
+                (pick_up_items, equip_from_keys, equip_items)
+                    .chain()
+                    .after(interact),
+            );
+    }
+}
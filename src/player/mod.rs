@@ -14,14 +14,27 @@ use bevy_mod_raycast::prelude::RaycastSystem;
 use crate::scene::prop::sound_source::SoundSource;
 
 use self::{
-    controller::ControllerPlugin, ik::IKPlugin, movement::MovementPlugin, target::PlayerTarget,
+    click_to_move::ClickToMovePlugin,
+    controller::ControllerPlugin,
+    fear::FearPlugin,
+    flashlight::FlashlightPlugin,
+    ik::{ArmAim, HumanoidIKPlugin},
+    interaction::InteractionPlugin,
+    inventory::InventoryPlugin,
+    movement::MovementPlugin,
+    target::PlayerTarget,
 };
 
-mod controller;
-mod create;
+pub mod click_to_move;
+pub mod controller;
+pub mod create;
+pub mod fear;
+pub mod flashlight;
 pub mod follow;
-mod ik;
-mod movement;
+pub mod ik;
+pub mod interaction;
+pub mod inventory;
+pub mod movement;
 pub mod target;
 
 pub const EAR_GAP: f32 = 0.25;
@@ -239,11 +252,20 @@ pub fn update_sound_level(
 */
 fn update_light_dir(
     target: Res<PlayerTarget>,
+    arm_query: Query<&ArmAim>,
     mut player_query: Query<&mut Transform, With<SpotLight>>,
 ) {
+    //shine where the hand points
+    let aim = arm_query.iter().find_map(|arm_aim| arm_aim.aim);
+
     for mut transform in &mut player_query {
         let transform = transform.as_mut();
 
+        if let Some(aim) = aim {
+            transform.look_to(aim, Vec3::Y);
+            continue;
+        }
+
         let PlayerTarget(Some(target)) = target.as_ref() else {
             continue;
         };
@@ -258,31 +280,43 @@ pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((ControllerPlugin, MovementPlugin, IKPlugin))
-            .add_systems(
-                First,
-                target::update_player_target
-                    .before(RaycastSystem::BuildRays::<target::PlayerTargetSet>),
-            )
-            .add_systems(Startup, create::create_player)
-            .add_systems(
-                //player movement
-                Update,
-                (
-                    // move_controllable,
-                    rotate_camera_view,
-                    // movement::update_pos,
-                    follow::follow,
-                    update_light_dir,
-                ),
-            )
-            .add_systems(
-                //update sound
-                Update,
-                (
-                    update_sound_sink_pos,
-                    //update_sound_level
-                ),
-            );
+        app.add_plugins((
+            ControllerPlugin,
+            MovementPlugin,
+            ClickToMovePlugin,
+            HumanoidIKPlugin,
+            InteractionPlugin,
+            InventoryPlugin,
+            FlashlightPlugin,
+            FearPlugin,
+        ))
+        .add_systems(
+            First,
+            target::update_player_target
+                .before(RaycastSystem::BuildRays::<target::PlayerTargetSet>),
+        )
+        .add_systems(Startup, create::create_player)
+        .add_systems(
+            //player movement
+            Update,
+            (
+                // move_controllable,
+                rotate_camera_view,
+                // movement::update_pos,
+                create::set_up_player_humanoid
+                    .before(ik::initialize_leg_ik)
+                    .before(ik::initialize_arm_ik),
+                follow::follow,
+                update_light_dir.after(ik::update_arm_aim),
+            ),
+        )
+        .add_systems(
+            //update sound
+            Update,
+            (
+                update_sound_sink_pos,
+                //update_sound_level
+            ),
+        );
     }
 }
@@ -1,8 +1,11 @@
+use std::collections::VecDeque;
+
 use bevy::{
     app::{Plugin, Update},
     ecs::{
         component::Component,
         query::{With, Without},
+        schedule::{common_conditions::resource_equals, IntoSystemConfigs},
         system::{Query, Res},
     },
     math::Vec3,
@@ -11,7 +14,13 @@ use bevy::{
     transform::components::Transform,
 };
 
-use super::{controller::MovementInput, Controllable};
+use crate::{humanoid::ragdoll::Standing, scene::nav_mesh::NavMesh};
+
+use super::{
+    controller::{MovementInput, MovementMode},
+    fear::Fear,
+    Controllable,
+};
 
 #[derive(Component)]
 pub struct Direction(pub Vec3);
@@ -32,54 +41,123 @@ fn update_direction_from_input(
     }
 }
 
+/// Walking speed of the [`Controllable`] entity when calm, see [`Fear::speed`]
+pub const MOVE_SPEED: f32 = 5.;
+
+/// World space (forward, sideways) axes of [`Direction`] for an entity at `transform` seen from
+/// `camera`
+pub fn camera_axes(camera: Option<&Transform>, transform: &Transform) -> (Vec3, Vec3) {
+    match camera {
+        Some(t) => {
+            let delta = t.translation - transform.translation;
+
+            let forward = Vec3 {
+                x: delta.x,
+                y: 0.,
+                z: delta.z,
+            }
+            .normalize();
+
+            let side_ways = Vec3 {
+                x: forward.z,
+                y: 0.,
+                z: -forward.x,
+            };
+
+            (-forward, side_ways)
+        }
+        None => (
+            Vec3 {
+                x: 1.,
+                y: 0.,
+                z: 0.,
+            },
+            Vec3 {
+                x: 0.,
+                y: 0.,
+                z: 1.,
+            },
+        ),
+    }
+}
+
+/// Moves `translation` by `delta` over the nav mesh. The entity keeps its height above the floor
+/// & can't leave the walkable area; without a nav mesh it moves freely
+pub fn walk(nav_mesh: &NavMesh, translation: &mut Vec3, delta: Vec3) {
+    let Some((triangle, floor)) = nav_mesh.snap(*translation) else {
+        *translation += delta;
+        return;
+    };
+
+    let (_, new_floor) = nav_mesh.slide(triangle, floor, delta);
+
+    *translation = Vec3::new(
+        new_floor.x,
+        translation.y + new_floor.y - floor.y,
+        new_floor.z,
+    );
+}
+
+/// Walks `step` along `path` over the nav mesh, dropping the waypoints reached. Returns the
+/// heading of the last move
+pub fn walk_path(
+    nav_mesh: &NavMesh,
+    translation: &mut Vec3,
+    path: &mut VecDeque<Vec3>,
+    mut step: f32,
+) -> Vec3 {
+    let mut heading = Vec3::ZERO;
+
+    while let Some(waypoint) = path.front() {
+        let delta = Vec3::new(waypoint.x - translation.x, 0., waypoint.z - translation.z);
+        let dist = delta.length();
+
+        heading = delta.normalize_or_zero();
+
+        match dist > step {
+            true => {
+                walk(nav_mesh, translation, heading * step);
+                break;
+            }
+            false => {
+                walk(nav_mesh, translation, delta);
+                step -= dist;
+                path.pop_front();
+            }
+        }
+    }
+
+    heading
+}
+
+/// [`Controllable`] entities free to walk, limp ones can't
+type Walkers<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, &'static Direction),
+    (With<Controllable>, Without<Camera>, Standing),
+>;
+
 pub fn update_pos(
     time: Res<Time>,
+    fear: Res<Fear>,
+    nav_mesh: Res<NavMesh>,
     camera_query: Query<&Transform, With<Camera>>,
-    mut player_query: Query<(&mut Transform, &Direction), (With<Controllable>, Without<Camera>)>,
+    mut player_query: Walkers,
 ) {
     for (mut transform, direction) in &mut player_query {
         let transform = transform.as_mut();
 
-        let (local_x, local_z) = {
-            let camera: Option<&Transform> = camera_query.iter().next();
-            match camera {
-                Some(t) => {
-                    let delta = t.translation - transform.translation;
-
-                    let forward = Vec3 {
-                        x: delta.x,
-                        y: 0.,
-                        z: delta.z,
-                    }
-                    .normalize();
-
-                    let side_ways = Vec3 {
-                        x: forward.z,
-                        y: 0.,
-                        z: -1. * forward.x,
-                    };
-
-                    (-1. * forward, side_ways)
-                }
-                None => (
-                    Vec3 {
-                        x: 1.,
-                        y: 0.,
-                        z: 0.,
-                    },
-                    Vec3 {
-                        x: 0.,
-                        y: 0.,
-                        z: 1.,
-                    },
-                ),
-            }
-        };
+        let (local_x, local_z) = camera_axes(camera_query.iter().next(), transform);
 
         let forward = local_z * direction.0.z;
         let right = local_x * direction.0.x;
 
-        transform.translation += time.delta_seconds() * (forward + right) * 5.;
+        walk(
+            &nav_mesh,
+            &mut transform.translation,
+            time.delta_seconds() * (forward + right) * MOVE_SPEED * fear.speed(),
+        );
     }
 }
 
@@ -87,6 +165,140 @@ pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Update, (update_direction_from_input, update_pos));
+        app.add_systems(
+            Update,
+            (update_direction_from_input, update_pos)
+                .chain()
+                .run_if(resource_equals(MovementMode::Keyboard)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        prelude::{App, MinimalPlugins},
+        time::TimeUpdateStrategy,
+    };
+
+    use crate::{player::controller::Magnitude, scene::nav_mesh::tests::grid};
+
+    use super::*;
+
+    const STEP: f32 = 0.1;
+
+    /// Headless app walking a player over a 4 x 2 nav mesh from (0.5, 0, 0.5), without a camera
+    /// so forward is +X & right is +Z
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, MovementPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                STEP,
+            )))
+            .insert_resource(grid(&[
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (0, 1),
+                (1, 1),
+                (2, 1),
+                (3, 1),
+            ]))
+            .init_resource::<MovementInput>()
+            .init_resource::<MovementMode>()
+            .init_resource::<Fear>();
+        app.world.spawn((
+            Controllable,
+            Direction(Vec3::ZERO),
+            Transform::from_xyz(0.5, 0., 0.5),
+        ));
+
+        //the first update has no time delta
+        app.update();
+
+        app
+    }
+
+    /// Holds the input for `steps` updates & returns where the player ends up
+    fn replay(app: &mut App, input: &[(Magnitude, Magnitude, usize)]) -> Vec3 {
+        for (forward, right, steps) in input {
+            *app.world.resource_mut::<MovementInput>() = MovementInput {
+                forward: forward.clone(),
+                right: right.clone(),
+            };
+            for _ in 0..*steps {
+                app.update();
+            }
+        }
+
+        let mut query = app.world.query_filtered::<&Transform, With<Controllable>>();
+        query.single(&app.world).translation
+    }
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!(a.distance(b) < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn walks_at_move_speed() {
+        let mut app = app();
+
+        let position = replay(&mut app, &[(Magnitude::Positive, Magnitude::Zero, 2)]);
+        assert_near(position, Vec3::new(0.5 + 2. * STEP * MOVE_SPEED, 0., 0.5));
+
+        let position = replay(&mut app, &[(Magnitude::Zero, Magnitude::Positive, 2)]);
+        assert_near(position, Vec3::new(1.5, 0., 1.5));
+
+        let position = replay(&mut app, &[(Magnitude::Zero, Magnitude::Zero, 5)]);
+        assert_near(position, Vec3::new(1.5, 0., 1.5));
+    }
+
+    #[test]
+    fn slides_along_the_edge_of_the_nav_mesh() {
+        let input = [
+            (Magnitude::Positive, Magnitude::Zero, 2),
+            (Magnitude::Zero, Magnitude::Positive, 2),
+            //diagonally into the +Z edge, then along it
+            (Magnitude::Positive, Magnitude::Positive, 4),
+        ];
+
+        let position = replay(&mut app(), &input);
+        assert_near(position, Vec3::new(3.5, 0., 2.));
+
+        //into the corner, stopped by both edges
+        let position = replay(
+            &mut app(),
+            &[
+                &input[..],
+                &[(Magnitude::Positive, Magnitude::Positive, 10)],
+            ]
+            .concat(),
+        );
+        assert_near(position, Vec3::new(4., 0., 2.));
+
+        //back out the other way
+        let position = replay(
+            &mut app(),
+            &[
+                &input[..],
+                &[(Magnitude::Negative, Magnitude::Negative, 20)],
+            ]
+            .concat(),
+        );
+        assert_near(position, Vec3::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn replays_deterministically() {
+        let input = [
+            (Magnitude::Positive, Magnitude::Negative, 3),
+            (Magnitude::Negative, Magnitude::Positive, 7),
+            (Magnitude::Positive, Magnitude::Positive, 11),
+        ];
+
+        assert_eq!(replay(&mut app(), &input), replay(&mut app(), &input));
     }
 }
@@ -0,0 +1,757 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    ecs::system::SystemParam,
+    log::{error, info},
+    prelude::{
+        App, Commands, Entity, Event, EventReader, EventWriter, Input, IntoSystemConfigs, KeyCode,
+        Plugin, Quat, Query, Res, ResMut, Transform, Update, Vec3, Visibility, With, Without,
+    },
+    time::{Timer, TimerMode},
+};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cryptid::{behaviour::CryptidState, Cryptid, CryptidBrain},
+    lightning::{Lightning, ScaryState},
+    player::{
+        click_to_move::NavPath,
+        controller::{QUICK_LOAD, QUICK_SAVE},
+        fear::Fear,
+        flashlight::FlashlightBattery,
+        inventory::{EquipEvent, Equipment, Hand, Inventory, UnequipEvent},
+        movement::Direction,
+        Controllable,
+    },
+    scene::{
+        item_state::{restore_scene_items, KeptSceneItems},
+        level::{CurrentLevel, LoadLevelEvent},
+        prop::{
+            door::{Door, DoorState},
+            physics::{push_rigid_bodies, Pusher},
+            PropVisibility,
+        },
+        SceneItemId,
+    },
+};
+
+/// Version of the save format written by this build. Bump it whenever [`SaveGame`] changes & add a
+/// migration from the previous version to [`SaveGame::from_ron`]
+pub const SAVE_VERSION: u32 = 1;
+
+const QUICK_SAVE_PATH: &str = "saves/quick.save.ron";
+
+/// Everything persisted by a save
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    pub version: u32,
+    /// level loaded when saving, None when a lone scene was loaded
+    pub level: Option<String>,
+    pub player: PlayerSave,
+    /// [`SceneItemId`]s of the props seen
+    pub seen_props: Vec<String>,
+    /// state of every door by [`SceneItemId`]
+    pub doors: Vec<(String, DoorState)>,
+    pub lightning: Option<LightningSave>,
+    pub cryptids: Vec<CryptidSave>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayerSave {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub inventory: Vec<String>,
+    pub left_hand: Option<String>,
+    pub right_hand: Option<String>,
+    pub flashlight: Option<BatterySave>,
+    pub fear: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BatterySave {
+    pub on: bool,
+    pub charge: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TimerSave {
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
+impl From<&Timer> for TimerSave {
+    fn from(value: &Timer) -> Self {
+        Self {
+            duration: value.duration().as_secs_f32(),
+            elapsed: value.elapsed_secs(),
+        }
+    }
+}
+
+impl From<&TimerSave> for Timer {
+    fn from(value: &TimerSave) -> Self {
+        let mut timer = Timer::from_seconds(value.duration, TimerMode::Once);
+        timer.set_elapsed(std::time::Duration::from_secs_f32(value.elapsed));
+        timer
+    }
+}
+
+/// [`Lightning`] state, the random number generator carries on from the running game
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LightningSave {
+    Calm(TimerSave),
+    Flash(TimerSave),
+    Wait(TimerSave),
+    Thunder(TimerSave),
+    Done,
+}
+
+impl From<&Lightning> for LightningSave {
+    fn from(value: &Lightning) -> Self {
+        match value {
+            Lightning::Calm { wait_timer, .. } => LightningSave::Calm(wait_timer.into()),
+            Lightning::Scary { state, .. } => match state {
+                ScaryState::Lightning(timer) => LightningSave::Flash(timer.into()),
+                ScaryState::Wait(timer) => LightningSave::Wait(timer.into()),
+                ScaryState::Thunder(timer) => LightningSave::Thunder(timer.into()),
+                ScaryState::Done => LightningSave::Done,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CryptidSave {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub state: CryptidState,
+    /// seconds before the state times out
+    pub time_left: f32,
+    pub last_seen: Option<Vec3>,
+}
+
+/// Why a save couldn't be written or read
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+    /// written by a newer build
+    NewerVersion(u32),
+    /// written by a build too old to be migrated
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{err}"),
+            SaveError::Parse(err) => write!(f, "the save is corrupted: {err}"),
+            SaveError::Write(err) => write!(f, "could not write the save: {err}"),
+            SaveError::NewerVersion(version) => write!(
+                f,
+                "the save is from a newer version of the game (format {version}, this build reads up to {SAVE_VERSION})"
+            ),
+            SaveError::UnsupportedVersion(version) => {
+                write!(f, "the save format {version} is no longer supported")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        SaveError::Parse(err)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(err: ron::Error) -> Self {
+        SaveError::Write(err)
+    }
+}
+
+/// Just the version of a save, read before the rest to pick a migration
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+impl SaveGame {
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        Ok(ron::ser::to_string_pretty(self, PrettyConfig::default())?)
+    }
+
+    /// Reads a save written by this build or migrates one from an older build
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        let SaveHeader { version } = ron::from_str(text)?;
+
+        match version {
+            SAVE_VERSION => Ok(ron::from_str(text)?),
+            //older formats are read as their own struct & migrated one version at a time, eg:
+            //1 => Ok(SaveGameV1::into(ron::from_str::<SaveGameV1>(text)?)),
+            version if version > SAVE_VERSION => Err(SaveError::NewerVersion(version)),
+            version => Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        Ok(fs::write(path, self.to_ron()?)?)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
+
+/// Writes a [`SaveGame`] of the world to the path
+#[derive(Event, Debug, Clone)]
+pub struct SaveGameEvent(pub PathBuf);
+
+/// Restores the world from the [`SaveGame`] at the path
+#[derive(Event, Debug, Clone)]
+pub struct LoadGameEvent(pub PathBuf);
+
+type Players<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static Inventory,
+        Option<&'static Equipment>,
+        Option<&'static FlashlightBattery>,
+    ),
+    (With<Controllable>, Without<Cryptid>),
+>;
+
+/// Everything read to save the game
+#[derive(SystemParam)]
+pub struct SavedWorld<'w, 's> {
+    current_level: Option<Res<'w, CurrentLevel>>,
+    fear: Res<'w, Fear>,
+    player_query: Players<'w, 's>,
+    kept: Res<'w, KeptSceneItems>,
+    prop_query: Query<'w, 's, (&'static SceneItemId, &'static PropVisibility)>,
+    door_query: Query<'w, 's, (&'static SceneItemId, &'static Door)>,
+    lightning_query: Query<'w, 's, &'static Lightning>,
+    cryptid_query: Query<'w, 's, (&'static Transform, &'static CryptidBrain), With<Cryptid>>,
+}
+
+impl<'w, 's> SavedWorld<'w, 's> {
+    /// None until the player is spawned
+    pub fn save(&self) -> Option<SaveGame> {
+        let (transform, inventory, equipment, battery) = self.player_query.iter().next()?;
+        let held = |hand| {
+            equipment
+                .and_then(|equipment| equipment.hand(hand))
+                .map(|held| held.item.clone())
+        };
+
+        //items of the rooms streamed out are saved as they were kept, the live ones as they are
+        let live_props: HashSet<&String> = self.prop_query.iter().map(|(id, _)| &id.0).collect();
+        let mut seen_props: Vec<String> = self
+            .kept
+            .seen_props
+            .iter()
+            .filter(|id| !live_props.contains(id))
+            .cloned()
+            .chain(
+                self.prop_query
+                    .iter()
+                    .filter(|(_, visibility)| **visibility == PropVisibility::Seen)
+                    .map(|(id, _)| id.0.clone()),
+            )
+            .collect();
+        seen_props.sort();
+
+        let mut doors = self.kept.doors.clone();
+        doors.extend(
+            self.door_query
+                .iter()
+                .map(|(id, door)| (id.0.clone(), door.state.clone())),
+        );
+        let mut doors: Vec<(String, DoorState)> = doors.into_iter().collect();
+        doors.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Some(SaveGame {
+            version: SAVE_VERSION,
+            level: self.current_level.as_ref().map(|level| level.name.clone()),
+            player: PlayerSave {
+                translation: transform.translation,
+                rotation: transform.rotation,
+                inventory: inventory.0.clone(),
+                left_hand: held(Hand::Left),
+                right_hand: held(Hand::Right),
+                flashlight: battery.map(|battery| BatterySave {
+                    on: battery.on,
+                    charge: battery.charge,
+                }),
+                fear: self.fear.0,
+            },
+            seen_props,
+            doors,
+            lightning: self.lightning_query.iter().next().map(LightningSave::from),
+            cryptids: self
+                .cryptid_query
+                .iter()
+                .map(|(transform, brain)| CryptidSave {
+                    translation: transform.translation,
+                    rotation: transform.rotation,
+                    state: brain.state,
+                    time_left: brain.time_left(),
+                    last_seen: brain.last_seen,
+                })
+                .collect(),
+        })
+    }
+}
+
+type MutPlayers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Inventory,
+        Option<&'static mut FlashlightBattery>,
+        Option<&'static mut Pusher>,
+        Option<&'static mut Direction>,
+    ),
+    (With<Controllable>, Without<Cryptid>),
+>;
+
+/// Cryptids restored from a save
+type MutCryptids<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, &'static mut CryptidBrain),
+    (With<Cryptid>, Without<Controllable>),
+>;
+
+/// Everything a save is restored into, except scene items, see [`KeptSceneItems`]
+#[derive(SystemParam)]
+pub struct LoadedWorld<'w, 's> {
+    commands: Commands<'w, 's>,
+    current_level: Option<Res<'w, CurrentLevel>>,
+    fear: ResMut<'w, Fear>,
+    kept: ResMut<'w, KeptSceneItems>,
+    player_query: MutPlayers<'w, 's>,
+    lightning_query: Query<'w, 's, (&'static mut Lightning, &'static mut Visibility)>,
+    cryptid_query: MutCryptids<'w, 's>,
+    load_level_event: EventWriter<'w, LoadLevelEvent>,
+    equip_event: EventWriter<'w, EquipEvent>,
+    unequip_event: EventWriter<'w, UnequipEvent>,
+}
+
+impl<'w, 's> LoadedWorld<'w, 's> {
+    pub fn load(&mut self, save: &SaveGame) {
+        let loaded_level = self.current_level.as_ref().map(|level| &level.name);
+        if let (Some(level), false) = (&save.level, save.level.as_ref() == loaded_level) {
+            self.load_level_event.send(LoadLevelEvent(level.clone()));
+        }
+
+        if let Some((player, mut transform, mut inventory, battery, pusher, direction)) =
+            self.player_query.iter_mut().next()
+        {
+            transform.translation = save.player.translation;
+            transform.rotation = save.player.rotation;
+            inventory.0 = save.player.inventory.clone();
+
+            //teleported, it neither pushes props along the jump nor walks on to where it was going
+            if let Some(mut pusher) = pusher {
+                pusher.teleported();
+            }
+            if let Some(mut direction) = direction {
+                direction.0 = Vec3::ZERO;
+            }
+            self.commands.entity(player).remove::<NavPath>();
+
+            for (hand, item) in [
+                (Hand::Left, &save.player.left_hand),
+                (Hand::Right, &save.player.right_hand),
+            ] {
+                self.unequip_event.send(UnequipEvent {
+                    holder: player,
+                    hand,
+                });
+                if let Some(item) = item {
+                    self.equip_event.send(EquipEvent {
+                        holder: player,
+                        item: item.clone(),
+                        hand: Some(hand),
+                    });
+                }
+            }
+
+            if let (Some(mut battery), Some(saved)) = (battery, save.player.flashlight) {
+                battery.on = saved.on;
+                battery.charge = saved.charge;
+            }
+        }
+        self.fear.0 = save.player.fear;
+
+        if let (Some((mut lightning, mut visibility)), Some(saved)) =
+            (self.lightning_query.iter_mut().next(), &save.lightning)
+        {
+            let rng = match lightning.as_ref() {
+                Lightning::Calm { rng, .. } | Lightning::Scary { rng, .. } => rng.clone(),
+            };
+
+            *lightning = match saved {
+                LightningSave::Calm(timer) => Lightning::Calm {
+                    wait_timer: timer.into(),
+                    rng,
+                },
+                LightningSave::Flash(timer) => Lightning::Scary {
+                    state: ScaryState::Lightning(timer.into()),
+                    rng,
+                },
+                LightningSave::Wait(timer) => Lightning::Scary {
+                    state: ScaryState::Wait(timer.into()),
+                    rng,
+                },
+                LightningSave::Thunder(timer) => Lightning::Scary {
+                    state: ScaryState::Thunder(timer.into()),
+                    rng,
+                },
+                LightningSave::Done => Lightning::Scary {
+                    state: ScaryState::Done,
+                    rng,
+                },
+            };
+            *visibility = match saved {
+                LightningSave::Flash(_) => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+
+        for ((mut transform, mut brain), saved) in self.cryptid_query.iter_mut().zip(&save.cryptids)
+        {
+            transform.translation = saved.translation;
+            transform.rotation = saved.rotation;
+            brain.restore(saved.state, saved.time_left, saved.last_seen);
+        }
+
+        *self.kept = KeptSceneItems {
+            seen_props: save.seen_props.iter().cloned().collect(),
+            doors: save.doors.iter().cloned().collect(),
+            ..Default::default()
+        };
+    }
+}
+
+fn quick_save_and_load(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut save_event: EventWriter<SaveGameEvent>,
+    mut load_event: EventWriter<LoadGameEvent>,
+) {
+    if keyboard_input.just_pressed(QUICK_SAVE) {
+        save_event.send(SaveGameEvent(QUICK_SAVE_PATH.into()));
+    }
+    if keyboard_input.just_pressed(QUICK_LOAD) {
+        load_event.send(LoadGameEvent(QUICK_SAVE_PATH.into()));
+    }
+}
+
+pub fn save_game(mut save_event: EventReader<SaveGameEvent>, world: SavedWorld) {
+    for SaveGameEvent(path) in save_event.iter() {
+        let Some(save) = world.save() else {
+            error!(
+                "nothing to save to {}, the player isn't spawned",
+                path.display()
+            );
+            continue;
+        };
+
+        match save.write(path) {
+            Ok(()) => info!("saved the game to {}", path.display()),
+            Err(err) => error!("could not save the game to {}: {err}", path.display()),
+        }
+    }
+}
+
+pub fn load_game(mut load_event: EventReader<LoadGameEvent>, mut world: LoadedWorld) {
+    for LoadGameEvent(path) in load_event.iter() {
+        match SaveGame::read(path) {
+            Ok(save) => {
+                world.load(&save);
+                info!("loaded the game from {}", path.display());
+            }
+            Err(err) => error!("could not load the game from {}: {err}", path.display()),
+        }
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveGameEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_systems(
+                Update,
+                (quick_save_and_load, save_game, load_game)
+                    .chain()
+                    //pushers see the loaded position once the transforms propagate
+                    .after(push_rigid_bodies)
+                    .before(restore_scene_items),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use bevy::{
+        ecs::system::SystemState,
+        prelude::{MinimalPlugins, Vec3},
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use crate::player::interaction::{Interactable, Verb};
+
+    use super::*;
+
+    fn save() -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            level: Some("dev_playground".into()),
+            player: PlayerSave {
+                translation: Vec3::new(1., 0., -2.),
+                rotation: Quat::from_rotation_y(0.5),
+                inventory: vec!["flashlight".into(), "basement_key".into()],
+                left_hand: Some("basement_key".into()),
+                right_hand: None,
+                flashlight: Some(BatterySave {
+                    on: false,
+                    charge: 0.25,
+                }),
+                fear: 0.5,
+            },
+            seen_props: vec!["dev_playground/plastic_props/0".into()],
+            doors: vec![(
+                "dev_playground/doors/1".into(),
+                DoorState::Locked("basement".into()),
+            )],
+            lightning: Some(LightningSave::Wait(TimerSave {
+                duration: 2.,
+                elapsed: 0.5,
+            })),
+            cryptids: vec![CryptidSave {
+                translation: Vec3::new(5., 0., -5.),
+                rotation: Quat::IDENTITY,
+                state: CryptidState::Investigate(Vec3::new(2., 0., -3.)),
+                time_left: 3.,
+                last_seen: Some(Vec3::ONE),
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let save = save();
+        assert_eq!(SaveGame::from_ron(&save.to_ron().unwrap()).unwrap(), save);
+    }
+
+    #[test]
+    fn rejects_newer_and_unsupported_versions() {
+        let newer = SaveGame {
+            version: SAVE_VERSION + 1,
+            ..save()
+        };
+        assert!(matches!(
+            SaveGame::from_ron(&newer.to_ron().unwrap()),
+            Err(SaveError::NewerVersion(version)) if version == SAVE_VERSION + 1
+        ));
+
+        let ancient = SaveGame {
+            version: 0,
+            ..save()
+        };
+        assert!(matches!(
+            SaveGame::from_ron(&ancient.to_ron().unwrap()),
+            Err(SaveError::UnsupportedVersion(0))
+        ));
+    }
+
+    #[test]
+    fn loads_what_was_saved() {
+        let path =
+            std::env::temp_dir().join(format!("cryptid_game_{}.save.ron", std::process::id()));
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, SavePlugin))
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Fear>()
+            .init_resource::<KeptSceneItems>()
+            .add_systems(Update, restore_scene_items)
+            .add_event::<LoadLevelEvent>()
+            .add_event::<EquipEvent>()
+            .add_event::<UnequipEvent>();
+
+        let player = app
+            .world
+            .spawn((
+                Controllable,
+                Transform::from_xyz(1., 0., -2.),
+                Inventory(vec!["flashlight".into(), "basement_key".into()]),
+                Direction(Vec3::ZERO),
+            ))
+            .id();
+        let door = app
+            .world
+            .spawn((
+                SceneItemId::new("dev_playground", "doors", 1),
+                Transform::default(),
+                Door::new(
+                    DoorState::Locked("basement".into()),
+                    Vec3::ZERO,
+                    Vec3::X,
+                    90.,
+                ),
+                Interactable::new(Verb::Open, "Unlock the door"),
+            ))
+            .id();
+        let lightning = app
+            .world
+            .spawn((
+                Lightning::Scary {
+                    state: ScaryState::Wait(Timer::from_seconds(2., TimerMode::Once)),
+                    rng: SmallRng::seed_from_u64(0),
+                },
+                Visibility::Hidden,
+            ))
+            .id();
+
+        app.update();
+        app.world.send_event(SaveGameEvent(path.clone()));
+        app.update();
+
+        //play on after saving
+        app.world.get_mut::<Transform>(player).unwrap().translation = Vec3::new(8., 0., -8.);
+        app.world.get_mut::<Inventory>(player).unwrap().0 = vec!["flashlight".into()];
+        app.world
+            .entity_mut(player)
+            .insert(NavPath(VecDeque::from([Vec3::new(9., 0., -9.)])));
+        app.world.get_mut::<Direction>(player).unwrap().0 = Vec3::Z;
+        {
+            let mut entity = app.world.entity_mut(door);
+            let mut transform = *entity.get::<Transform>().unwrap();
+            entity
+                .get_mut::<Door>()
+                .unwrap()
+                .snap(DoorState::Open, &mut transform);
+            entity.insert(transform);
+        }
+        *app.world.get_mut::<Lightning>(lightning).unwrap() = Lightning::Scary {
+            state: ScaryState::Done,
+            rng: SmallRng::seed_from_u64(0),
+        };
+
+        app.world.send_event(LoadGameEvent(path.clone()));
+        app.update();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            app.world.get::<Transform>(player).unwrap().translation,
+            Vec3::new(1., 0., -2.)
+        );
+        //stopped where it was restored
+        assert!(app.world.get::<NavPath>(player).is_none());
+        assert_eq!(app.world.get::<Direction>(player).unwrap().0, Vec3::ZERO);
+        assert_eq!(
+            app.world.get::<Inventory>(player).unwrap().0,
+            vec!["flashlight".to_string(), "basement_key".to_string()]
+        );
+        assert_eq!(
+            app.world.get::<Door>(door).unwrap().state,
+            DoorState::Locked("basement".into())
+        );
+        assert_eq!(
+            app.world.get::<Interactable>(door).unwrap().prompt,
+            "Unlock the door"
+        );
+        assert_eq!(
+            app.world.get::<Transform>(door).unwrap().rotation,
+            Quat::IDENTITY
+        );
+        assert!(matches!(
+            app.world.get::<Lightning>(lightning).unwrap(),
+            Lightning::Scary {
+                state: ScaryState::Wait(timer),
+                ..
+            } if timer.duration().as_secs_f32() == 2.
+        ));
+    }
+
+    #[test]
+    fn saves_the_items_of_rooms_streamed_out() {
+        let mut app = App::new();
+        app.init_resource::<Fear>().insert_resource(KeptSceneItems {
+            seen_props: ["annex/plastic_props/2".to_string()].into_iter().collect(),
+            doors: [
+                ("annex/doors/0".to_string(), DoorState::Open),
+                ("dev_playground/doors/1".to_string(), DoorState::Open),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        app.world
+            .spawn((Controllable, Transform::default(), Inventory(Vec::new())));
+        app.world.spawn((
+            SceneItemId::new("dev_playground", "plastic_props", 0),
+            PropVisibility::Seen,
+        ));
+        app.world.spawn((
+            SceneItemId::new("dev_playground", "doors", 1),
+            Door::new(
+                DoorState::Locked("basement".into()),
+                Vec3::ZERO,
+                Vec3::X,
+                90.,
+            ),
+        ));
+
+        let mut state: SystemState<SavedWorld> = SystemState::new(&mut app.world);
+        let save = state.get(&app.world).save().unwrap();
+
+        assert_eq!(
+            save.seen_props,
+            vec![
+                "annex/plastic_props/2".to_string(),
+                "dev_playground/plastic_props/0".to_string()
+            ]
+        );
+        //the live door wins over its stale kept state
+        assert_eq!(
+            save.doors,
+            vec![
+                ("annex/doors/0".to_string(), DoorState::Open),
+                (
+                    "dev_playground/doors/1".to_string(),
+                    DoorState::Locked("basement".into())
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::AssetIo,
+    log::{error, info},
+    prelude::{AssetServer, Res, ResMut, Resource},
+};
+
+/// Kinds of assets that can be discovered by the catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogKind {
+    Floor,
+    Wall,
+    PlasticProp,
+}
+
+impl CatalogKind {
+    /// Folder (relative to the asset folder) that holds every asset of this kind
+    pub fn root(&self) -> &'static str {
+        match self {
+            CatalogKind::Floor | CatalogKind::Wall => "scenes",
+            CatalogKind::PlasticProp => "props",
+        }
+    }
+
+    /// Files (relative to the asset's directory) that must exist for the asset to be usable
+    pub fn required_files(&self) -> &'static [&'static str] {
+        match self {
+            CatalogKind::Floor => &[
+                "mesh/mesh.glb",
+                "textures/colour.png",
+                "textures/metallic.png",
+                "textures/normal.png",
+                "textures/texture_map.png",
+            ],
+            CatalogKind::Wall => &["mesh/mesh.glb", "textures/normal.png"],
+            CatalogKind::PlasticProp => &["mesh/mesh.glb", "textures/noise.png"],
+        }
+    }
+
+    /// Kind of the scene asset in `dir`. Any of the floor only textures makes it a floor, so a
+    /// floor missing some of them is reported as an incomplete floor rather than taken for a wall
+    fn of_scene_dir(asset_io: &dyn AssetIo, dir: &Path) -> Self {
+        let mut floor_only = CatalogKind::Floor
+            .required_files()
+            .iter()
+            .filter(|file| !CatalogKind::Wall.required_files().contains(file));
+
+        match floor_only.any(|file| asset_io.is_file(&dir.join(file))) {
+            true => CatalogKind::Floor,
+            false => CatalogKind::Wall,
+        }
+    }
+}
+
+impl Display for CatalogKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogKind::Floor => write!(f, "floor"),
+            CatalogKind::Wall => write!(f, "wall"),
+            CatalogKind::PlasticProp => write!(f, "plastic prop"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CatalogEntry {
+    pub kind: CatalogKind,
+    /// key used by [`super::floor::Floors`], [`super::wall::Walls`] & [`super::prop::Props`]
+    pub key: String,
+}
+
+/// Asset directory that looks like a catalog entry but is missing required files
+#[derive(Debug, Clone)]
+pub struct CatalogIssue {
+    pub kind: CatalogKind,
+    pub key: String,
+    pub missing: Vec<&'static str>,
+}
+
+impl Display for CatalogIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?} ({}/{}) is missing: {}",
+            self.kind,
+            self.key,
+            self.kind.root(),
+            self.key,
+            self.missing.join(", ")
+        )
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct AssetCatalog {
+    pub entries: Vec<CatalogEntry>,
+    pub issues: Vec<CatalogIssue>,
+}
+
+impl AssetCatalog {
+    /// Scans `scenes/*/*` for floors & walls and `props/*` for props
+    ///
+    /// Scene directories without a `textures` folder (nav meshes, shadow casters, ...) are not
+    /// catalog entries and are skipped
+    pub fn scan(asset_io: &dyn AssetIo) -> Self {
+        let mut catalog = AssetCatalog::default();
+
+        for scene in sub_directories(asset_io, Path::new("scenes")) {
+            for dir in sub_directories(asset_io, &scene) {
+                if !asset_io.is_dir(&dir.join("textures")) {
+                    continue;
+                }
+
+                catalog.add(
+                    asset_io,
+                    CatalogKind::of_scene_dir(asset_io, &dir),
+                    format!("{}/{}", name(&scene), name(&dir)),
+                    &dir,
+                );
+            }
+        }
+
+        for dir in sub_directories(asset_io, Path::new("props")) {
+            catalog.add(asset_io, CatalogKind::PlasticProp, name(&dir), &dir);
+        }
+
+        catalog
+    }
+
+    fn add(&mut self, asset_io: &dyn AssetIo, kind: CatalogKind, key: String, dir: &Path) {
+        let missing: Vec<&'static str> = kind
+            .required_files()
+            .iter()
+            .filter(|file| !asset_io.is_file(&dir.join(file)))
+            .copied()
+            .collect();
+
+        match missing.is_empty() {
+            true => self.entries.push(CatalogEntry { kind, key }),
+            false => self.issues.push(CatalogIssue { kind, key, missing }),
+        }
+    }
+
+    pub fn keys(&self, kind: CatalogKind) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.kind == kind)
+            .map(|entry| entry.key.as_str())
+    }
+
+    pub fn issue(&self, key: &str) -> Option<&CatalogIssue> {
+        self.issues.iter().find(|issue| issue.key == key)
+    }
+}
+
+fn sub_directories(asset_io: &dyn AssetIo, path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = asset_io.read_directory(path) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries.filter(|entry| asset_io.is_dir(entry)).collect();
+    dirs.sort();
+
+    dirs
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+pub fn build_asset_catalog(mut catalog: ResMut<AssetCatalog>, asset_server: Res<AssetServer>) {
+    *catalog = AssetCatalog::scan(asset_server.asset_io());
+
+    info!(
+        "asset catalog: {} entries, {} incomplete",
+        catalog.entries.len(),
+        catalog.issues.len()
+    );
+    for issue in &catalog.issues {
+        error!("incomplete {issue}");
+    }
+}
@@ -0,0 +1,454 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    ecs::system::EntityCommands,
+    prelude::{shape, Mesh, Quat, Transform, Vec2, Vec3},
+    reflect::{TypePath, TypeUuid},
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::{
+    perception::Occluder,
+    player::{
+        interaction::{Interactable, Verb},
+        inventory::Item,
+        target::PlayerTargetSet,
+    },
+};
+
+use super::prop::{
+    door::{Door, DoorState},
+    physics::{ColliderShape, ImpactSound, RigidBody},
+    sound_source::{SoundSource, SoundVolume},
+    Forgettable, PropVisibilityTarget,
+};
+
+/// Describes everything spawned for a scene. Loaded from `scenes/{name}/{name}.scene.ron`
+///
+/// Floors, walls and props reference the keys used by [`super::floor::Floors`],
+/// [`super::wall::Walls`] and [`super::prop::Props`]
+#[derive(Deserialize, TypeUuid, TypePath, Debug, Clone, Default)]
+#[uuid = "4f3c1d5a-2b8e-4a61-9f0d-7c2e5b9a1e33"]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub shadow_casters: Vec<MeshDescription>,
+    #[serde(default)]
+    pub nav_mesh: Option<MeshDescription>,
+    #[serde(default)]
+    pub nav_areas: Vec<NavAreaDescription>,
+    #[serde(default)]
+    pub floors: Vec<ItemDescription>,
+    #[serde(default)]
+    pub walls: Vec<ItemDescription>,
+    #[serde(default)]
+    pub plastic_props: Vec<ItemDescription>,
+    #[serde(default)]
+    pub sound_emitters: Vec<SoundEmitterDescription>,
+    #[serde(default)]
+    pub doors: Vec<DoorPropDescription>,
+    #[serde(default)]
+    pub pickups: Vec<PickupDescription>,
+    /// where the cryptid appears once the scene spawns, unless it's already roaming
+    #[serde(default)]
+    pub cryptid_spawn: Option<TransformDescription>,
+}
+
+/// Mesh loaded straight from an asset path (shadow casters & nav meshes)
+#[derive(Deserialize, Debug, Clone)]
+pub struct MeshDescription {
+    pub mesh: String,
+    #[serde(default)]
+    pub transform: TransformDescription,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+}
+
+/// Walkable rectangle added to the nav mesh, from `min` to `max` in the scene's XZ plane.
+/// Rectangles sharing a whole edge are joined, so doorways line up with the rooms they connect
+#[derive(Deserialize, Debug, Clone)]
+pub struct NavAreaDescription {
+    pub min: Vec2,
+    pub max: Vec2,
+    #[serde(default)]
+    pub height: f32,
+}
+
+impl NavAreaDescription {
+    pub fn mesh(&self) -> Mesh {
+        let (min, max, y) = (self.min, self.max, self.height);
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [min.x, y, min.y],
+                [max.x, y, min.y],
+                [max.x, y, max.y],
+                [min.x, y, max.y],
+            ],
+        );
+        mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 2, 3])));
+
+        mesh
+    }
+}
+
+/// Floor, wall or prop referenced by its registry key
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemDescription {
+    pub key: String,
+    #[serde(default)]
+    pub transform: TransformDescription,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+}
+
+/// Transform written in a designer friendly form
+///
+/// `rotation` is a set of XYZ euler angles in degrees
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransformDescription {
+    #[serde(default)]
+    pub translation: Vec3,
+    #[serde(default)]
+    pub rotation: Vec3,
+    #[serde(default = "TransformDescription::default_scale")]
+    pub scale: Vec3,
+}
+
+impl TransformDescription {
+    fn default_scale() -> Vec3 {
+        Vec3::ONE
+    }
+}
+
+impl Default for TransformDescription {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Vec3::ZERO,
+            scale: Self::default_scale(),
+        }
+    }
+}
+
+impl From<&TransformDescription> for Transform {
+    fn from(value: &TransformDescription) -> Self {
+        Transform {
+            translation: value.translation,
+            rotation: Quat::from_euler(
+                bevy::prelude::EulerRot::XYZ,
+                value.rotation.x.to_radians(),
+                value.rotation.y.to_radians(),
+                value.rotation.z.to_radians(),
+            ),
+            scale: value.scale,
+        }
+    }
+}
+
+/// Marker components that can be attached to any spawned scene entity
+#[derive(Deserialize, Debug, Clone)]
+pub enum Marker {
+    PlayerTargetSet,
+    #[serde(alias = "PropVisibilityBlocker")]
+    Occluder,
+    PropVisibilityTarget(Vec<Vec3>),
+    Forgettable,
+    /// only simulated on entities with a mesh, eg: props
+    RigidBody(RigidBodyDescription),
+    /// also needs [`Marker::PlayerTargetSet`] to be hovered
+    Interactable(Interactable),
+    /// item key picked up by a [`crate::player::interaction::Verb::PickUp`] interactable
+    Item(String),
+    /// opened with [`crate::player::interaction::Verb::Open`], also needs
+    /// [`Marker::PlayerTargetSet`] to be hovered
+    Door(DoorDescription),
+}
+
+impl Marker {
+    pub fn insert(&self, entity: &mut EntityCommands) {
+        match self {
+            Marker::PlayerTargetSet => {
+                entity.insert(PlayerTargetSet);
+            }
+            Marker::Occluder => {
+                entity.insert(Occluder);
+            }
+            Marker::PropVisibilityTarget(points) => {
+                entity.insert(PropVisibilityTarget::from(points.clone()));
+            }
+            Marker::Forgettable => {
+                entity.insert(Forgettable);
+            }
+            Marker::RigidBody(description) => {
+                entity.insert(RigidBody::from(description));
+
+                if let Some(sound) = &description.impact_sound {
+                    entity.insert(ImpactSound(sound.clone()));
+                }
+            }
+            Marker::Interactable(interactable) => {
+                entity.insert(interactable.clone());
+            }
+            Marker::Item(item) => {
+                entity.insert(Item(item.clone()));
+            }
+            Marker::Door(description) => {
+                let door = Door::from(description);
+
+                entity.insert((Interactable::new(Verb::Open, door.prompt()), door));
+            }
+        }
+    }
+}
+
+pub fn insert_markers(entity: &mut EntityCommands, markers: &[Marker]) {
+    for marker in markers {
+        marker.insert(entity);
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RigidBodyDescription {
+    #[serde(default = "RigidBodyDescription::default_mass")]
+    pub mass: f32,
+    #[serde(default = "RigidBodyDescription::default_friction")]
+    pub friction: f32,
+    #[serde(default = "RigidBodyDescription::default_restitution")]
+    pub restitution: f32,
+    #[serde(default)]
+    pub shape: ColliderShape,
+    /// sound played when the body hits the floor, a default thud without one
+    #[serde(default)]
+    pub impact_sound: Option<String>,
+}
+
+impl RigidBodyDescription {
+    fn default_mass() -> f32 {
+        1.
+    }
+    fn default_friction() -> f32 {
+        0.5
+    }
+    fn default_restitution() -> f32 {
+        0.2
+    }
+}
+
+impl From<&RigidBodyDescription> for RigidBody {
+    fn from(value: &RigidBodyDescription) -> Self {
+        RigidBody::new(value.mass, value.friction, value.restitution, value.shape)
+    }
+}
+
+/// Door prop swinging around a vertical hinge. Points are in the prop's space
+#[derive(Deserialize, Debug, Clone)]
+pub struct DoorDescription {
+    #[serde(default = "DoorDescription::default_state")]
+    pub state: DoorState,
+    /// bottom of the hinge axis
+    #[serde(default)]
+    pub hinge: Vec3,
+    /// bottom of the edge opposite to the hinge
+    pub latch: Vec3,
+    /// degrees swung when open, counter clockwise from above
+    #[serde(default = "DoorDescription::default_open_angle")]
+    pub open_angle: f32,
+}
+
+impl DoorDescription {
+    fn default_state() -> DoorState {
+        DoorState::Closed
+    }
+    fn default_open_angle() -> f32 {
+        90.
+    }
+}
+
+impl From<&DoorDescription> for Door {
+    fn from(value: &DoorDescription) -> Self {
+        Door::new(
+            value.state.clone(),
+            value.hinge,
+            value.latch,
+            value.open_angle,
+        )
+    }
+}
+
+/// Door leaf spawned as a box, hinged along its edge at the origin of `transform` & latching
+/// `size.x` along its X axis. `size` is (width, height, thickness)
+#[derive(Deserialize, Debug, Clone)]
+pub struct DoorPropDescription {
+    #[serde(default)]
+    pub transform: TransformDescription,
+    #[serde(default = "DoorPropDescription::default_size")]
+    pub size: Vec3,
+    #[serde(default = "DoorDescription::default_state")]
+    pub state: DoorState,
+    #[serde(default = "DoorDescription::default_open_angle")]
+    pub open_angle: f32,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+}
+
+impl DoorPropDescription {
+    fn default_size() -> Vec3 {
+        Vec3::new(1., 2.1, 0.05)
+    }
+
+    /// The [`Marker::Door`] swinging the leaf
+    pub fn door(&self) -> DoorDescription {
+        DoorDescription {
+            state: self.state.clone(),
+            hinge: Vec3::ZERO,
+            latch: Vec3::X * self.size.x,
+            open_angle: self.open_angle,
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        shape::Box {
+            min_x: 0.,
+            max_x: self.size.x,
+            min_y: 0.,
+            max_y: self.size.y,
+            min_z: -self.size.z / 2.,
+            max_z: self.size.z / 2.,
+        }
+        .into()
+    }
+}
+
+/// Item lying in the scene as a small cube, picked up into the inventory by its key
+#[derive(Deserialize, Debug, Clone)]
+pub struct PickupDescription {
+    pub item: String,
+    #[serde(default)]
+    pub transform: TransformDescription,
+    #[serde(default = "PickupDescription::default_size")]
+    pub size: f32,
+    #[serde(default = "PickupDescription::default_prompt")]
+    pub prompt: String,
+}
+
+impl PickupDescription {
+    fn default_size() -> f32 {
+        0.1
+    }
+    fn default_prompt() -> String {
+        "Pick up".into()
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        shape::Cube::new(self.size).into()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SoundEmitterDescription {
+    pub source: String,
+    pub sound_source: SoundSource,
+    #[serde(default = "SoundEmitterDescription::default_volume")]
+    pub volume: f32,
+    #[serde(default)]
+    pub looping: bool,
+    #[serde(default)]
+    pub sound_volume: SoundVolumeDescription,
+}
+
+impl SoundEmitterDescription {
+    fn default_volume() -> f32 {
+        1.
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SoundVolumeDescription {
+    pub max: f32,
+    pub drop_of_dist: f32,
+}
+
+impl Default for SoundVolumeDescription {
+    fn default() -> Self {
+        Self {
+            max: 1.,
+            drop_of_dist: 10.,
+        }
+    }
+}
+
+impl From<&SoundVolumeDescription> for SoundVolume {
+    fn from(value: &SoundVolumeDescription) -> Self {
+        SoundVolume::new(value.max, value.drop_of_dist)
+    }
+}
+
+#[derive(Default)]
+pub struct SceneDescriptionLoader;
+
+impl AssetLoader for SceneDescriptionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let description: SceneDescription = ron::de::from_bytes(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(description));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["scene.ron"]
+    }
+}
+
+pub fn scene_path(name: &str) -> String {
+    format!("scenes/{name}/{name}.scene.ron")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dev_scene_has_a_locked_door_and_its_key() {
+        let description: SceneDescription = ron::de::from_str(include_str!(
+            "../../assets/scenes/dev_playground/dev_playground.scene.ron"
+        ))
+        .unwrap();
+
+        let locks: Vec<DoorState> = description
+            .doors
+            .iter()
+            .map(|door| door.door().state)
+            .collect();
+        assert_eq!(
+            locks,
+            vec![DoorState::Closed, DoorState::Locked("basement".into())]
+        );
+        assert!(description
+            .pickups
+            .iter()
+            .any(|pickup| pickup.item == "basement_key"));
+    }
+
+    #[test]
+    fn dev_scene_spawns_the_cryptid_away_from_pickups() {
+        let description: SceneDescription = ron::de::from_str(include_str!(
+            "../../assets/scenes/dev_playground/dev_playground.scene.ron"
+        ))
+        .unwrap();
+
+        let spawn = description.cryptid_spawn.unwrap().translation;
+        for pickup in &description.pickups {
+            assert!(spawn.distance(pickup.transform.translation) > 2.);
+        }
+    }
+}
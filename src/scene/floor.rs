@@ -12,6 +12,8 @@ use bevy::{
     },
 };
 
+use super::catalog::{build_asset_catalog, AssetCatalog, CatalogKind};
+
 #[derive(Resource)]
 pub struct Floors(pub HashMap<String, Floor>);
 
@@ -40,7 +42,7 @@ pub fn into_mesh_bundle(
     }
 }
 
-fn load_floor(asset_server: &ResMut<AssetServer>, dir: &'static str) -> Floor {
+fn load_floor(asset_server: &ResMut<AssetServer>, dir: &str) -> Floor {
     Floor {
         mesh: asset_server.load(format!("scenes/{dir}/mesh/mesh.glb#Mesh0/Primitive0")),
         material: FloorMaterial {
@@ -59,15 +61,17 @@ fn load_floor(asset_server: &ResMut<AssetServer>, dir: &'static str) -> Floor {
     }
 }
 
-pub fn load_floors(mut floors: ResMut<Floors>, asset_server: ResMut<AssetServer>) {
-    floors.as_mut().0.insert(
-        "dev_playground/metal_grate_floor".into(),
-        load_floor(&asset_server, "dev_playground/metal_grate_floor"),
-    );
-    floors.as_mut().0.insert(
-        "dev_playground/stainless_steel_floor".into(),
-        load_floor(&asset_server, "dev_playground/stainless_steel_floor"),
-    );
+pub fn load_floors(
+    mut floors: ResMut<Floors>,
+    catalog: Res<AssetCatalog>,
+    asset_server: ResMut<AssetServer>,
+) {
+    for key in catalog.keys(CatalogKind::Floor) {
+        floors
+            .as_mut()
+            .0
+            .insert(key.into(), load_floor(&asset_server, key));
+    }
 }
 
 pub struct FloorPlugin;
@@ -78,7 +82,7 @@ impl Plugin for FloorPlugin {
 
         app.insert_resource(floors)
             .add_plugins((MaterialPlugin::<FloorMaterial>::default(),))
-            .add_systems(PreStartup, load_floors);
+            .add_systems(PreStartup, load_floors.after(build_asset_catalog));
     }
 }
 
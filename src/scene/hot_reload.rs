@@ -0,0 +1,146 @@
+use bevy::{
+    asset::{Asset, AssetPath, HandleId},
+    log::info,
+    prelude::{
+        AssetEvent, Assets, Commands, DetectChangesMut, EventReader, Image, Mesh, Res, ResMut,
+    },
+    utils::HashSet,
+};
+
+use super::{
+    description::SceneDescription,
+    floor::Floors,
+    item_state::{KeptSceneItems, SceneItemStates},
+    prop::{materials::plastic::PlasticMaterial, Props},
+    wall::Walls,
+    LoadedScenes,
+};
+
+fn modified<T: Asset>(event: &AssetEvent<T>) -> Option<HandleId> {
+    match event {
+        AssetEvent::Modified { handle } => Some(handle.id()),
+        AssetEvent::Created { .. } | AssetEvent::Removed { .. } => None,
+    }
+}
+
+/// Every mesh & texture the scene description depends on
+fn referenced_assets(
+    description: &SceneDescription,
+    floors: &Floors,
+    walls: &Walls,
+    plastic_props: &Props<PlasticMaterial>,
+) -> HashSet<HandleId> {
+    let mut assets = HashSet::new();
+
+    for mesh in description
+        .shadow_casters
+        .iter()
+        .chain(description.nav_mesh.iter())
+    {
+        assets.insert(HandleId::from(AssetPath::from(mesh.mesh.as_str())));
+    }
+    for floor in description
+        .floors
+        .iter()
+        .filter_map(|item| floors.0.get(&item.key))
+    {
+        assets.insert(floor.mesh.id());
+        assets.extend(
+            [
+                &floor.material.base_color_texture,
+                &floor.material.metallic_texture,
+                &floor.material.normal_map_texture,
+                &floor.material.texture_map,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|texture| texture.id()),
+        );
+    }
+    for wall in description
+        .walls
+        .iter()
+        .filter_map(|item| walls.0.get(&item.key))
+    {
+        assets.insert(wall.mesh.id());
+        assets.extend(wall.material.normal_map_texture.iter().map(|t| t.id()));
+    }
+    for prop in description
+        .plastic_props
+        .iter()
+        .filter_map(|item| plastic_props.0.get(&item.key))
+    {
+        assets.insert(prop.mesh.id());
+        assets.extend(
+            [
+                &prop.material.noise_texture_1,
+                &prop.material.noise_texture_2,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|texture| texture.id()),
+        );
+    }
+
+    assets
+}
+
+/// Despawns every loaded scene whose description, or a floor, wall or prop it references, changes
+/// on disk. [`super::spawn_scenes`] then rebuilds them from the reloaded assets
+///
+/// The player isn't owned by any scene root so it keeps its position. Seen props, door states &
+/// moved rigid bodies are kept in [`KeptSceneItems`] & restored once respawned
+#[allow(clippy::too_many_arguments)]
+pub fn reload_changed_scenes(
+    mut commands: Commands,
+    mut loaded_scenes: ResMut<LoadedScenes>,
+    mut kept: ResMut<KeptSceneItems>,
+    item_query: SceneItemStates,
+    scene_descriptions: Res<Assets<SceneDescription>>,
+    floors: Res<Floors>,
+    walls: Res<Walls>,
+    plastic_props: Res<Props<PlasticMaterial>>,
+    mut description_events: EventReader<AssetEvent<SceneDescription>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+) {
+    let modified_descriptions: HashSet<HandleId> =
+        description_events.iter().filter_map(modified).collect();
+    let modified_assets: HashSet<HandleId> = mesh_events
+        .iter()
+        .filter_map(modified)
+        .chain(image_events.iter().filter_map(modified))
+        .collect();
+
+    if modified_descriptions.is_empty() && modified_assets.is_empty() {
+        return;
+    }
+
+    for (name, instance) in loaded_scenes.0.iter_mut() {
+        if !instance.spawned() {
+            continue;
+        }
+        let Some(description) = scene_descriptions.get(&instance.description) else {
+            continue;
+        };
+
+        let reload = modified_descriptions.contains(&instance.description.id())
+            || !referenced_assets(description, &floors, &walls, &plastic_props)
+                .is_disjoint(&modified_assets);
+
+        if reload {
+            info!("reloading scene {name} ({})", instance.scene);
+
+            //only the respawned items pick it up, as they're added
+            let prefix = format!("{name}/");
+            let kept = kept.bypass_change_detection();
+            for (id, transform, visibility, door, rigid_body) in &item_query {
+                if id.0.starts_with(&prefix) {
+                    kept.keep(id, visibility, door, rigid_body.map(|_| transform));
+                }
+            }
+
+            instance.despawn(&mut commands);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::{
+    prelude::{DetectChanges, Query, Ref, Res, Resource, Transform},
+    utils::{HashMap, HashSet},
+};
+
+use crate::player::interaction::Interactable;
+
+use super::{
+    prop::{
+        door::{Door, DoorState},
+        physics::RigidBody,
+        PropVisibility,
+    },
+    SceneItemId,
+};
+
+/// State of scene items kept while they're despawned, by [`SceneItemId`], & applied to them as
+/// they spawn again. Filled when a scene is hot reloaded or streamed out & when a save is loaded
+#[derive(Resource, Default, Debug)]
+pub struct KeptSceneItems {
+    pub seen_props: HashSet<String>,
+    pub doors: HashMap<String, DoorState>,
+    /// transforms of rigid bodies, not saved to disk
+    pub moved: HashMap<String, Transform>,
+}
+
+impl KeptSceneItems {
+    /// Keeps the state of a scene item about to be despawned, for when it's spawned again
+    pub fn keep(
+        &mut self,
+        id: &SceneItemId,
+        visibility: Option<&PropVisibility>,
+        door: Option<&Door>,
+        rigid_body: Option<&Transform>,
+    ) {
+        match visibility {
+            Some(PropVisibility::Seen) => {
+                self.seen_props.insert(id.0.clone());
+            }
+            _ => {
+                self.seen_props.remove(&id.0);
+            }
+        }
+        if let Some(door) = door {
+            self.doors.insert(id.0.clone(), door.state.clone());
+        }
+        if let Some(transform) = rigid_body {
+            self.moved.insert(id.0.clone(), *transform);
+        }
+    }
+}
+
+/// Runtime state of scene items, kept when they're despawned
+pub type SceneItemStates<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static SceneItemId,
+        &'static Transform,
+        Option<&'static PropVisibility>,
+        Option<&'static Door>,
+        Option<&'static RigidBody>,
+    ),
+>;
+
+/// Scene items the kept state is restored into, props & doors
+type SceneItems<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Ref<'static, SceneItemId>,
+        &'static mut Transform,
+        Option<&'static mut PropVisibility>,
+        Option<(&'static mut Door, &'static mut Interactable)>,
+        Option<&'static RigidBody>,
+    ),
+>;
+
+/// Applies the kept state to every scene item when it changes & to scene items spawned since
+pub fn restore_scene_items(kept: Res<KeptSceneItems>, mut item_query: SceneItems) {
+    for (id, mut transform, visibility, door, rigid_body) in &mut item_query {
+        if !kept.is_changed() && !id.is_added() {
+            continue;
+        }
+
+        if let Some(mut visibility) = visibility {
+            let saved = match kept.seen_props.contains(&id.0) {
+                true => PropVisibility::Seen,
+                false => PropVisibility::Hidden,
+            };
+            if *visibility != saved {
+                *visibility = saved;
+            }
+        }
+
+        if let (Some((mut door, mut interactable)), Some(state)) = (door, kept.doors.get(&id.0)) {
+            door.snap(state.clone(), &mut transform);
+            interactable.prompt = door.prompt().into();
+        }
+
+        if let (Some(_), Some(moved)) = (rigid_body, kept.moved.get(&id.0)) {
+            *transform = *moved;
+        }
+    }
+}
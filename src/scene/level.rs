@@ -0,0 +1,408 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    log::info,
+    prelude::{
+        AddAsset, App, AssetEvent, AssetServer, Assets, Commands, DetectChangesMut, Event,
+        EventReader, GlobalTransform, Handle, Plugin, Query, Res, ResMut, Resource, Vec3, With,
+    },
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashSet},
+};
+use serde::Deserialize;
+
+use crate::player::Controllable;
+
+use super::{
+    description::TransformDescription,
+    item_state::{KeptSceneItems, SceneItemStates},
+    LoadedScenes, SceneInstance,
+};
+
+/// A level made of rooms. Each room is a scene placed in the level & connected to other rooms
+/// through doors. Loaded from `levels/{name}.level.ron`
+#[derive(Deserialize, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "8a0b6a44-51f4-4d1e-a4a4-0c6e1f0f2d71"]
+pub struct LevelDescription {
+    /// Room loaded while the player isn't inside any room
+    pub start_room: String,
+    pub rooms: Vec<RoomDescription>,
+    #[serde(default)]
+    pub doors: Vec<DoorDescription>,
+    /// Number of doors away from the player's room that stay loaded
+    #[serde(default = "LevelDescription::default_stream_depth")]
+    pub stream_depth: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RoomDescription {
+    pub name: String,
+    /// scene spawned for the room
+    pub scene: String,
+    #[serde(default)]
+    pub transform: TransformDescription,
+    /// level space area covered by the room
+    pub bounds: RoomBounds,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RoomBounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl RoomBounds {
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DoorDescription {
+    pub rooms: (String, String),
+    #[serde(default)]
+    pub position: Vec3,
+}
+
+impl LevelDescription {
+    fn default_stream_depth() -> usize {
+        1
+    }
+
+    pub fn room(&self, name: &str) -> Option<&RoomDescription> {
+        self.rooms.iter().find(|room| room.name == name)
+    }
+
+    pub fn room_at(&self, point: Vec3) -> Option<&RoomDescription> {
+        self.rooms.iter().find(|room| room.bounds.contains(point))
+    }
+
+    /// Rooms sharing a door with `room`
+    pub fn connected_rooms<'a>(&'a self, room: &'a str) -> impl Iterator<Item = &'a str> {
+        self.doors
+            .iter()
+            .filter_map(
+                move |DoorDescription { rooms: (a, b), .. }| match (a == room, b == room) {
+                    (true, _) => Some(b.as_str()),
+                    (_, true) => Some(a.as_str()),
+                    _ => None,
+                },
+            )
+    }
+
+    /// Rooms at most `depth` doors away from `room` (including `room`)
+    pub fn rooms_near(&self, room: &str, depth: usize) -> HashSet<String> {
+        let mut rooms = HashSet::new();
+        let mut queue = VecDeque::from([(room.to_string(), 0)]);
+
+        while let Some((room, dist)) = queue.pop_front() {
+            if !rooms.insert(room.clone()) || dist == depth {
+                continue;
+            }
+
+            for next in self.connected_rooms(&room) {
+                queue.push_back((next.to_string(), dist + 1));
+            }
+        }
+
+        rooms
+    }
+}
+
+#[derive(Default)]
+pub struct LevelDescriptionLoader;
+
+impl AssetLoader for LevelDescriptionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let description: LevelDescription = ron::de::from_bytes(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(description));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+pub fn level_path(name: &str) -> String {
+    format!("levels/{name}.level.ron")
+}
+
+/// Requests the level with the given name to be streamed in, replacing any loaded scene or level
+#[derive(Event)]
+pub struct LoadLevelEvent(pub String);
+
+#[derive(Resource)]
+pub struct CurrentLevel {
+    pub name: String,
+    pub description: Handle<LevelDescription>,
+    /// Room the player was last seen in
+    pub current_room: Option<String>,
+}
+
+pub fn request_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loaded_scenes: ResMut<LoadedScenes>,
+    mut load_level_event: EventReader<LoadLevelEvent>,
+) {
+    let Some(LoadLevelEvent(name)) = load_level_event.iter().last() else {
+        return;
+    };
+
+    loaded_scenes.clear(&mut commands);
+    commands.insert_resource(CurrentLevel {
+        name: name.clone(),
+        description: asset_server.load(level_path(name)),
+        current_room: None,
+    });
+}
+
+/// Loads the rooms near the [`Controllable`] player & unloads every other room. The state of the
+/// scene items unloaded is kept in [`KeptSceneItems`] & restored when their room loads again
+#[allow(clippy::too_many_arguments)]
+pub fn stream_rooms(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current_level: Option<ResMut<CurrentLevel>>,
+    levels: Res<Assets<LevelDescription>>,
+    mut level_events: EventReader<AssetEvent<LevelDescription>>,
+    player_query: Query<&GlobalTransform, With<Controllable>>,
+    mut loaded_scenes: ResMut<LoadedScenes>,
+    mut kept: ResMut<KeptSceneItems>,
+    item_query: SceneItemStates,
+) {
+    let level_modified = level_events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => current_level
+            .as_ref()
+            .is_some_and(|level| level.description == *handle),
+        AssetEvent::Created { .. } | AssetEvent::Removed { .. } => false,
+    });
+
+    let Some(mut current_level) = current_level else {
+        return;
+    };
+    let Some(level) = levels.get(&current_level.description) else {
+        return;
+    };
+
+    if level_modified {
+        info!("reloading level {}", current_level.name);
+        loaded_scenes.clear(&mut commands);
+    }
+
+    let player_room = player_query
+        .iter()
+        .next()
+        .and_then(|transform| level.room_at(transform.translation()))
+        .map(|room| room.name.clone());
+
+    let room = match (player_room, &current_level.current_room) {
+        (Some(room), _) => room,
+        (None, Some(room)) => room.clone(),
+        (None, None) => level.start_room.clone(),
+    };
+
+    if current_level.current_room.as_ref() != Some(&room) {
+        info!("{}: entered room {room}", current_level.name);
+        current_level.current_room = Some(room.clone());
+    }
+
+    let nearby_rooms = level.rooms_near(&room, level.stream_depth);
+
+    //only the respawned items pick it up, as they're added
+    let kept = kept.bypass_change_detection();
+    loaded_scenes.0.retain(|name, instance| {
+        let keep = nearby_rooms.contains(name);
+        if !keep {
+            let prefix = format!("{name}/");
+            for (id, transform, visibility, door, rigid_body) in &item_query {
+                if id.0.starts_with(&prefix) {
+                    kept.keep(id, visibility, door, rigid_body.map(|_| transform));
+                }
+            }
+
+            instance.despawn(&mut commands);
+        }
+        keep
+    });
+
+    for name in nearby_rooms {
+        if loaded_scenes.0.contains_key(&name) {
+            continue;
+        }
+        let Some(room) = level.room(&name) else {
+            continue;
+        };
+
+        loaded_scenes.0.insert(
+            name,
+            SceneInstance::new(&room.scene, &asset_server, (&room.transform).into()),
+        );
+    }
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelDescription>()
+            .init_asset_loader::<LevelDescriptionLoader>()
+            .add_event::<LoadLevelEvent>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Transform;
+
+    use crate::scene::{description::SceneDescription, nav_mesh::NavMesh};
+
+    use super::*;
+
+    fn level(source: &str) -> LevelDescription {
+        ron::de::from_str(source).unwrap()
+    }
+
+    fn rooms(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Rooms `a` - `b` - `c` in a row, `d` unconnected
+    const CORRIDOR: &str = r#"(
+        start_room: "a",
+        rooms: [
+            (name: "a", scene: "s", bounds: (min: (0., 0., 0.), max: (10., 5., 10.))),
+            (name: "b", scene: "s", bounds: (min: (10., 0., 0.), max: (20., 5., 10.))),
+            (name: "c", scene: "s", bounds: (min: (20., 0., 0.), max: (30., 5., 10.))),
+            (name: "d", scene: "s", bounds: (min: (40., 0., 0.), max: (50., 5., 10.))),
+        ],
+        doors: [(rooms: ("a", "b")), (rooms: ("c", "b"))],
+    )"#;
+
+    #[test]
+    fn streams_rooms_by_door_depth() {
+        let level = level(CORRIDOR);
+
+        assert_eq!(level.rooms_near("a", 0), rooms(&["a"]));
+        assert_eq!(level.rooms_near("a", 1), rooms(&["a", "b"]));
+        assert_eq!(level.rooms_near("a", 2), rooms(&["a", "b", "c"]));
+        assert_eq!(level.rooms_near("b", 1), rooms(&["a", "b", "c"]));
+        assert_eq!(level.rooms_near("d", 5), rooms(&["d"]));
+    }
+
+    #[test]
+    fn finds_the_room_at_a_point() {
+        let level = level(CORRIDOR);
+
+        let room = |point| level.room_at(point).map(|room| room.name.as_str());
+        assert_eq!(room(Vec3::new(5., 1., 5.)), Some("a"));
+        assert_eq!(room(Vec3::new(25., 1., 5.)), Some("c"));
+        assert_eq!(room(Vec3::new(35., 1., 5.)), None);
+    }
+
+    /// Scenes of the dev level by name
+    fn dev_scene(name: &str) -> SceneDescription {
+        let source = match name {
+            "dev_playground" => {
+                include_str!("../../assets/scenes/dev_playground/dev_playground.scene.ron")
+            }
+            "dev_annex" => include_str!("../../assets/scenes/dev_annex/dev_annex.scene.ron"),
+            _ => panic!("{name} isn't a dev scene"),
+        };
+        ron::de::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn dev_level_streams_the_annexes() {
+        let level = level(include_str!("../../assets/levels/dev_playground.level.ron"));
+
+        assert_eq!(
+            level.rooms_near(&level.start_room, level.stream_depth),
+            rooms(&[
+                "dev_playground",
+                "dev_playground_annex",
+                "dev_playground_north_annex"
+            ])
+        );
+        //walking into an annex streams the other one out
+        assert_eq!(
+            level.rooms_near("dev_playground_annex", level.stream_depth),
+            rooms(&["dev_playground", "dev_playground_annex"])
+        );
+    }
+
+    #[test]
+    fn dev_level_rooms_are_walkable_through_their_doors() {
+        let level = level(include_str!("../../assets/levels/dev_playground.level.ron"));
+
+        let nav_mesh = NavMesh::from_triangles(level.rooms.iter().flat_map(|room| {
+            let transform = GlobalTransform::from(Transform::from(&room.transform));
+            dev_scene(&room.scene)
+                .nav_areas
+                .iter()
+                .flat_map(|area| NavMesh::mesh_triangles(&area.mesh(), &transform))
+                .collect::<Vec<_>>()
+        }));
+
+        let start = Vec3::new(3., 0., -3.);
+        for (room, goal) in [
+            ("dev_playground_annex", Vec3::new(25., 0., -2.)),
+            ("dev_playground_north_annex", Vec3::new(4., 0., -20.)),
+        ] {
+            assert_eq!(level.room_at(goal).unwrap().name, room);
+            assert!(nav_mesh.locate(goal).is_some(), "{room} has no nav mesh");
+
+            let path = nav_mesh.find_path(start, goal).unwrap();
+            assert!(path.last().unwrap().distance(goal) < 1e-4);
+
+            //through the door between the rooms
+            let door = level
+                .doors
+                .iter()
+                .find(|door| door.rooms.1 == room)
+                .unwrap()
+                .position;
+            let distance_to_door = |from: Vec3, to: Vec3| {
+                let t = (door - from).dot(to - from) / (to - from).length_squared();
+                door.distance(from.lerp(to, t.clamp(0., 1.)))
+            };
+            assert!(path
+                .windows(2)
+                .any(|leg| distance_to_door(leg[0], leg[1]) < 0.5));
+        }
+    }
+
+    #[test]
+    fn dev_level_doors_hang_in_their_doorways() {
+        let level = level(include_str!("../../assets/levels/dev_playground.level.ron"));
+        let scene = dev_scene("dev_playground");
+
+        for door in level
+            .doors
+            .iter()
+            .filter(|door| door.rooms.0 == "dev_playground")
+        {
+            assert!(
+                scene.doors.iter().any(|prop| {
+                    let transform = Transform::from(&prop.transform);
+                    let leaf = prop.door();
+                    let middle = transform.transform_point((leaf.hinge + leaf.latch) / 2.);
+
+                    middle.distance(door.position) < 0.01
+                }),
+                "no door prop between {:?}",
+                door.rooms
+            );
+        }
+    }
+}
@@ -1,444 +1,208 @@
-use std::f32::consts::PI;
-
-use bevy::audio::{PlaybackMode, Volume};
-use bevy::math::{Quat, Vec3};
 use bevy::prelude::{
-    App, AssetServer, Assets, Commands, MaterialMeshBundle, PlaybackSettings, Plugin, Res, ResMut,
-    SpatialSettings, Startup,
+    AddAsset, App, AssetServer, Assets, Commands, Component, DespawnRecursiveExt, Entity, Event,
+    EventReader, EventWriter, Handle, IntoSystemConfigs, Plugin, PreStartup, Res, ResMut, Resource,
+    Startup, Transform, Update,
 };
-use bevy::transform::components::Transform;
-
-use crate::player::target::PlayerTargetSet;
+use bevy::utils::HashMap;
 
-use self::floor::{FloorMaterial, FloorPlugin, Floors};
-use self::nav_mesh::NavMeshBundle;
-use self::prop::materials::plastic::PlasticMaterial;
-use self::prop::sound_source::{PropSoundBundle, SoundSource, SoundVolume};
-use self::prop::{PropPlugin, PropVisibility, PropVisibilityBlocker, PropVisibilityTarget, Props};
-use self::shadow_caster::ShadowCasterMaterial;
-use self::wall::{WallMaterial, WallPlugin, Walls};
+use self::catalog::{build_asset_catalog, AssetCatalog};
+use self::description::{scene_path, SceneDescription, SceneDescriptionLoader};
+use self::floor::FloorPlugin;
+use self::item_state::KeptSceneItems;
+use self::level::{CurrentLevel, LevelPlugin, LoadLevelEvent};
+use self::nav_mesh::NavMeshPlugin;
+use self::prop::PropPlugin;
+use self::spawner::SceneSpawner;
+use self::wall::WallPlugin;
 
+pub mod catalog;
+pub mod description;
 pub mod floor;
+pub mod hot_reload;
+pub mod item_state;
+pub mod level;
 pub mod nav_mesh;
 pub mod prop;
 pub mod shadow_caster;
+pub mod spawner;
 pub mod wall;
 
-fn create_scene(
-    mut commands: Commands,
-    floors: Res<Floors>,
-    walls: Res<Walls>,
-    plastic_props: Res<Props<PlasticMaterial>>,
-    asset_server: ResMut<AssetServer>,
-    mut floor_materials: ResMut<Assets<FloorMaterial>>,
-    mut wall_materials: ResMut<Assets<WallMaterial>>,
-    mut shadow_caster_material: ResMut<Assets<ShadowCasterMaterial>>,
-    mut plastic_material: ResMut<Assets<PlasticMaterial>>,
-) {
-    //shadow caster
-    {
-        commands.spawn((
-            MaterialMeshBundle {
-                mesh: asset_server.load(
-                    "scenes/dev_playground/room_shadow_caster/mesh/mesh.glb#Mesh0/Primitive0",
-                ),
-                material: shadow_caster_material.add(Default::default()),
-                ..Default::default()
-            },
-            PropVisibilityBlocker,
-        ));
+/// Requests the scene with the given name to be loaded & spawned on its own, replacing any
+/// loaded scene or level
+#[derive(Event)]
+pub struct LoadSceneEvent(pub String);
+
+/// A [`SceneDescription`] placed in the world
+pub struct SceneInstance {
+    pub scene: String,
+    pub description: Handle<SceneDescription>,
+    pub transform: Transform,
+    /// Root entity owning everything spawned for the scene
+    pub root: Option<Entity>,
+}
+
+impl SceneInstance {
+    pub fn new(scene: &str, asset_server: &AssetServer, transform: Transform) -> Self {
+        Self {
+            scene: scene.into(),
+            description: asset_server.load(scene_path(scene)),
+            transform,
+            root: None,
+        }
     }
-    //walls
-    {
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_2").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 0.,
-                        y: 0.,
-                        z: -10.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 3.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_4").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 14.,
-                        y: 0.,
-                        z: 0.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 1.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_1").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 0.,
-                        y: 0.,
-                        z: 0.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 0.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_1").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 14.,
-                        y: 0.,
-                        z: -10.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 2.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
 
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_3").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 0.,
-                        y: 0.,
-                        z: -10.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 3.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_3").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 14.,
-                        y: 0.,
-                        z: 0.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 1.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_3").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 0.,
-                        y: 0.,
-                        z: 0.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 0.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            wall::into_mesh_bundle(
-                walls.as_ref().0.get("dev_playground/wall_3").unwrap(),
-                &mut wall_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 14.,
-                        y: 0.,
-                        z: -10.,
-                    },
-                    rotation: Quat::from_rotation_y(PI / 2. * 2.),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
+    pub fn spawned(&self) -> bool {
+        self.root.is_some()
     }
-    //floors
-    {
-        commands.spawn((
-            floor::into_mesh_bundle(
-                floors
-                    .as_ref()
-                    .0
-                    .get("dev_playground/metal_grate_floor")
-                    .unwrap(),
-                &mut floor_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 2.,
-                        y: 0.,
-                        z: -2.,
-                    },
-                    rotation: Quat::default(),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            floor::into_mesh_bundle(
-                floors
-                    .as_ref()
-                    .0
-                    .get("dev_playground/metal_grate_floor")
-                    .unwrap(),
-                &mut floor_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 2.,
-                        y: 0.,
-                        z: -6.,
-                    },
-                    rotation: Quat::default(),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
-        commands.spawn((
-            floor::into_mesh_bundle(
-                floors
-                    .as_ref()
-                    .0
-                    .get("dev_playground/stainless_steel_floor")
-                    .unwrap(),
-                &mut floor_materials,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 0.,
-                        y: 0.,
-                        z: 0.,
-                    },
-                    rotation: Quat::default(),
-                    scale: Vec3 {
-                        x: 1.,
-                        y: 1.,
-                        z: 1.,
-                    },
-                }),
-            ),
-            PlayerTargetSet,
-        ));
+
+    /// Despawns every entity owned by the scene. The scene is spawned again once its description
+    /// is loaded
+    pub fn despawn(&mut self, commands: &mut Commands) {
+        if let Some(root) = self.root.take() {
+            commands.entity(root).despawn_recursive();
+        }
     }
-    //windows
-    {
-        let rain_window_loop = asset_server.load("rain/rain_window_loop.ogg");
-        commands.spawn((
-            //window mesh
-            PropSoundBundle {
-                sound_source: SoundSource::Point(Vec3 {
-                    x: 1.55556,
-                    y: 1.58101,
-                    z: 0.,
-                }),
-                source: rain_window_loop.clone(),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Loop,
-                    volume: Volume::new_relative(2.),
-                    ..Default::default()
-                },
-                spatial: SpatialSettings::new(Transform::default(), 1.0, Vec3::ZERO),
-            },
-            SoundVolume::new(0.5, 10.),
-        ));
-        commands.spawn((
-            //window mesh
-            PropSoundBundle {
-                sound_source: SoundSource::Point(Vec3 {
-                    x: 4.66667,
-                    y: 1.58101,
-                    z: 0.,
-                }),
-                source: rain_window_loop.clone(),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Loop,
-                    volume: Volume::new_relative(2.),
-                    ..Default::default()
-                },
-                spatial: SpatialSettings::new(Transform::default(), 1.0, Vec3::ZERO),
-            },
-            SoundVolume::new(0.5, 10.),
-        ));
-        commands.spawn((
-            //window mesh
-            PropSoundBundle {
-                sound_source: SoundSource::Point(Vec3 {
-                    x: 7.77778,
-                    y: 1.58101,
-                    z: 0.,
-                }),
-                source: rain_window_loop.clone(),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Loop,
-                    volume: Volume::new_relative(2.),
-                    ..Default::default()
-                },
-                spatial: SpatialSettings::new(Transform::default(), 1.0, Vec3::ZERO),
-            },
-            SoundVolume::new(0.5, 10.),
-        ));
-        commands.spawn((
-            //window mesh
-            PropSoundBundle {
-                sound_source: SoundSource::Point(Vec3 {
-                    x: 10.8889,
-                    y: 1.58101,
-                    z: 0.,
-                }),
-                source: rain_window_loop.clone(),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Loop,
-                    volume: Volume::new_relative(2.),
-                    ..Default::default()
-                },
-                spatial: SpatialSettings::new(Transform::default(), 1.0, Vec3::ZERO),
-            },
-            SoundVolume::new(0.5, 10.),
-        ));
+}
+
+/// Scene instances currently in the world, keyed by instance name (scene or room name)
+#[derive(Resource, Default)]
+pub struct LoadedScenes(pub HashMap<String, SceneInstance>);
+
+impl LoadedScenes {
+    pub fn clear(&mut self, commands: &mut Commands) {
+        for (_, mut instance) in self.0.drain() {
+            instance.despawn(commands);
+        }
     }
-    //nav mesh
-    {
-        commands.spawn((NavMeshBundle::new(
-            asset_server.load("scenes/dev_playground/nav_mesh/nav_mesh.glb#Mesh0/Primitive0"),
-            Transform::default(),
-        ),));
+}
+
+/// Marks the entity that owns every entity spawned from a [`SceneDescription`]
+#[derive(Component)]
+pub struct SceneRoot(pub String);
+
+/// Identifies an entity spawned from a scene item across runs: `{instance}/{list}/{index}`, eg:
+/// `dev_playground/plastic_props/0`
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SceneItemId(pub String);
+
+impl SceneItemId {
+    pub fn new(instance: &str, list: &str, index: usize) -> Self {
+        Self(format!("{instance}/{list}/{index}"))
     }
-    //props
-    {
-        commands.spawn((
-            prop::into_mesh_bundle(
-                plastic_props.0.get("plastic_bin_1").unwrap(),
-                &mut plastic_material,
-                Some(Transform {
-                    translation: Vec3 {
-                        x: 6.,
-                        y: 0.,
-                        z: -5.,
-                    },
-                    scale: Vec3::new(3., 3., 3.),
-                    rotation: Quat::from_rotation_y(0.5),
-                }),
-            ),
-            plastic_props.0.get("plastic_bin_1").unwrap().clone(),
-            PropVisibility::Hidden,
-            //prop::Forgettable,
-            PropVisibilityTarget::from(vec![
-                Vec3::new(0.051597, 0.046506, 0.031542),
-                Vec3::new(0.051597, 0.046506, 0.468458),
-                Vec3::new(1.0584, 0.046506, 0.031542),
-                Vec3::new(1.0584, 0.046506, 0.468458),
-            ]),
-            PlayerTargetSet,
-        ));
+}
+
+fn request_scene(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loaded_scenes: ResMut<LoadedScenes>,
+    mut load_scene_event: EventReader<LoadSceneEvent>,
+) {
+    let Some(LoadSceneEvent(name)) = load_scene_event.iter().last() else {
+        return;
+    };
+
+    commands.remove_resource::<CurrentLevel>();
+    loaded_scenes.clear(&mut commands);
+    loaded_scenes.0.insert(
+        name.clone(),
+        SceneInstance::new(name, &asset_server, Transform::default()),
+    );
+}
+
+fn spawn_scenes(
+    mut spawner: SceneSpawner,
+    scene_descriptions: Res<Assets<SceneDescription>>,
+    mut loaded_scenes: ResMut<LoadedScenes>,
+) {
+    for (name, instance) in loaded_scenes.0.iter_mut() {
+        if instance.spawned() {
+            continue;
+        }
+        let Some(description) = scene_descriptions.get(&instance.description) else {
+            continue;
+        };
+
+        instance.root = Some(spawner.spawn(name, description, instance.transform));
+    }
+}
+
+/// What the world starts with
+pub enum WorldStart {
+    Scene(String),
+    Level(String),
+}
+
+pub struct WorldPlugin {
+    pub start: WorldStart,
+}
+
+impl WorldPlugin {
+    pub fn new(scene: &str) -> Self {
+        Self {
+            start: WorldStart::Scene(scene.into()),
+        }
+    }
+    pub fn level(level: &str) -> Self {
+        Self {
+            start: WorldStart::Level(level.into()),
+        }
     }
-    // commands.spawn((
-    //     a
-    // ))
-    // commands.spawn((
-    //     window::into_mesh_bundle(
-    //         windows
-    //             .as_ref()
-    //             .0
-    //             .get("dev_playground/window_1")
-    //             .unwrap(),
-    //         &mut window_materials,
-    //         Some(Transform {
-    //             translation: Vec3 {
-    //                 x: 5.,
-    //                 y: 1.,
-    //                 z: -5.,
-    //             },
-    //             rotation: Quat::default(),
-    //             scale: Vec3 {
-    //                 x: 1.,
-    //                 y: 1.,
-    //                 z: 1.,
-    //             },
-    //         }),
-    //     ),
-    //     PlayerTargetSet,
-    // ));
 }
 
-pub struct WorldPlugin;
+impl Default for WorldPlugin {
+    fn default() -> Self {
+        Self::level("dev_playground")
+    }
+}
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((PropPlugin, FloorPlugin, WallPlugin))
-            .add_systems(Startup, (create_scene,)); //PostStartup (Load scene)
-                                                    //.add_systems(
-                                                    //     Update,
-                                                    //     (
-                                                    //         move_to,
-                                                    //         follow
-                                                    //     )
-                                                    // );
+        app.add_plugins((
+            PropPlugin,
+            FloorPlugin,
+            WallPlugin,
+            LevelPlugin,
+            NavMeshPlugin,
+        ))
+        .init_resource::<AssetCatalog>()
+        .init_resource::<LoadedScenes>()
+        .init_resource::<KeptSceneItems>()
+        .add_systems(PreStartup, build_asset_catalog)
+        .add_asset::<SceneDescription>()
+        .init_asset_loader::<SceneDescriptionLoader>()
+        .add_event::<LoadSceneEvent>()
+        .add_systems(
+            Update,
+            (
+                request_scene,
+                level::request_level,
+                level::stream_rooms,
+                hot_reload::reload_changed_scenes,
+                spawn_scenes,
+                item_state::restore_scene_items,
+            )
+                .chain(),
+        );
+
+        match &self.start {
+            WorldStart::Scene(scene) => {
+                let scene = scene.clone();
+                app.add_systems(
+                    Startup,
+                    move |mut load_scene_event: EventWriter<LoadSceneEvent>| {
+                        load_scene_event.send(LoadSceneEvent(scene.clone()));
+                    },
+                );
+            }
+            WorldStart::Level(level) => {
+                let level = level.clone();
+                app.add_systems(
+                    Startup,
+                    move |mut load_level_event: EventWriter<LoadLevelEvent>| {
+                        load_level_event.send(LoadLevelEvent(level.clone()));
+                    },
+                );
+            }
+        }
     }
 }
@@ -1,19 +1,27 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
 use bevy::{
     asset::Handle,
     ecs::{bundle::Bundle, component::Component},
+    prelude::{
+        App, AssetEvent, Assets, DetectChanges, Entity, EventReader, IntoSystemConfigs, Local,
+        Plugin, Query, Ref, Res, ResMut, Resource, Update, Vec3, With,
+    },
     render::{
-        mesh::Mesh,
+        mesh::{Indices, Mesh, VertexAttributeValues},
         view::{ComputedVisibility, Visibility},
     },
     transform::components::{GlobalTransform, Transform},
+    utils::HashMap,
 };
 
+/// Marks a mesh whose triangles make up (part of) the walkable [`NavMesh`]
 #[derive(Component)]
-pub struct NavMesh;
+pub struct NavMeshSource;
 
 #[derive(Bundle)]
 pub struct NavMeshBundle {
-    nav_mesh: NavMesh,
+    nav_mesh: NavMeshSource,
     mesh: Handle<Mesh>,
     transform: Transform,
     global_transform: GlobalTransform,
@@ -24,7 +32,7 @@ pub struct NavMeshBundle {
 impl NavMeshBundle {
     pub fn new(mesh: Handle<Mesh>, transform: Transform) -> Self {
         return NavMeshBundle {
-            nav_mesh: NavMesh,
+            nav_mesh: NavMeshSource,
             mesh,
             transform,
             global_transform: GlobalTransform::default(),
@@ -33,3 +41,852 @@ impl NavMeshBundle {
         };
     }
 }
+
+/// Segment in world space the [`NavMesh`] can't be walked across, eg: a closed door
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct NavMeshBarrier(pub Vec3, pub Vec3);
+
+/// Vertices closer than this are treated as the same vertex
+const WELD_DISTANCE: f32 = 0.001;
+/// Distance kept from a barrier when sliding into it, so the next move starts on its near side
+const BARRIER_GAP: f32 = 0.01;
+
+/// Walkable triangles in world space & the adjacency between them
+///
+/// Triangles are located in the XZ plane (top down); heights are interpolated from the triangle
+#[derive(Resource, Default, Debug, Clone)]
+pub struct NavMesh {
+    vertices: Vec<Vec3>,
+    triangles: Vec<[usize; 3]>,
+    /// triangle sharing edge `i` (vertex `i` -> vertex `i + 1`) of each triangle
+    links: Vec<[Option<usize>; 3]>,
+    /// `links` minus the ones crossing a barrier
+    neighbours: Vec<[Option<usize>; 3]>,
+    /// segments cutting the triangles on either side apart, see [`NavMeshBarrier`]
+    barriers: Vec<(Vec3, Vec3)>,
+}
+
+impl NavMesh {
+    /// Builds a nav mesh from a triangle soup, welding shared vertices
+    pub fn from_triangles(triangles: impl IntoIterator<Item = [Vec3; 3]>) -> Self {
+        let mut nav_mesh = NavMesh::default();
+        let mut welded: HashMap<[i32; 3], usize> = HashMap::new();
+
+        for triangle in triangles {
+            let indices = triangle.map(|vertex| {
+                let key = (vertex / WELD_DISTANCE).round().as_ivec3().to_array();
+
+                *welded.entry(key).or_insert_with(|| {
+                    nav_mesh.vertices.push(vertex);
+                    nav_mesh.vertices.len() - 1
+                })
+            });
+
+            // degenerate triangles can't be walked on
+            if indices[0] == indices[1] || indices[1] == indices[2] || indices[0] == indices[2] {
+                continue;
+            }
+
+            nav_mesh.triangles.push(indices);
+        }
+
+        nav_mesh.build_neighbours();
+
+        nav_mesh
+    }
+
+    /// Reads the triangles of `mesh` transformed into world space
+    pub fn mesh_triangles(mesh: &Mesh, transform: &GlobalTransform) -> Vec<[Vec3; 3]> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return Vec::new();
+        };
+
+        let positions: Vec<Vec3> = positions
+            .iter()
+            .map(|pos| transform.transform_point(Vec3::from(*pos)))
+            .collect();
+
+        let indices: Vec<usize> = match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+            Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+            None => (0..positions.len()).collect(),
+        };
+
+        indices
+            .chunks_exact(3)
+            .map(|tri| [positions[tri[0]], positions[tri[1]], positions[tri[2]]])
+            .collect()
+    }
+
+    fn build_neighbours(&mut self) {
+        let mut edges: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+
+        self.links = vec![[None; 3]; self.triangles.len()];
+
+        for (triangle, indices) in self.triangles.iter().enumerate() {
+            for edge in 0..3 {
+                let (a, b) = (indices[edge], indices[(edge + 1) % 3]);
+                let key = (a.min(b), a.max(b));
+
+                match edges.get(&key) {
+                    Some(&(other, other_edge)) => {
+                        self.links[triangle][edge] = Some(other);
+                        self.links[other][other_edge] = Some(triangle);
+                    }
+                    None => {
+                        edges.insert(key, (triangle, edge));
+                    }
+                }
+            }
+        }
+
+        self.neighbours = self.links.clone();
+    }
+
+    pub fn barriers(&self) -> &[(Vec3, Vec3)] {
+        &self.barriers
+    }
+
+    /// Disconnects neighbouring triangles whose link, from one centroid to the other through the
+    /// shared edge, crosses one of the `barriers` in the XZ plane. Replaces the previous barriers
+    pub fn set_barriers(&mut self, barriers: Vec<(Vec3, Vec3)>) {
+        self.barriers = barriers;
+
+        for triangle in 0..self.triangles.len() {
+            let vertices = self.triangle(triangle);
+
+            for edge in 0..3 {
+                self.neighbours[triangle][edge] = self.links[triangle][edge].filter(|other| {
+                    let middle = (vertices[edge] + vertices[(edge + 1) % 3]) / 2.;
+                    let (from, to) = (self.centroid(triangle), self.centroid(*other));
+
+                    !self.barriers.iter().any(|(a, b)| {
+                        segments_cross_xz(from, middle, *a, *b)
+                            || segments_cross_xz(middle, to, *a, *b)
+                    })
+                });
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn triangle(&self, triangle: usize) -> [Vec3; 3] {
+        self.triangles[triangle].map(|index| self.vertices[index])
+    }
+
+    pub fn centroid(&self, triangle: usize) -> Vec3 {
+        let [a, b, c] = self.triangle(triangle);
+
+        (a + b + c) / 3.
+    }
+
+    pub fn neighbours(&self, triangle: usize) -> impl Iterator<Item = usize> + '_ {
+        self.neighbours[triangle].iter().flatten().copied()
+    }
+
+    /// Edges that aren't shared with another triangle, ie: the edge of the walkable area
+    pub fn boundary_edges(&self, triangle: usize) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+        let vertices = self.triangle(triangle);
+
+        (0..3)
+            .filter(move |edge| self.neighbours[triangle][*edge].is_none())
+            .map(move |edge| (vertices[edge], vertices[(edge + 1) % 3]))
+    }
+
+    /// Edge shared by two neighbouring triangles
+    pub fn shared_edge(&self, from: usize, to: usize) -> Option<(Vec3, Vec3)> {
+        let vertices = self.triangle(from);
+
+        (0..3)
+            .find(|edge| self.neighbours[from][*edge] == Some(to))
+            .map(|edge| (vertices[edge], vertices[(edge + 1) % 3]))
+    }
+
+    /// Triangle containing `point` when viewed from above. When triangles overlap the one closest
+    /// in height is picked
+    pub fn locate(&self, point: Vec3) -> Option<usize> {
+        (0..self.triangles.len())
+            .filter_map(|triangle| {
+                let height = self.height_at(triangle, point)?;
+
+                Some((triangle, (height - point.y).abs()))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(triangle, _)| triangle)
+    }
+
+    /// Height of `triangle` below/above `point`; None if `point` isn't over the triangle
+    pub fn height_at(&self, triangle: usize, point: Vec3) -> Option<f32> {
+        let [a, b, c] = self.triangle(triangle);
+
+        let (u, v, w) = barycentric_xz(point, a, b, c)?;
+
+        const EPSILON: f32 = -1e-5;
+        if u < EPSILON || v < EPSILON || w < EPSILON {
+            return None;
+        }
+
+        Some(u * a.y + v * b.y + w * c.y)
+    }
+
+    /// `point` moved onto the walkable surface directly below/above it
+    pub fn project_down(&self, point: Vec3) -> Option<(usize, Vec3)> {
+        let triangle = self.locate(point)?;
+
+        Some((
+            triangle,
+            Vec3::new(point.x, self.height_at(triangle, point)?, point.z),
+        ))
+    }
+
+    /// `point` moved onto the walkable surface, straight down/up if possible & to the closest point
+    /// otherwise
+    pub fn snap(&self, point: Vec3) -> Option<(usize, Vec3)> {
+        self.project_down(point)
+            .or_else(|| self.closest_point(point))
+    }
+
+    /// Height of the plane of `triangle` at `point`, even if `point` isn't over the triangle
+    fn plane_height(&self, triangle: usize, point: Vec3) -> f32 {
+        let [a, b, c] = self.triangle(triangle);
+
+        match barycentric_xz(point, a, b, c) {
+            Some((u, v, w)) => u * a.y + v * b.y + w * c.y,
+            None => self.centroid(triangle).y,
+        }
+    }
+
+    /// Where the segment `from -> to` leaves `triangle` in the XZ plane: (edge, fraction of the
+    /// segment). None if `to` is inside the triangle
+    fn exit_edge(&self, triangle: usize, from: Vec3, to: Vec3) -> Option<(usize, f32)> {
+        const EPSILON: f32 = 1e-6;
+
+        let vertices = self.triangle(triangle);
+        let winding = triarea2(vertices[0], vertices[1], vertices[2]).signum();
+
+        (0..3)
+            .filter_map(|edge| {
+                let (a, b) = (vertices[edge], vertices[(edge + 1) % 3]);
+
+                //positive is inside the triangle
+                let from_side = triarea2(a, b, from) * winding;
+                let to_side = triarea2(a, b, to) * winding;
+
+                //moving along an edge doesn't leave through it
+                (to_side < -EPSILON && to_side < from_side - EPSILON)
+                    .then(|| (edge, (from_side.max(0.) / (from_side.max(0.) - to_side))))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// First barrier the segment `from -> to` runs into in the XZ plane: (fraction of the segment,
+    /// direction of the barrier)
+    fn barrier_hit(&self, from: Vec3, to: Vec3) -> Option<(f32, Vec3)> {
+        self.barriers
+            .iter()
+            .filter_map(|(a, b)| Some((segment_crossing_xz(from, to, *a, *b)?, *b - *a)))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+    }
+
+    /// Moves `from` (on `triangle`) by `delta` in the XZ plane without leaving the nav mesh,
+    /// sliding along boundary edges & barriers & following the height of the triangles walked
+    /// over
+    pub fn slide(&self, triangle: usize, from: Vec3, delta: Vec3) -> (usize, Vec3) {
+        const MAX_STEPS: usize = 32;
+
+        let (mut triangle, mut position) = (triangle, from);
+        let mut remaining = Vec3::new(delta.x, 0., delta.z);
+
+        for _ in 0..MAX_STEPS {
+            if remaining.length_squared() < f32::EPSILON {
+                break;
+            }
+
+            let target = position + remaining;
+            let exit = self.exit_edge(triangle, position, target);
+
+            //barriers can cut through the middle of a triangle, stop short of the first one
+            if let Some((t, barrier)) = self
+                .barrier_hit(position, target)
+                .filter(|(t, _)| exit.is_none_or(|(_, exit)| *t <= exit))
+            {
+                let length = remaining.length();
+                position += remaining * ((t * length - BARRIER_GAP).max(0.) / length);
+
+                let along = Vec3::new(barrier.x, 0., barrier.z).normalize_or_zero();
+                remaining = along * along.dot(remaining * (1. - t));
+                continue;
+            }
+
+            let Some((edge, t)) = exit else {
+                position = target;
+                break;
+            };
+
+            position += remaining * t;
+            remaining *= 1. - t;
+
+            match self.neighbours[triangle][edge] {
+                Some(next) => triangle = next,
+                None => {
+                    //keep the part of the movement running along the edge
+                    let vertices = self.triangle(triangle);
+                    let edge = vertices[(edge + 1) % 3] - vertices[edge];
+                    let along = Vec3::new(edge.x, 0., edge.z).normalize_or_zero();
+
+                    remaining = along * along.dot(remaining);
+                }
+            }
+        }
+
+        position.y = self.plane_height(triangle, position);
+
+        (triangle, position)
+    }
+
+    /// Closest point on the nav mesh to `point`
+    pub fn closest_point(&self, point: Vec3) -> Option<(usize, Vec3)> {
+        (0..self.triangles.len())
+            .map(|triangle| {
+                let [a, b, c] = self.triangle(triangle);
+
+                (triangle, closest_point_on_triangle(point, a, b, c))
+            })
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(point)
+                    .total_cmp(&b.distance_squared(point))
+            })
+    }
+
+    /// First intersection of the ray with the nav mesh
+    pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<(usize, Vec3)> {
+        (0..self.triangles.len())
+            .filter_map(|triangle| {
+                let [a, b, c] = self.triangle(triangle);
+
+                ray_triangle_intersection(origin, direction, a, b, c).map(|t| (triangle, t))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(triangle, t)| (triangle, origin + direction * t))
+    }
+
+    /// A* over the triangle adjacency graph
+    pub fn triangle_path(&self, start: usize, goal: usize, goal_pos: Vec3) -> Option<Vec<usize>> {
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<usize, usize> = HashMap::new();
+        let mut cost: HashMap<usize, f32> = HashMap::new();
+
+        cost.insert(start, 0.);
+        open.push(Node {
+            triangle: start,
+            estimate: self.centroid(start).distance(goal_pos),
+        });
+
+        while let Some(Node { triangle, .. }) = open.pop() {
+            if triangle == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while let Some(previous) = came_from.get(&current) {
+                    path.push(*previous);
+                    current = *previous;
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            let current_cost = cost[&triangle];
+            for neighbour in self.neighbours(triangle) {
+                let new_cost =
+                    current_cost + self.centroid(triangle).distance(self.centroid(neighbour));
+
+                if cost
+                    .get(&neighbour)
+                    .is_some_and(|old_cost| *old_cost <= new_cost)
+                {
+                    continue;
+                }
+
+                cost.insert(neighbour, new_cost);
+                came_from.insert(neighbour, triangle);
+                open.push(Node {
+                    triangle: neighbour,
+                    estimate: new_cost + self.centroid(neighbour).distance(goal_pos),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Shortest path between two points on the nav mesh. Points off the nav mesh are projected
+    /// onto it first
+    pub fn find_path(&self, start: Vec3, goal: Vec3) -> Option<Vec<Vec3>> {
+        let (start_triangle, start) = self.snap(start)?;
+        let (goal_triangle, goal) = self.snap(goal)?;
+
+        let triangles = self.triangle_path(start_triangle, goal_triangle, goal)?;
+
+        Some(self.smooth_path(start, goal, &triangles))
+    }
+
+    /// Funnel algorithm (string pulling) through the portals of a triangle path
+    pub fn smooth_path(&self, start: Vec3, goal: Vec3, triangles: &[usize]) -> Vec<Vec3> {
+        let mut portals = vec![(start, start)];
+        for pair in triangles.windows(2) {
+            let Some((a, b)) = self.shared_edge(pair[0], pair[1]) else {
+                continue;
+            };
+
+            // left & right as seen while walking out of the triangle
+            let centroid = self.centroid(pair[0]);
+            portals.push(match triarea2(centroid, a, b) > 0. {
+                true => (a, b),
+                false => (b, a),
+            });
+        }
+        portals.push((goal, goal));
+
+        funnel(&portals)
+    }
+}
+
+#[derive(PartialEq)]
+struct Node {
+    triangle: usize,
+    estimate: f32,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed so the binary heap pops the lowest estimate first
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Twice the signed area of the triangle in the XZ plane
+fn triarea2(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    (c.x - a.x) * (b.z - a.z) - (b.x - a.x) * (c.z - a.z)
+}
+
+/// Distance between two points ignoring height
+pub fn flat_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec3::new(a.x - b.x, 0., a.z - b.z).length()
+}
+
+/// Whether segments `a0 -> a1` & `b0 -> b1` intersect in the XZ plane
+pub fn segments_cross_xz(a0: Vec3, a1: Vec3, b0: Vec3, b1: Vec3) -> bool {
+    let straddles =
+        |p0: Vec3, p1: Vec3, q0: Vec3, q1: Vec3| triarea2(p0, p1, q0) * triarea2(p0, p1, q1) <= 0.;
+
+    straddles(a0, a1, b0, b1) && straddles(b0, b1, a0, a1)
+}
+
+/// Fraction of segment `a0 -> a1` where it crosses segment `b0 -> b1` in the XZ plane, None if
+/// they don't cross or are parallel
+fn segment_crossing_xz(a0: Vec3, a1: Vec3, b0: Vec3, b1: Vec3) -> Option<f32> {
+    let cross = |u: Vec3, v: Vec3| u.x * v.z - u.z * v.x;
+    let (a, b, offset) = (a1 - a0, b1 - b0, b0 - a0);
+
+    let denom = cross(a, b);
+    if denom.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let (t, u) = (cross(offset, b) / denom, cross(offset, a) / denom);
+    ((0. ..=1.).contains(&t) && (0. ..=1.).contains(&u)).then_some(t)
+}
+
+fn same_xz(a: Vec3, b: Vec3) -> bool {
+    (a.x - b.x).abs() < WELD_DISTANCE && (a.z - b.z).abs() < WELD_DISTANCE
+}
+
+/// Simple stupid funnel algorithm. `portals` are (left, right) pairs, starting with the start
+/// point & ending with the goal
+///
+/// A side of the funnel sitting on the apex doesn't constrain the other side yet; portals often
+/// share the apex vertex when the path turns around it
+fn funnel(portals: &[(Vec3, Vec3)]) -> Vec<Vec3> {
+    let (start, _) = portals[0];
+    let (goal, _) = portals[portals.len() - 1];
+
+    let mut path = vec![start];
+
+    let (mut apex, mut left, mut right) = (start, start, start);
+    let (mut left_index, mut right_index) = (0, 0);
+
+    let mut i = 1;
+    while i < portals.len() {
+        let (new_left, new_right) = portals[i];
+
+        // tighten right side of the funnel
+        if triarea2(apex, right, new_right) <= 0. {
+            if same_xz(apex, right) || same_xz(apex, left) || triarea2(apex, left, new_right) > 0. {
+                right = new_right;
+                right_index = i;
+            } else {
+                // right crossed over left; left becomes a corner of the path
+                path.push(left);
+                apex = left;
+                (right, right_index) = (apex, left_index);
+                i = left_index + 1;
+                continue;
+            }
+        }
+
+        // tighten left side of the funnel
+        if triarea2(apex, left, new_left) >= 0. {
+            if same_xz(apex, left) || same_xz(apex, right) || triarea2(apex, right, new_left) < 0. {
+                left = new_left;
+                left_index = i;
+            } else {
+                // left crossed over right; right becomes a corner of the path
+                path.push(right);
+                apex = right;
+                (left, left_index) = (apex, right_index);
+                i = right_index + 1;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    if !path.last().is_some_and(|last| same_xz(*last, goal)) {
+        path.push(goal);
+    }
+
+    path
+}
+
+/// Barycentric coordinates of `p` in triangle `abc` projected onto the XZ plane
+fn barycentric_xz(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<(f32, f32, f32)> {
+    let denominator = (b.z - c.z) * (a.x - c.x) + (c.x - b.x) * (a.z - c.z);
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let u = ((b.z - c.z) * (p.x - c.x) + (c.x - b.x) * (p.z - c.z)) / denominator;
+    let v = ((c.z - a.z) * (p.x - c.x) + (a.x - c.x) * (p.z - c.z)) / denominator;
+
+    Some((u, v, 1. - u - v))
+}
+
+/// Closest point to `p` on triangle `abc` (Real-Time Collision Detection, 5.1.5)
+fn closest_point_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0. && d2 <= 0. {
+        return a;
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0. && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0. && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && (d4 - d3) >= 0. && (d5 - d6) >= 0. {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = 1. / (va + vb + vc);
+    a + ab * (vb * denominator) + ac * (vc * denominator)
+}
+
+/// Möller–Trumbore; returns the distance along the ray
+fn ray_triangle_intersection(
+    origin: Vec3,
+    direction: Vec3,
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+) -> Option<f32> {
+    let ab = b - a;
+    let ac = c - a;
+
+    let p = direction.cross(ac);
+    let determinant = ab.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inverse = 1. / determinant;
+    let t_vec = origin - a;
+
+    let u = t_vec.dot(p) * inverse;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let q = t_vec.cross(ab);
+    let v = direction.dot(q) * inverse;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = ac.dot(q) * inverse;
+    (t >= 0.).then_some(t)
+}
+
+type NavMeshSources<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Handle<Mesh>, Ref<'static, GlobalTransform>),
+    With<NavMeshSource>,
+>;
+
+/// Rebuilds the [`NavMesh`] resource whenever a [`NavMeshSource`] is added, removed, moved or its
+/// mesh (re)loads
+pub fn build_nav_mesh(
+    mut nav_mesh: ResMut<NavMesh>,
+    meshes: Res<Assets<Mesh>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    source_query: NavMeshSources,
+    mut built_from: Local<Vec<Entity>>,
+) {
+    let modified_meshes: Vec<Handle<Mesh>> = mesh_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle.clone_weak()),
+            AssetEvent::Created { .. } | AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+
+    let loaded_sources: Vec<Entity> = source_query
+        .iter()
+        .filter(|(_, mesh, _)| meshes.contains(*mesh))
+        .map(|(entity, _, _)| entity)
+        .collect();
+
+    let changed = *built_from != loaded_sources
+        || source_query
+            .iter()
+            .any(|(_, mesh, transform)| transform.is_changed() || modified_meshes.contains(mesh));
+
+    if !changed {
+        return;
+    }
+
+    *nav_mesh = NavMesh::from_triangles(source_query.iter().flat_map(|(_, mesh, transform)| {
+        meshes
+            .get(mesh)
+            .map(|mesh| NavMesh::mesh_triangles(mesh, &transform))
+            .unwrap_or_default()
+    }));
+    *built_from = loaded_sources;
+}
+
+/// Keeps the [`NavMesh`] cut along every [`NavMeshBarrier`]
+pub fn cut_nav_mesh(mut nav_mesh: ResMut<NavMesh>, barrier_query: Query<&NavMeshBarrier>) {
+    let barriers: Vec<(Vec3, Vec3)> = barrier_query
+        .iter()
+        .map(|NavMeshBarrier(a, b)| (*a, *b))
+        .collect();
+
+    //rebuilt nav meshes start without barriers
+    if nav_mesh.barriers() != barriers {
+        nav_mesh.set_barriers(barriers);
+    }
+}
+
+pub struct NavMeshPlugin;
+
+impl Plugin for NavMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavMesh>()
+            .add_systems(Update, (build_nav_mesh, cut_nav_mesh).chain());
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Two triangles covering the unit square at (`x`, `z`), at height `y` of the point
+    fn quad(x: f32, z: f32, height: impl Fn(f32) -> f32) -> [[Vec3; 3]; 2] {
+        let corner = |x: f32, z: f32| Vec3::new(x, height(x), z);
+        let (a, b, c, d) = (
+            corner(x, z),
+            corner(x + 1., z),
+            corner(x + 1., z + 1.),
+            corner(x, z + 1.),
+        );
+
+        [[a, b, c], [a, c, d]]
+    }
+
+    /// Flat nav mesh made of the unit squares at the given (x, z) cells
+    pub fn grid(cells: &[(i32, i32)]) -> NavMesh {
+        NavMesh::from_triangles(
+            cells
+                .iter()
+                .flat_map(|(x, z)| quad(*x as f32, *z as f32, |_| 0.)),
+        )
+    }
+
+    /// `length` cells along X, one cell wide along Z
+    pub fn strip(length: i32) -> NavMesh {
+        grid(&(0..length).map(|x| (x, 0)).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn welds_shared_vertices() {
+        let jitter = Vec3::splat(WELD_DISTANCE * 0.1);
+        let [first, second] = quad(0., 0., |_| 0.);
+        let nav_mesh = NavMesh::from_triangles([first, second.map(|vertex| vertex + jitter)]);
+
+        assert_eq!(nav_mesh.vertices.len(), 4);
+        assert_eq!(nav_mesh.neighbours(0).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(nav_mesh.neighbours(1).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(nav_mesh.boundary_edges(0).count(), 2);
+    }
+
+    #[test]
+    fn drops_degenerate_triangles() {
+        let nav_mesh = NavMesh::from_triangles([[Vec3::ZERO, Vec3::ZERO, Vec3::X]]);
+
+        assert!(nav_mesh.is_empty());
+    }
+
+    #[test]
+    fn a_star_crosses_neighbouring_triangles() {
+        let nav_mesh = strip(4);
+        let (start, goal) = (Vec3::new(0.2, 0., 0.5), Vec3::new(3.8, 0., 0.5));
+        let (start_triangle, goal_triangle) = (
+            nav_mesh.locate(start).unwrap(),
+            nav_mesh.locate(goal).unwrap(),
+        );
+
+        let path = nav_mesh
+            .triangle_path(start_triangle, goal_triangle, goal)
+            .unwrap();
+
+        assert_eq!(path.first(), Some(&start_triangle));
+        assert_eq!(path.last(), Some(&goal_triangle));
+        assert!(path.len() >= 5);
+        for pair in path.windows(2) {
+            assert!(nav_mesh.neighbours(pair[0]).any(|next| next == pair[1]));
+        }
+    }
+
+    #[test]
+    fn funnel_drops_redundant_corners() {
+        //straight corridor, no corners
+        let path = strip(4)
+            .find_path(Vec3::new(0.2, 0., 0.5), Vec3::new(3.8, 0., 0.5))
+            .unwrap();
+        assert_eq!(path, vec![Vec3::new(0.2, 0., 0.5), Vec3::new(3.8, 0., 0.5)]);
+
+        //L shaped corridor, a single corner on the inside of the bend
+        let nav_mesh = grid(&[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        let path = nav_mesh
+            .find_path(Vec3::new(0.5, 0., 0.5), Vec3::new(2.5, 0., 2.5))
+            .unwrap();
+        assert_eq!(
+            path,
+            vec![
+                Vec3::new(0.5, 0., 0.5),
+                Vec3::new(2., 0., 1.),
+                Vec3::new(2.5, 0., 2.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn no_path_to_unreachable_goals() {
+        //two islands
+        let nav_mesh = grid(&[(0, 0), (1, 0), (5, 0), (6, 0)]);
+        assert!(nav_mesh
+            .find_path(Vec3::new(0.5, 0., 0.5), Vec3::new(6.5, 0., 0.5))
+            .is_none());
+
+        //off mesh goals are pulled onto the closest walkable point, never walked to
+        let nav_mesh = strip(4);
+        assert!(nav_mesh.locate(Vec3::new(10., 0., 0.5)).is_none());
+        let path = nav_mesh
+            .find_path(Vec3::new(0.5, 0., 0.5), Vec3::new(10., 0., 0.5))
+            .unwrap();
+        assert_eq!(path.last(), Some(&Vec3::new(4., 0., 0.5)));
+
+        //nothing is reachable without a nav mesh
+        assert!(NavMesh::default().find_path(Vec3::ZERO, Vec3::X).is_none());
+    }
+
+    #[test]
+    fn slides_along_boundary_edges_following_the_floor() {
+        let nav_mesh =
+            NavMesh::from_triangles((0..4).flat_map(|x| quad(x as f32, 0., |x| x * 0.5)));
+        let from = Vec3::new(0.5, 0.25, 0.5);
+
+        let (triangle, to) = nav_mesh.slide(nav_mesh.locate(from).unwrap(), from, Vec3::ONE);
+
+        assert!(to.distance(Vec3::new(1.5, 0.75, 1.)) < 1e-4, "{to}");
+        assert_eq!(nav_mesh.locate(to), Some(triangle));
+    }
+
+    #[test]
+    fn barriers_cut_links() {
+        let mut nav_mesh = strip(4);
+        let (start, goal) = (Vec3::new(0.5, 0., 0.5), Vec3::new(3.5, 0., 0.5));
+        let barrier = (Vec3::new(2., 0., -1.), Vec3::new(2., 0., 2.));
+
+        nav_mesh.set_barriers(vec![barrier]);
+        assert!(nav_mesh.find_path(start, goal).is_none());
+
+        let (left, right) = (
+            nav_mesh.locate(Vec3::new(1.9, 0., 0.5)).unwrap(),
+            nav_mesh.locate(Vec3::new(2.1, 0., 0.5)).unwrap(),
+        );
+        assert!(nav_mesh.shared_edge(left, right).is_none());
+        //stopped short of the barrier, sliding along it
+        let to = nav_mesh
+            .slide(left, Vec3::new(1.5, 0., 0.5), Vec3::new(1., 0., 0.2))
+            .1;
+        assert!(to.x < 2. && to.x > 2. - BARRIER_GAP * 2., "{to}");
+        assert!((to.z - 0.7).abs() < BARRIER_GAP, "{to}");
+
+        nav_mesh.set_barriers(Vec::new());
+        assert!(nav_mesh.find_path(start, goal).is_some());
+    }
+}
@@ -0,0 +1,483 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    audio::AudioSinkPlayback,
+    log::info,
+    prelude::{
+        Added, App, Assets, Commands, Component, Entity, EventReader, EventWriter, GlobalTransform,
+        IntoSystemConfigs, Plugin, Quat, Query, Res, SpatialAudioSink, Transform, Update, Vec3,
+        With,
+    },
+    time::Time,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    noise::{update_hearing, NoiseEvent},
+    perception::Occluder,
+    player::{
+        interaction::{interact, Interactable, OpenEvent},
+        inventory::{Inventory, ItemDefinitions, ItemKind, ItemLibrary},
+        Controllable,
+    },
+    scene::nav_mesh::{segments_cross_xz, NavMeshBarrier},
+};
+
+use super::sound_source::SoundSource;
+
+/// Fraction of its swing a door covers per second
+const SWING_SPEED: f32 = 1.5;
+/// Volume left of a sound behind each closed door
+const MUFFLE: f32 = 0.3;
+/// Loudness of a door opening or closing
+const DOOR_LOUDNESS: f32 = 0.6;
+/// Distance at which door noises fade out
+const DOOR_RANGE: f32 = 10.;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum DoorState {
+    Open,
+    Closed,
+    /// opened by a key item for the lock with this name
+    Locked(String),
+}
+
+/// A prop swinging open & shut around a vertical hinge, opened by an [`OpenEvent`]. While shut it
+/// blocks sight, cuts the nav mesh & muffles the sounds behind it
+#[derive(Component, Debug, Clone)]
+pub struct Door {
+    pub state: DoorState,
+    /// bottom of the hinge axis in the prop's space
+    hinge: Vec3,
+    /// bottom of the edge opposite to the hinge in the prop's space
+    latch: Vec3,
+    /// radians swung around the hinge when open, counter clockwise from above
+    open_angle: f32,
+    /// 0 shut & 1 open
+    swing: f32,
+    /// transform of the door when shut
+    closed: Option<Transform>,
+}
+
+impl Door {
+    /// `open_angle` in degrees
+    pub fn new(state: DoorState, hinge: Vec3, latch: Vec3, open_angle: f32) -> Self {
+        Self {
+            swing: match state {
+                DoorState::Open => 1.,
+                DoorState::Closed | DoorState::Locked(_) => 0.,
+            },
+            state,
+            hinge,
+            latch,
+            open_angle: open_angle.to_radians(),
+            closed: None,
+        }
+    }
+
+    /// Puts the door in `state` without swinging it
+    pub fn snap(&mut self, state: DoorState, transform: &mut Transform) {
+        let closed = self.closed_transform(transform);
+
+        self.state = state;
+        self.swing = self.target();
+        *transform = swung(&closed, self.hinge, self.open_angle * self.swing);
+    }
+
+    /// Swing the door is heading to
+    fn target(&self) -> f32 {
+        match self.state {
+            DoorState::Open => 1.,
+            DoorState::Closed | DoorState::Locked(_) => 0.,
+        }
+    }
+
+    /// Transform of the door when shut, worked out from `transform` the first time
+    fn closed_transform(&mut self, transform: &Transform) -> Transform {
+        match self.closed {
+            Some(closed) => closed,
+            None => {
+                //spawned open, the transform isn't the closed one
+                let closed = match self.swing > 0. {
+                    true => swung(transform, self.hinge, -self.open_angle),
+                    false => *transform,
+                };
+                self.closed = Some(closed);
+                closed
+            }
+        }
+    }
+
+    /// Closed or locked & done swinging
+    pub fn shut(&self) -> bool {
+        self.state != DoorState::Open && self.swing <= 0.
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        match self.state {
+            DoorState::Open => "Close the door",
+            DoorState::Closed => "Open the door",
+            DoorState::Locked(_) => "Unlock the door",
+        }
+    }
+
+    /// The door leaf from hinge to latch, in world space
+    fn leaf(&self, transform: &GlobalTransform) -> (Vec3, Vec3) {
+        (
+            transform.transform_point(self.hinge),
+            transform.transform_point(self.latch),
+        )
+    }
+}
+
+/// Whether `interactor` carries a key to `lock`
+fn has_key(inventory: &Inventory, definitions: &ItemDefinitions, lock: &str) -> bool {
+    inventory.0.iter().any(|item| {
+        definitions
+            .items
+            .get(item)
+            .is_some_and(|definition| matches!(&definition.kind, ItemKind::Key(key) if key == lock))
+    })
+}
+
+fn open_doors(
+    mut open_event: EventReader<OpenEvent>,
+    library: Res<ItemLibrary>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    inventory_query: Query<&Inventory>,
+    mut door_query: Query<(&mut Door, &mut Interactable, &GlobalTransform)>,
+    mut noise_events: EventWriter<NoiseEvent>,
+) {
+    for event in open_event.iter() {
+        let Ok((mut door, mut interactable, transform)) = door_query.get_mut(event.target) else {
+            continue;
+        };
+
+        door.state = match &door.state {
+            DoorState::Open => DoorState::Closed,
+            DoorState::Closed => DoorState::Open,
+            DoorState::Locked(lock) => {
+                let unlocked = match (
+                    inventory_query.get(event.interactor),
+                    definitions.get(&library.0),
+                ) {
+                    (Ok(inventory), Some(definitions)) => has_key(inventory, definitions, lock),
+                    _ => false,
+                };
+
+                match unlocked {
+                    true => {
+                        info!("unlocked the {lock} door");
+                        DoorState::Open
+                    }
+                    false => {
+                        info!("the {lock} door is locked");
+                        continue;
+                    }
+                }
+            }
+        };
+        interactable.prompt = door.prompt().into();
+
+        noise_events.send(
+            NoiseEvent::new(transform.translation(), DOOR_LOUDNESS, DOOR_RANGE)
+                .from_entity(event.interactor),
+        );
+    }
+}
+
+/// Swings doors around their hinge towards open or shut
+fn swing_doors(time: Res<Time>, mut door_query: Query<(&mut Door, &mut Transform)>) {
+    for (mut door, mut transform) in &mut door_query {
+        let closed = door.closed_transform(&transform);
+
+        let target = door.target();
+        if door.swing == target {
+            continue;
+        }
+
+        let step = SWING_SPEED * time.delta_seconds();
+        door.swing = match target > door.swing {
+            true => (door.swing + step).min(target),
+            false => (door.swing - step).max(target),
+        };
+
+        //ease in & out
+        let eased = (1. - f32::cos(door.swing * PI)) / 2.;
+        *transform = swung(&closed, door.hinge, door.open_angle * eased);
+    }
+}
+
+/// `transform` rotated by `angle` around the vertical axis through `hinge` (in its own space)
+fn swung(transform: &Transform, hinge: Vec3, angle: f32) -> Transform {
+    let pivot = transform.transform_point(hinge);
+    let rotation = Quat::from_rotation_y(angle);
+
+    Transform {
+        translation: pivot + rotation * (transform.translation - pivot),
+        rotation: rotation * transform.rotation,
+        scale: transform.scale,
+    }
+}
+
+type DoorBlockers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Door,
+        &'static GlobalTransform,
+        Option<&'static NavMeshBarrier>,
+        Option<&'static Occluder>,
+    ),
+>;
+
+/// Shut doors block sight & the nav mesh, doors swinging or open don't
+fn block_shut_doors(mut commands: Commands, door_query: DoorBlockers) {
+    for (entity, door, transform, barrier, occluder) in &door_query {
+        let mut entity = commands.entity(entity);
+
+        match door.shut() {
+            true => {
+                let (hinge, latch) = door.leaf(transform);
+                if barrier != Some(&NavMeshBarrier(hinge, latch)) {
+                    entity.insert(NavMeshBarrier(hinge, latch));
+                }
+                if occluder.is_none() {
+                    entity.insert(Occluder);
+                }
+            }
+            false => {
+                if barrier.is_some() {
+                    entity.remove::<NavMeshBarrier>();
+                }
+                if occluder.is_some() {
+                    entity.remove::<Occluder>();
+                }
+            }
+        }
+    }
+}
+
+/// Volume a sound started playing at, before any door muffled it
+#[derive(Component, Debug, Clone, Copy)]
+struct UnmuffledVolume(f32);
+
+type StartedSounds<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static SpatialAudioSink),
+    (With<SoundSource>, Added<SpatialAudioSink>),
+>;
+
+fn store_unmuffled_volumes(mut commands: Commands, sound_query: StartedSounds) {
+    for (entity, sink) in &sound_query {
+        commands
+            .entity(entity)
+            .insert(UnmuffledVolume(sink.volume()));
+    }
+}
+
+/// Quietens sounds for each shut door between them & the player
+fn muffle_sounds(
+    player_query: Query<&Transform, With<Controllable>>,
+    door_query: Query<(&Door, &GlobalTransform)>,
+    sound_query: Query<(&SoundSource, &UnmuffledVolume, &SpatialAudioSink)>,
+) {
+    let Some(player) = player_query.iter().next() else {
+        return;
+    };
+    let listener = player.translation;
+
+    let shut_doors: Vec<(Vec3, Vec3)> = door_query
+        .iter()
+        .filter(|(door, _)| door.shut())
+        .map(|(door, transform)| door.leaf(transform))
+        .collect();
+
+    for (source, UnmuffledVolume(unmuffled), sink) in &sound_query {
+        let position = source.source(&listener);
+
+        let doors = shut_doors
+            .iter()
+            .filter(|(hinge, latch)| segments_cross_xz(listener, position, *hinge, *latch))
+            .count();
+
+        let volume = unmuffled * MUFFLE.powi(doors as i32);
+
+        if sink.volume() != volume {
+            sink.set_volume(volume);
+        }
+    }
+}
+
+pub struct DoorPlugin;
+
+impl Plugin for DoorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                open_doors.after(interact).before(update_hearing),
+                swing_doors,
+                block_shut_doors,
+                store_unmuffled_volumes,
+                muffle_sounds,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{apply_deferred, MinimalPlugins};
+
+    use crate::scene::nav_mesh::{cut_nav_mesh, tests::strip, NavMesh};
+
+    use super::*;
+
+    /// Unit wide door hinged at its origin, latching along X
+    fn door(state: DoorState) -> Door {
+        Door::new(state, Vec3::ZERO, Vec3::X, 90.)
+    }
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!(a.distance(b) < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn snaps_open_and_shut() {
+        let mut door = door(DoorState::Closed);
+        let mut transform = Transform::default();
+
+        door.snap(DoorState::Open, &mut transform);
+        assert!(!door.shut());
+        assert_near(transform.transform_point(door.latch), Vec3::NEG_Z);
+
+        door.snap(DoorState::Locked("basement".into()), &mut transform);
+        assert!(door.shut());
+        assert_near(transform.transform_point(door.latch), Vec3::X);
+    }
+
+    #[test]
+    fn snaps_shut_when_spawned_open() {
+        let mut door = door(DoorState::Open);
+        let mut transform = Transform::from_rotation(Quat::from_rotation_y(PI / 2.));
+
+        door.snap(DoorState::Closed, &mut transform);
+        assert_near(transform.transform_point(door.latch), Vec3::X);
+        assert_near(transform.rotation * Vec3::X, Vec3::X);
+    }
+
+    #[test]
+    fn swings_around_the_hinge() {
+        let transform = Transform::from_xyz(2., 0., 3.);
+        let hinge = Vec3::new(0.5, 0., 0.);
+
+        let swung = swung(&transform, hinge, PI);
+        assert_near(
+            swung.transform_point(hinge),
+            transform.transform_point(hinge),
+        );
+        assert_near(swung.translation, Vec3::new(3., 0., 3.));
+        assert_near(swung.transform_point(Vec3::X), Vec3::new(2., 0., 3.));
+    }
+
+    #[test]
+    fn finds_keys_in_the_inventory() {
+        let definitions: ItemDefinitions =
+            ron::de::from_str(include_str!("../../../assets/items/items.items.ron")).unwrap();
+
+        let inventory = Inventory(vec!["flashlight".into(), "basement_key".into()]);
+        assert!(has_key(&inventory, &definitions, "basement"));
+        assert!(!has_key(&inventory, &definitions, "attic"));
+
+        let inventory = Inventory(vec!["flashlight".into(), "unknown".into()]);
+        assert!(!has_key(&inventory, &definitions, "basement"));
+    }
+
+    #[test]
+    fn shut_doors_cut_the_nav_mesh() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(strip(4))
+            .add_systems(
+                Update,
+                (block_shut_doors, apply_deferred, cut_nav_mesh).chain(),
+            );
+
+        //leaf across the strip from (2, 0, -1) to (2, 0, 2)
+        let transform =
+            Transform::from_xyz(2., 0., -1.).with_rotation(Quat::from_rotation_y(-PI / 2.));
+        let door = app
+            .world
+            .spawn((
+                Door::new(DoorState::Closed, Vec3::ZERO, Vec3::X * 3., 90.),
+                transform,
+                GlobalTransform::from(transform),
+            ))
+            .id();
+
+        let (start, goal) = (Vec3::new(0.5, 0., 0.5), Vec3::new(3.5, 0., 0.5));
+        app.update();
+        assert!(app
+            .world
+            .resource::<NavMesh>()
+            .find_path(start, goal)
+            .is_none());
+
+        app.world.get_mut::<Door>(door).unwrap().state = DoorState::Open;
+        app.world.get_mut::<Door>(door).unwrap().swing = 1.;
+        app.update();
+        assert!(app
+            .world
+            .resource::<NavMesh>()
+            .find_path(start, goal)
+            .is_some());
+    }
+
+    #[test]
+    fn shut_doors_block_moves_within_a_triangle() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(NavMesh::from_triangles([[
+                Vec3::ZERO,
+                Vec3::new(0., 0., 4.),
+                Vec3::new(4., 0., 0.),
+            ]]))
+            .add_systems(
+                Update,
+                (block_shut_doors, apply_deferred, cut_nav_mesh).chain(),
+            );
+
+        //leaf across the only triangle from (1, 0, -1) to (1, 0, 5)
+        let transform =
+            Transform::from_xyz(1., 0., -1.).with_rotation(Quat::from_rotation_y(-PI / 2.));
+        let door = app
+            .world
+            .spawn((
+                Door::new(DoorState::Closed, Vec3::ZERO, Vec3::X * 6., 90.),
+                transform,
+                GlobalTransform::from(transform),
+            ))
+            .id();
+
+        let from = Vec3::new(0.5, 0., 0.5);
+        let walk = |app: &App| {
+            let nav_mesh = app.world.resource::<NavMesh>();
+            nav_mesh
+                .slide(nav_mesh.locate(from).unwrap(), from, Vec3::X * 2.)
+                .1
+        };
+
+        app.update();
+        let to = walk(&app);
+        assert!(to.x < 1. && to.x > 0.9, "{to} went through the door");
+
+        app.world.get_mut::<Door>(door).unwrap().state = DoorState::Open;
+        app.world.get_mut::<Door>(door).unwrap().swing = 1.;
+        app.update();
+        assert_near(walk(&app), Vec3::new(2.5, 0., 0.5));
+    }
+}
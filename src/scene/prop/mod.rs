@@ -1,51 +1,31 @@
+use crate::perception::{Perception, VisionSensor};
 use bevy::{
     asset::Asset,
     prelude::{
         default, App, AssetServer, Assets, Color, Commands, Component, Entity, GlobalTransform,
-        Handle, Material, MaterialMeshBundle, Mesh, Plugin, PreStartup, Query, ResMut, Resource,
-        Startup, Transform, Update, Vec3, With,
+        Handle, IntoSystemConfigs, Material, MaterialMeshBundle, Mesh, Plugin, PreStartup, Query,
+        Res, ResMut, Resource, Startup, Transform, Update, Vec3, With,
     },
     reflect::TypeUuid,
     utils::HashMap,
 };
-use bevy_mod_raycast::{
-    prelude::{Raycast, RaycastSettings, RaycastVisibility},
-    primitives::Ray3d,
-};
 
-use self::materials::{plastic::PlasticMaterial, MaterialsPlugin};
+use self::{
+    door::DoorPlugin,
+    materials::{plastic::PlasticMaterial, MaterialsPlugin},
+    physics::PropPhysicsPlugin,
+};
 
-use super::shadow_caster::ShadowCasterMaterial;
+use super::{
+    catalog::{build_asset_catalog, AssetCatalog, CatalogKind},
+    shadow_caster::ShadowCasterMaterial,
+};
 
+pub mod door;
 pub mod materials;
+pub mod physics;
 pub mod sound_source;
 
-#[derive(Component)]
-pub struct PropVisibilityBlocker;
-#[derive(Component)]
-pub struct PropVisibilitySource(f32);
-
-impl PropVisibilitySource {
-    pub fn from_angle(angle: f32) -> Self {
-        Self(f32::cos(angle))
-    }
-    pub fn from_cos(cos: f32) -> Self {
-        Self(cos)
-    }
-}
-
-impl From<f32> for PropVisibilitySource {
-    fn from(value: f32) -> Self {
-        PropVisibilitySource::from_cos(value)
-    }
-}
-
-impl Default for PropVisibilitySource {
-    fn default() -> Self {
-        Self(0.25)
-    }
-}
-
 #[derive(Component)]
 pub struct PropVisibilityTarget(pub Vec<Vec3>);
 
@@ -112,34 +92,32 @@ pub fn show_seen_props<M: TypeUuid + Asset + Material>(
     }
 }
 
+/// Reveals props seen by a [`VisionSensor`] & hides forgettable props once an
+/// [`crate::perception::Occluder`] is in the way
 pub fn update_prop_visibility(
-    source_query: Query<(&GlobalTransform, &PropVisibilitySource)>,
+    sensor_query: Query<(&GlobalTransform, &VisionSensor)>,
 
     mut prop_query: Query<
         (
             Entity,
             &GlobalTransform,
+            &PropVisibilityTarget,
             Option<&Forgettable>,
             &mut PropVisibility,
         ),
-        (With<Handle<Mesh>>, With<PropVisibilityTarget>),
+        With<Handle<Mesh>>,
     >,
-    target_query: Query<&PropVisibilityTarget, With<Handle<Mesh>>>,
-    blocker_query: Query<(), (With<PropVisibilityBlocker>, With<Handle<Mesh>>)>,
 
-    mut ray_cast: Raycast,
+    mut perception: Perception,
     // mut gizmos: Gizmos,
 ) {
-    for (source_transform, source) in &source_query {
-        let origin = source_transform.translation();
+    for (sensor_transform, sensor) in &sensor_query {
+        let origin = sensor_transform.translation();
+        let forward = sensor_transform.forward();
 
-        // gizmos.ray(origin, source_transform.forward(), Color::RED);
-
-        for target in &mut prop_query {
-            let (target, target_transform, forgettable, mut visibility) = target;
-
-            let target = target_query.get(target).unwrap();
+        // gizmos.ray(origin, forward, Color::RED);
 
+        for (entity, target_transform, target, forgettable, mut visibility) in &mut prop_query {
             'pos_loop: for target_pos in target.0.iter() {
                 let target_pos = {
                     let mut pos = *target_pos;
@@ -150,30 +128,20 @@ pub fn update_prop_visibility(
 
                 // gizmos.sphere(target_pos, Quat::IDENTITY, 0.05, Color::RED);
 
-                let direction = (target_pos - origin).normalize();
-
-                if source_transform.forward().dot(direction) < source.0 {
+                if !sensor.in_view(origin, forward, target_pos)
+                    || !sensor.bright_enough(
+                        origin.distance(target_pos),
+                        perception.light_level(target_pos),
+                    )
+                {
                     continue;
                 }
 
-                let ray = Ray3d::new(origin, direction);
-                let settings = RaycastSettings {
-                    visibility: RaycastVisibility::MustBeVisible,
-                    filter: &|entity: Entity| {
-                        target_query.contains(entity) || blocker_query.contains(entity)
-                    },
-                    early_exit_test: &|_| true,
-                };
-                // ray_cast.debug_cast_ray(ray, &settings, &mut gizmos);
-
-                let ray_hits = ray_cast.cast_ray(ray, &settings);
+                let seen = perception.line_of_sight(origin, target_pos, Some(entity));
 
-                let Some((entity, _)) = ray_hits.iter().next() else {
-                    continue;
-                };
                 {
                     let visibility = visibility.as_mut();
-                    match (target_query.contains(*entity), &visibility, forgettable) {
+                    match (seen, &visibility, forgettable) {
                         (false, PropVisibility::Seen, None)
                         | (false, PropVisibility::Hidden, _)
                         | (true, PropVisibility::Seen, _) => {}
@@ -232,10 +200,7 @@ pub fn into_mesh_bundle<M: TypeUuid + Asset + Material>(
       // )
 }
 
-fn load_plastic_prop(
-    asset_server: &ResMut<AssetServer>,
-    dir: &'static str,
-) -> Prop<PlasticMaterial> {
+fn load_plastic_prop(asset_server: &ResMut<AssetServer>, dir: &str) -> Prop<PlasticMaterial> {
     Prop {
         mesh: asset_server.load(format!("props/{dir}/mesh/mesh.glb#Mesh0/Primitive0")),
         material: PlasticMaterial {
@@ -248,12 +213,15 @@ fn load_plastic_prop(
 
 pub fn load_plastic_props(
     mut props: ResMut<Props<PlasticMaterial>>,
+    catalog: Res<AssetCatalog>,
     asset_server: ResMut<AssetServer>,
 ) {
-    props.as_mut().0.insert(
-        "plastic_bin_1".into(),
-        load_plastic_prop(&asset_server, "plastic_bin_1"),
-    );
+    for key in catalog.keys(CatalogKind::PlasticProp) {
+        props
+            .as_mut()
+            .0
+            .insert(key.into(), load_plastic_prop(&asset_server, key));
+    }
 }
 
 pub fn setup(mut _commands: Commands) {}
@@ -265,9 +233,9 @@ impl Plugin for PropPlugin {
         let plastic_props = Props::<PlasticMaterial>(HashMap::new());
 
         app.insert_resource(plastic_props)
-            .add_plugins((MaterialsPlugin,))
+            .add_plugins((MaterialsPlugin, PropPhysicsPlugin, DoorPlugin))
             .add_systems(Startup, setup)
-            .add_systems(PreStartup, load_plastic_props)
+            .add_systems(PreStartup, load_plastic_props.after(build_asset_catalog))
             .add_systems(Update, update_prop_visibility)
             .add_systems(Update, prop_visibility_system![PlasticMaterial]);
     }
@@ -0,0 +1,606 @@
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    math::BVec3,
+    prelude::{
+        default, warn, App, AssetServer, Assets, Commands, Component, Entity, EventWriter,
+        GlobalTransform, Handle, Input, IntoSystemConfigs, KeyCode, Mat3, Mesh, Parent,
+        PlaybackSettings, Plugin, PostUpdate, Quat, Query, Res, SpatialSettings, Transform, Update,
+        Vec3, With, Without,
+    },
+    render::mesh::VertexAttributeValues,
+    time::Time,
+    transform::TransformSystem,
+};
+use serde::Deserialize;
+
+use crate::{
+    humanoid::ragdoll::Standing,
+    noise::{update_hearing, NoiseEvent},
+    player::{controller::KICK, Controllable},
+    scene::nav_mesh::NavMesh,
+};
+
+use super::sound_source::{PropSoundBundle, SoundSource};
+
+const GRAVITY: Vec3 = Vec3::new(0., -9.81, 0.);
+/// Height of the floor away from the nav mesh
+const GROUND: f32 = 0.;
+/// Longest simulation step, longer frames are split up
+const MAX_STEP: f32 = 1. / 120.;
+const CONTACT_ITERATIONS: usize = 4;
+/// Fraction of the velocity lost per second
+const LINEAR_DAMPING: f32 = 0.1;
+const ANGULAR_DAMPING: f32 = 0.5;
+/// Floor contacts slower than this don't bounce
+const BOUNCE_SPEED: f32 = 0.5;
+/// Speed under which a body resting on the floor falls asleep
+const SLEEP_SPEED: f32 = 0.05;
+/// Seconds a body rests before it sleeps
+const SLEEP_TIME: f32 = 0.5;
+/// Slowest impact making a noise
+const IMPACT_SPEED: f32 = 1.;
+/// Seconds between the impact noises of a body
+const IMPACT_COOLDOWN: f32 = 0.2;
+/// Loudness of an impact per unit of speed & square root of mass
+const IMPACT_LOUDNESS: f32 = 0.15;
+const MAX_IMPACT_LOUDNESS: f32 = 2.;
+/// Distance at which impact noises fade out
+const IMPACT_RANGE: f32 = 12.;
+/// Impact sound of rigid bodies that don't set their own
+const DEFAULT_IMPACT_SOUND: &str = "props/impact.ogg";
+/// Height above its feet a [`Pusher`] bumps props at
+const BUMP_HEIGHT: f32 = 0.4;
+/// Reach of a kick from the kicker's feet
+const KICK_REACH: f32 = 0.9;
+/// Impulse of a kick, a 1kg prop flies off at this speed
+const KICK_IMPULSE: f32 = 8.;
+/// Height above the feet a kick lands at
+const KICK_HEIGHT: f32 = 0.2;
+
+/// How a [`RigidBody`]'s collider is fit to its prop mesh
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColliderShape {
+    /// corners of the mesh's bounding box
+    #[default]
+    BoundingBox,
+    /// the mesh vertices furthest along the 26 directions to the faces, edges & corners of a
+    /// cube, an approximation of the convex hull
+    ConvexHull,
+}
+
+/// Lets a prop fall, tumble & be pushed around. Simulated once a [`Collider`] is fit to its mesh
+#[derive(Component, Debug, Clone)]
+pub struct RigidBody {
+    pub mass: f32,
+    pub friction: f32,
+    pub restitution: f32,
+    pub shape: ColliderShape,
+    pub velocity: Vec3,
+    pub angular_velocity: Vec3,
+    /// seconds spent resting, asleep after [`SLEEP_TIME`]
+    rest: f32,
+    /// seconds until the next impact can be heard
+    impact_cooldown: f32,
+}
+
+/// Sound played where a [`RigidBody`] hits the floor, [`DEFAULT_IMPACT_SOUND`] without one
+#[derive(Component, Debug, Clone)]
+pub struct ImpactSound(pub String);
+
+/// Convex collider of a [`RigidBody`], fit to its mesh
+#[derive(Component, Debug, Clone)]
+pub struct Collider {
+    /// hull points in the body's space, scaled
+    pub points: Vec<Vec3>,
+    /// centre of mass in the body's space, scaled
+    pub center: Vec3,
+    /// inverse principal moments of inertia of the bounding box
+    inverse_inertia: Vec3,
+}
+
+/// Bumps rigid bodies out of its way while moving, eg: the player walking into props
+#[derive(Component, Debug)]
+pub struct Pusher {
+    pub radius: f32,
+    pub height: f32,
+    last_position: Option<Vec3>,
+}
+
+impl Pusher {
+    pub fn new(radius: f32, height: f32) -> Self {
+        Self {
+            radius,
+            height,
+            last_position: None,
+        }
+    }
+
+    /// Forgets where it last stood, so a teleport isn't taken for a push
+    pub fn teleported(&mut self) {
+        self.last_position = None;
+    }
+}
+
+impl Collider {
+    pub fn fit(mesh: &Mesh, shape: ColliderShape, scale: Vec3, mass: f32) -> Option<Self> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+
+        let positions: Vec<Vec3> = positions
+            .iter()
+            .map(|position| Vec3::from(*position) * scale)
+            .collect();
+
+        let (min, max) = bounds(positions.iter().copied())?;
+
+        let points = match shape {
+            ColliderShape::BoundingBox => (0..8)
+                .map(|corner| {
+                    Vec3::select(
+                        BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0),
+                        max,
+                        min,
+                    )
+                })
+                .collect(),
+            ColliderShape::ConvexHull => hull_points(&positions),
+        };
+
+        let size = max - min;
+        let inertia = Vec3::new(
+            size.y * size.y + size.z * size.z,
+            size.x * size.x + size.z * size.z,
+            size.x * size.x + size.y * size.y,
+        ) * mass
+            / 12.;
+
+        Some(Self {
+            points,
+            center: (min + max) / 2.,
+            inverse_inertia: Vec3::ONE / inertia.max(Vec3::splat(f32::EPSILON)),
+        })
+    }
+
+    /// Centre of mass of a body at world `transform`
+    pub fn center(&self, transform: &Transform) -> Vec3 {
+        transform.translation + transform.rotation * self.center
+    }
+
+    fn world_points<'a>(&'a self, transform: &'a Transform) -> impl Iterator<Item = Vec3> + 'a {
+        self.points
+            .iter()
+            .map(|point| transform.translation + transform.rotation * *point)
+    }
+
+    /// World space bounding box of a body at world `transform`
+    pub fn bounds(&self, transform: &Transform) -> (Vec3, Vec3) {
+        bounds(self.world_points(transform))
+            .unwrap_or((transform.translation, transform.translation))
+    }
+
+    fn inverse_inertia(&self, transform: &Transform) -> Mat3 {
+        let rotation = Mat3::from_quat(transform.rotation);
+
+        rotation * Mat3::from_diagonal(self.inverse_inertia) * rotation.transpose()
+    }
+}
+
+fn bounds(points: impl Iterator<Item = Vec3>) -> Option<(Vec3, Vec3)> {
+    points.fold(None, |bounds, point| match bounds {
+        Some((min, max)) => Some((point.min(min), point.max(max))),
+        None => Some((point, point)),
+    })
+}
+
+fn hull_points(positions: &[Vec3]) -> Vec<Vec3> {
+    let mut points: Vec<Vec3> = Vec::new();
+
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                let direction = Vec3::new(x as f32, y as f32, z as f32);
+                if direction == Vec3::ZERO {
+                    continue;
+                }
+
+                let Some(furthest) = positions
+                    .iter()
+                    .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+                else {
+                    continue;
+                };
+
+                if !points.contains(furthest) {
+                    points.push(*furthest);
+                }
+            }
+        }
+    }
+
+    points
+}
+
+impl RigidBody {
+    pub fn new(mass: f32, friction: f32, restitution: f32, shape: ColliderShape) -> Self {
+        Self {
+            mass,
+            friction,
+            restitution,
+            shape,
+            velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
+            rest: 0.,
+            impact_cooldown: 0.,
+        }
+    }
+
+    pub fn asleep(&self) -> bool {
+        self.rest >= SLEEP_TIME
+    }
+
+    /// Applies `impulse` at world `point` of the body at world `transform` & wakes it up
+    pub fn apply_impulse(
+        &mut self,
+        collider: &Collider,
+        transform: &Transform,
+        impulse: Vec3,
+        point: Vec3,
+    ) {
+        let inverse_inertia = collider.inverse_inertia(transform);
+        self.apply_at(
+            &inverse_inertia,
+            point - collider.center(transform),
+            impulse,
+        );
+        self.rest = 0.;
+    }
+
+    fn apply_at(&mut self, inverse_inertia: &Mat3, offset: Vec3, impulse: Vec3) {
+        self.velocity += impulse / self.mass;
+        self.angular_velocity += *inverse_inertia * offset.cross(impulse);
+    }
+
+    /// Velocity of the point at `offset` from the centre of mass
+    fn velocity_at(&self, offset: Vec3) -> Vec3 {
+        self.velocity + self.angular_velocity.cross(offset)
+    }
+
+    /// Inverse of the mass felt by an impulse along `direction` at `offset` from the centre of
+    /// mass
+    fn inverse_mass_at(&self, inverse_inertia: &Mat3, offset: Vec3, direction: Vec3) -> f32 {
+        1. / self.mass + direction.dot((*inverse_inertia * offset.cross(direction)).cross(offset))
+    }
+
+    /// Advances the body at world `transform` by `delta`, sliding it over the nav mesh & resting
+    /// it on the floor. Returns the speed of the hardest impact with the floor
+    fn step(
+        &mut self,
+        collider: &Collider,
+        transform: &mut Transform,
+        nav_mesh: &NavMesh,
+        delta: f32,
+    ) -> f32 {
+        self.velocity += GRAVITY * delta;
+        self.velocity *= 1. - LINEAR_DAMPING * delta;
+        self.angular_velocity *= 1. - ANGULAR_DAMPING * delta;
+
+        let center = collider.center(transform);
+        let movement = self.velocity * delta;
+
+        //walls of the nav mesh stop the body, away from it there's only the ground
+        let (center, floor) = match nav_mesh.project_down(center) {
+            Some((triangle, floor)) => {
+                let (_, new_floor) = nav_mesh.slide(triangle, floor, movement);
+                let moved = new_floor - floor;
+
+                self.velocity.x = moved.x / delta;
+                self.velocity.z = moved.z / delta;
+
+                (
+                    Vec3::new(new_floor.x, center.y + movement.y, new_floor.z),
+                    new_floor.y,
+                )
+            }
+            None => (center + movement, GROUND),
+        };
+
+        transform.rotation = (Quat::from_scaled_axis(self.angular_velocity * delta)
+            * transform.rotation)
+            .normalize();
+        transform.translation = center - transform.rotation * collider.center;
+
+        //(offset from the centre of mass, normal speed to reach) of the points under the floor
+        let mut penetration: f32 = 0.;
+        let mut impact: f32 = 0.;
+        let contacts: Vec<(Vec3, f32)> = collider
+            .world_points(transform)
+            .filter(|point| point.y < floor)
+            .map(|point| {
+                penetration = penetration.max(floor - point.y);
+
+                let offset = point - center;
+                let speed = -self.velocity_at(offset).y;
+                impact = impact.max(speed);
+
+                let bounce = match speed > BOUNCE_SPEED {
+                    true => speed * self.restitution,
+                    false => 0.,
+                };
+                (offset, bounce)
+            })
+            .collect();
+
+        let inverse_inertia = collider.inverse_inertia(transform);
+        let mut normal_impulses = vec![0.; contacts.len()];
+        let mut friction_impulses = vec![0.; contacts.len()];
+
+        for _ in 0..CONTACT_ITERATIONS {
+            for (i, (offset, target)) in contacts.iter().enumerate() {
+                let speed = self.velocity_at(*offset).y;
+                let impulse =
+                    (target - speed) / self.inverse_mass_at(&inverse_inertia, *offset, Vec3::Y);
+
+                //contacts only push
+                let total = (normal_impulses[i] + impulse).max(0.);
+                self.apply_at(
+                    &inverse_inertia,
+                    *offset,
+                    Vec3::Y * (total - normal_impulses[i]),
+                );
+                normal_impulses[i] = total;
+
+                //coulomb friction against sliding
+                let velocity = self.velocity_at(*offset);
+                let sliding = Vec3::new(velocity.x, 0., velocity.z);
+                let slide_speed = sliding.length();
+                if slide_speed < f32::EPSILON {
+                    continue;
+                }
+
+                let tangent = sliding / slide_speed;
+                let friction = (slide_speed
+                    / self.inverse_mass_at(&inverse_inertia, *offset, tangent))
+                .min(self.friction * normal_impulses[i] - friction_impulses[i])
+                .max(0.);
+                self.apply_at(&inverse_inertia, *offset, -tangent * friction);
+                friction_impulses[i] += friction;
+            }
+        }
+
+        transform.translation.y += penetration;
+
+        let resting = !contacts.is_empty()
+            && self.velocity.length() < SLEEP_SPEED
+            && self.angular_velocity.length() < SLEEP_SPEED;
+        match resting {
+            true => {
+                self.rest += delta;
+                if self.asleep() {
+                    self.velocity = Vec3::ZERO;
+                    self.angular_velocity = Vec3::ZERO;
+                }
+            }
+            false => self.rest = 0.,
+        }
+
+        impact
+    }
+}
+
+/// Fits a [`Collider`] to each rigid body once its mesh is loaded & its transform propagated
+fn fit_colliders(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    body_query: Query<(Entity, &RigidBody, &Handle<Mesh>, &GlobalTransform), Without<Collider>>,
+) {
+    for (entity, body, mesh, transform) in &body_query {
+        let Some(mesh) = meshes.get(mesh) else {
+            continue;
+        };
+
+        let scale = transform.compute_transform().scale;
+        match Collider::fit(mesh, body.shape, scale, body.mass) {
+            Some(collider) => {
+                commands.entity(entity).insert(collider);
+            }
+            None => {
+                warn!("rigid body {entity:?} has a mesh without positions, it stays static");
+                commands.entity(entity).remove::<RigidBody>();
+            }
+        }
+    }
+}
+
+/// Point of the footprint of `bounds` closest to `position`, at the height of `position`
+fn closest_footprint_point((min, max): (Vec3, Vec3), position: Vec3) -> Vec3 {
+    Vec3::new(
+        position.x.clamp(min.x, max.x),
+        position.y.clamp(min.y, max.y),
+        position.z.clamp(min.z, max.z),
+    )
+}
+
+pub fn push_rigid_bodies(
+    time: Res<Time>,
+    mut pusher_query: Query<(&mut Pusher, &GlobalTransform)>,
+    mut body_query: Query<(&mut RigidBody, &Collider, &GlobalTransform)>,
+) {
+    for (mut pusher, transform) in &mut pusher_query {
+        let position = transform.translation();
+        let velocity = match (pusher.last_position, time.delta_seconds() > 0.) {
+            (Some(last_position), true) => (position - last_position) / time.delta_seconds(),
+            _ => Vec3::ZERO,
+        };
+        pusher.last_position = Some(position);
+
+        if velocity.length_squared() < f32::EPSILON {
+            continue;
+        }
+
+        for (mut body, collider, body_transform) in &mut body_query {
+            let body_transform = body_transform.compute_transform();
+            let bounds = collider.bounds(&body_transform);
+
+            //above or below the pusher
+            if bounds.1.y < position.y || bounds.0.y > position.y + pusher.height {
+                continue;
+            }
+
+            let point = closest_footprint_point(bounds, position + Vec3::Y * BUMP_HEIGHT);
+            let offset = Vec3::new(point.x - position.x, 0., point.z - position.z);
+            if offset.length() > pusher.radius {
+                continue;
+            }
+
+            //inside the footprint, push it along
+            let normal = match offset.try_normalize() {
+                Some(normal) => normal,
+                None => Vec3::new(velocity.x, 0., velocity.z).normalize_or_zero(),
+            };
+
+            let inverse_inertia = collider.inverse_inertia(&body_transform);
+            let body_offset = point - collider.center(&body_transform);
+            let closing = (velocity - body.velocity_at(body_offset)).dot(normal);
+            if closing <= 0. {
+                continue;
+            }
+
+            let impulse =
+                normal * closing / body.inverse_mass_at(&inverse_inertia, body_offset, normal);
+            body.apply_impulse(collider, &body_transform, impulse, point);
+        }
+    }
+}
+
+/// Kicks the rigid bodies within reach of the [`Controllable`] entity's feet away from it
+fn kick_rigid_bodies(
+    keyboard_input: Res<Input<KeyCode>>,
+    kicker_query: Query<&GlobalTransform, (With<Controllable>, Standing)>,
+    mut body_query: Query<(&mut RigidBody, &Collider, &GlobalTransform)>,
+) {
+    if !keyboard_input.just_pressed(KICK) {
+        return;
+    }
+
+    for kicker in &kicker_query {
+        let feet = kicker.translation();
+
+        for (mut body, collider, body_transform) in &mut body_query {
+            let body_transform = body_transform.compute_transform();
+            let point = closest_footprint_point(
+                collider.bounds(&body_transform),
+                feet + Vec3::Y * KICK_HEIGHT,
+            );
+
+            if feet.distance(point) > KICK_REACH {
+                continue;
+            }
+
+            let center = collider.center(&body_transform);
+            let away = Vec3::new(center.x - feet.x, 0., center.z - feet.z).normalize_or_zero();
+
+            body.apply_impulse(
+                collider,
+                &body_transform,
+                (away + Vec3::Y * 0.5).normalize() * KICK_IMPULSE,
+                point,
+            );
+        }
+    }
+}
+
+/// Rigid bodies with a collider, simulated in world space
+type SimulatedBodies<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut RigidBody,
+        &'static Collider,
+        &'static mut Transform,
+        &'static GlobalTransform,
+        Option<&'static Parent>,
+        Option<&'static ImpactSound>,
+    ),
+>;
+
+/// Steps the rigid bodies & makes a noise & an [`ImpactSound`] when they hit the floor hard
+fn simulate_rigid_bodies(
+    mut commands: Commands,
+    time: Res<Time>,
+    nav_mesh: Res<NavMesh>,
+    asset_server: Res<AssetServer>,
+    mut body_query: SimulatedBodies,
+    parent_query: Query<&GlobalTransform, Without<RigidBody>>,
+    mut noise_events: EventWriter<NoiseEvent>,
+) {
+    let steps = (time.delta_seconds() / MAX_STEP).ceil().max(1.);
+    let delta = time.delta_seconds() / steps;
+
+    if delta <= 0. {
+        return;
+    }
+
+    for (entity, mut body, collider, mut transform, global_transform, parent, impact_sound) in
+        &mut body_query
+    {
+        body.impact_cooldown -= time.delta_seconds();
+
+        if body.asleep() {
+            continue;
+        }
+
+        let mut world = global_transform.compute_transform();
+        let mut impact: f32 = 0.;
+        for _ in 0..steps as usize {
+            impact = impact.max(body.step(collider, &mut world, &nav_mesh, delta));
+        }
+
+        *transform = match parent.and_then(|parent| parent_query.get(parent.get()).ok()) {
+            Some(parent) => GlobalTransform::from(world).reparented_to(parent),
+            None => world,
+        };
+
+        if impact < IMPACT_SPEED || body.impact_cooldown > 0. {
+            continue;
+        }
+        body.impact_cooldown = IMPACT_COOLDOWN;
+
+        let position = collider.center(&world);
+        let loudness = (impact * body.mass.sqrt() * IMPACT_LOUDNESS).min(MAX_IMPACT_LOUDNESS);
+
+        noise_events.send(NoiseEvent::new(position, loudness, IMPACT_RANGE).from_entity(entity));
+
+        let sound = impact_sound.map_or(DEFAULT_IMPACT_SOUND, |ImpactSound(sound)| sound);
+        commands.spawn(PropSoundBundle {
+            sound_source: SoundSource::Point(position),
+            source: asset_server.load(sound),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: Volume::new_relative(loudness / MAX_IMPACT_LOUDNESS),
+                ..default()
+            },
+            spatial: SpatialSettings::new(Transform::default(), 1.0, Vec3::ZERO),
+        });
+    }
+}
+
+pub struct PropPhysicsPlugin;
+
+impl Plugin for PropPhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (push_rigid_bodies, kick_rigid_bodies, simulate_rigid_bodies)
+                .chain()
+                .before(update_hearing),
+        )
+        .add_systems(
+            PostUpdate,
+            fit_colliders.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
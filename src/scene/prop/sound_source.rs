@@ -1,8 +1,9 @@
 use bevy::prelude::{
-    AudioSource, Bundle, Component, Handle, PlaybackSettings, SpatialSettings, Vec3,
+    AudioSource, Bundle, Component, Handle, PlaybackSettings, SpatialSettings, Transform, Vec3,
 };
+use serde::Deserialize;
 
-#[derive(Component)]
+#[derive(Component, Deserialize, Debug, Clone)]
 pub enum SoundSource {
     Point(Vec3),
     Area(Vec<Vec3>),
@@ -24,6 +25,18 @@ impl SoundSource {
             }
         }
     }
+
+    /// The source moved from `transform`'s space into world space
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        match self {
+            SoundSource::Point(pos) => SoundSource::Point(transform.transform_point(*pos)),
+            SoundSource::Area(pos) => SoundSource::Area(
+                pos.iter()
+                    .map(|pos| transform.transform_point(*pos))
+                    .collect(),
+            ),
+        }
+    }
 }
 
 #[derive(Component)]
@@ -0,0 +1,228 @@
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    ecs::system::SystemParam,
+    prelude::{
+        error, AssetServer, Assets, BuildChildren, Color, Commands, Entity, MaterialMeshBundle,
+        Mesh, PbrBundle, PlaybackSettings, Res, ResMut, SpatialBundle, SpatialSettings,
+        StandardMaterial, Transform, Vec3,
+    },
+};
+
+use crate::{
+    cryptid::CryptidSpawn,
+    player::{
+        interaction::{Interactable, Verb},
+        inventory::Item,
+        target::PlayerTargetSet,
+    },
+};
+
+use super::{
+    catalog::{AssetCatalog, CatalogKind},
+    description::{insert_markers, Marker, SceneDescription},
+    floor::{self, FloorMaterial, Floors},
+    nav_mesh::NavMeshBundle,
+    prop::{
+        self,
+        materials::plastic::PlasticMaterial,
+        sound_source::{PropSoundBundle, SoundVolume},
+        PropVisibility, Props,
+    },
+    shadow_caster::ShadowCasterMaterial,
+    wall::{self, WallMaterial, Walls},
+    SceneItemId, SceneRoot,
+};
+
+/// Everything needed to turn a [`SceneDescription`] into entities
+#[derive(SystemParam)]
+pub struct SceneSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    catalog: Res<'w, AssetCatalog>,
+    floors: Res<'w, Floors>,
+    walls: Res<'w, Walls>,
+    plastic_props: Res<'w, Props<PlasticMaterial>>,
+    asset_server: Res<'w, AssetServer>,
+    floor_materials: ResMut<'w, Assets<FloorMaterial>>,
+    wall_materials: ResMut<'w, Assets<WallMaterial>>,
+    shadow_caster_material: ResMut<'w, Assets<ShadowCasterMaterial>>,
+    plastic_material: ResMut<'w, Assets<PlasticMaterial>>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    standard_materials: ResMut<'w, Assets<StandardMaterial>>,
+}
+
+impl<'w, 's> SceneSpawner<'w, 's> {
+    fn report_missing(&self, scene: &str, kind: CatalogKind, key: &str) {
+        match self.catalog.issue(key) {
+            Some(issue) => error!("{scene}: cannot spawn incomplete {issue}"),
+            None => error!("{scene}: no {kind} named {key:?} in the asset catalog"),
+        }
+    }
+
+    /// Spawns the scene under a new [`SceneRoot`] placed at `transform` & returns the root
+    pub fn spawn(
+        &mut self,
+        name: &str,
+        description: &SceneDescription,
+        transform: Transform,
+    ) -> Entity {
+        let mut children = Vec::new();
+
+        //shadow caster
+        for (index, shadow_caster) in description.shadow_casters.iter().enumerate() {
+            let mut entity = self.commands.spawn((
+                MaterialMeshBundle {
+                    mesh: self.asset_server.load(&shadow_caster.mesh),
+                    material: self.shadow_caster_material.add(Default::default()),
+                    transform: (&shadow_caster.transform).into(),
+                    ..Default::default()
+                },
+                SceneItemId::new(name, "shadow_casters", index),
+            ));
+            insert_markers(&mut entity, &shadow_caster.markers);
+            children.push(entity.id());
+        }
+        //walls
+        for (index, item) in description.walls.iter().enumerate() {
+            let Some(wall) = self.walls.0.get(&item.key) else {
+                self.report_missing(name, CatalogKind::Wall, &item.key);
+                continue;
+            };
+
+            let mut entity = self.commands.spawn((
+                wall::into_mesh_bundle(
+                    wall,
+                    &mut self.wall_materials,
+                    Some((&item.transform).into()),
+                ),
+                SceneItemId::new(name, "walls", index),
+            ));
+            insert_markers(&mut entity, &item.markers);
+            children.push(entity.id());
+        }
+        //floors
+        for (index, item) in description.floors.iter().enumerate() {
+            let Some(floor) = self.floors.0.get(&item.key) else {
+                self.report_missing(name, CatalogKind::Floor, &item.key);
+                continue;
+            };
+
+            let mut entity = self.commands.spawn((
+                floor::into_mesh_bundle(
+                    floor,
+                    &mut self.floor_materials,
+                    Some((&item.transform).into()),
+                ),
+                SceneItemId::new(name, "floors", index),
+            ));
+            insert_markers(&mut entity, &item.markers);
+            children.push(entity.id());
+        }
+        //sound emitters, their sources are in world space so they're placed with the root
+        for emitter in &description.sound_emitters {
+            let entity = self.commands.spawn((
+                PropSoundBundle {
+                    sound_source: emitter.sound_source.transformed(&transform),
+                    source: self.asset_server.load(&emitter.source),
+                    settings: PlaybackSettings {
+                        mode: match emitter.looping {
+                            true => PlaybackMode::Loop,
+                            false => PlaybackMode::Despawn,
+                        },
+                        volume: Volume::new_relative(emitter.volume),
+                        ..Default::default()
+                    },
+                    spatial: SpatialSettings::new(Transform::default(), 1.0, Vec3::ZERO),
+                },
+                SoundVolume::from(&emitter.sound_volume),
+            ));
+            children.push(entity.id());
+        }
+        //nav mesh
+        if let Some(nav_mesh) = &description.nav_mesh {
+            let entity = self.commands.spawn((NavMeshBundle::new(
+                self.asset_server.load(&nav_mesh.mesh),
+                (&nav_mesh.transform).into(),
+            ),));
+            children.push(entity.id());
+        }
+        for area in &description.nav_areas {
+            let entity = self.commands.spawn(NavMeshBundle::new(
+                self.meshes.add(area.mesh()),
+                Transform::default(),
+            ));
+            children.push(entity.id());
+        }
+        //props
+        for (index, item) in description.plastic_props.iter().enumerate() {
+            let Some(prop) = self.plastic_props.0.get(&item.key) else {
+                self.report_missing(name, CatalogKind::PlasticProp, &item.key);
+                continue;
+            };
+
+            let mut entity = self.commands.spawn((
+                prop::into_mesh_bundle(
+                    prop,
+                    &mut self.plastic_material,
+                    Some((&item.transform).into()),
+                ),
+                prop.clone(),
+                PropVisibility::Hidden,
+                SceneItemId::new(name, "plastic_props", index),
+            ));
+            insert_markers(&mut entity, &item.markers);
+            children.push(entity.id());
+        }
+
+        //doors
+        for (index, door) in description.doors.iter().enumerate() {
+            let mut entity = self.commands.spawn((
+                PbrBundle {
+                    mesh: self.meshes.add(door.mesh()),
+                    material: self
+                        .standard_materials
+                        .add(Color::rgb(0.3, 0.25, 0.2).into()),
+                    transform: (&door.transform).into(),
+                    ..Default::default()
+                },
+                PlayerTargetSet,
+                SceneItemId::new(name, "doors", index),
+            ));
+            Marker::Door(door.door()).insert(&mut entity);
+            insert_markers(&mut entity, &door.markers);
+            children.push(entity.id());
+        }
+        //pickups
+        for (index, pickup) in description.pickups.iter().enumerate() {
+            let entity = self.commands.spawn((
+                PbrBundle {
+                    mesh: self.meshes.add(pickup.mesh()),
+                    material: self
+                        .standard_materials
+                        .add(Color::rgb(0.8, 0.7, 0.3).into()),
+                    transform: (&pickup.transform).into(),
+                    ..Default::default()
+                },
+                Item(pickup.item.clone()),
+                Interactable::new(Verb::PickUp, &pickup.prompt),
+                PlayerTargetSet,
+                SceneItemId::new(name, "pickups", index),
+            ));
+            children.push(entity.id());
+        }
+
+        if let Some(spawn) = &description.cryptid_spawn {
+            let entity = self
+                .commands
+                .spawn((SpatialBundle::from_transform(spawn.into()), CryptidSpawn));
+            children.push(entity.id());
+        }
+
+        self.commands
+            .spawn((
+                SpatialBundle::from_transform(transform),
+                SceneRoot(name.into()),
+            ))
+            .push_children(&children)
+            .id()
+    }
+}
@@ -3,9 +3,10 @@ use std::collections::HashMap;
 use bevy::{
     pbr::{MaterialPipeline, MaterialPipelineKey, PBR_PREPASS_SHADER_HANDLE},
     prelude::{
-        AlphaMode, App, AssetServer, Assets, Camera, Handle, Image, Material, MaterialMeshBundle,
-        MaterialPlugin, Mesh, ParallaxMappingMethod, Plugin, PostUpdate, PreStartup, Query,
-        ReflectDefault, ResMut, Resource, Transform, Visibility, With,
+        AlphaMode, App, AssetServer, Assets, Camera, GlobalTransform, Handle, Image,
+        IntoSystemConfigs, Material, MaterialMeshBundle, MaterialPlugin, Mesh,
+        ParallaxMappingMethod, Plugin, PostUpdate, PreStartup, Query, ReflectDefault, Res, ResMut,
+        Resource, Transform, Visibility, With,
     },
     reflect::{Reflect, TypeUuid},
     render::{
@@ -16,6 +17,8 @@ use bevy::{
     },
 };
 
+use super::catalog::{build_asset_catalog, AssetCatalog, CatalogKind};
+
 #[derive(Resource)]
 pub struct Walls(pub HashMap<String, Wall>);
 
@@ -44,7 +47,7 @@ pub fn into_mesh_bundle(
     }
 }
 
-fn load_wall(asset_server: &ResMut<AssetServer>, dir: &'static str) -> Wall {
+fn load_wall(asset_server: &ResMut<AssetServer>, dir: &str) -> Wall {
     Wall {
         mesh: asset_server.load(format!("scenes/{dir}/mesh/mesh.glb#Mesh0/Primitive0")),
         material: WallMaterial {
@@ -56,28 +59,22 @@ fn load_wall(asset_server: &ResMut<AssetServer>, dir: &'static str) -> Wall {
     }
 }
 
-pub fn load_walls(mut walls: ResMut<Walls>, asset_server: ResMut<AssetServer>) {
-    walls.as_mut().0.insert(
-        "dev_playground/wall_1".into(),
-        load_wall(&asset_server, "dev_playground/wall_1"),
-    );
-    walls.as_mut().0.insert(
-        "dev_playground/wall_2".into(),
-        load_wall(&asset_server, "dev_playground/wall_2"),
-    );
-    walls.as_mut().0.insert(
-        "dev_playground/wall_3".into(),
-        load_wall(&asset_server, "dev_playground/wall_3"),
-    );
-    walls.as_mut().0.insert(
-        "dev_playground/wall_4".into(),
-        load_wall(&asset_server, "dev_playground/wall_4"),
-    );
+pub fn load_walls(
+    mut walls: ResMut<Walls>,
+    catalog: Res<AssetCatalog>,
+    asset_server: ResMut<AssetServer>,
+) {
+    for key in catalog.keys(CatalogKind::Wall) {
+        walls
+            .as_mut()
+            .0
+            .insert(key.into(), load_wall(&asset_server, key));
+    }
 }
 
 pub fn render_wall(
     camera_query: Query<&Transform, With<Camera>>,
-    mut wall_query: Query<(&mut Visibility, &Transform), With<Handle<WallMaterial>>>,
+    mut wall_query: Query<(&mut Visibility, &GlobalTransform), With<Handle<WallMaterial>>>,
 ) {
     // get camera dir vector
     let camera_iter = camera_query.iter().next();
@@ -109,7 +106,7 @@ impl Plugin for WallPlugin {
 
         app.insert_resource(walls)
             .add_plugins((MaterialPlugin::<WallMaterial>::default(),))
-            .add_systems(PreStartup, load_walls)
+            .add_systems(PreStartup, load_walls.after(build_asset_catalog))
             .add_systems(PostUpdate, render_wall);
     }
 }